@@ -104,6 +104,30 @@ fn url(c: &mut Criterion) {
 
 
 
+//--------------
+// Benchmarking request parsing followed by URL parsing
+//--------------
+
+
+
+const REQ_QUERY: &[u8] = b"GET /path/path.html/user?query1=value&query2=value&query3=value HTTP/1.1\r\n\
+Host: www.kittyhell.com\r\n\
+Accept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\n\
+Connection: keep-alive\r\n\r\n";
+
+fn req_url(c: &mut Criterion) {
+  c.benchmark_group("req_url")
+  .bench_function("req_url", |b| b.iter_batched_ref(|| {
+      ([htpp::EMPTY_HEADER; 20], [htpp::EMPTY_QUERY; 10])
+  },|(headers, queries)| {
+    let req = htpp::Request::parse(REQ_QUERY, headers).unwrap();
+    black_box(htpp::Url::parse(req.path.as_bytes(), queries).unwrap());
+  }, criterion::BatchSize::SmallInput));
+}
+
+
+
+
 //--------------
 // Running the benchmarks
 //--------------
@@ -116,6 +140,6 @@ const SAMPLES: usize = 200;
 criterion_group!{
     name = benches;
     config = Criterion::default().sample_size(SAMPLES).warm_up_time(WARMUP).measurement_time(MTIME);
-    targets = req, req_short, resp, resp_short, url
+    targets = req, req_short, resp, resp_short, url, req_url
 }
 criterion_main!(benches);
\ No newline at end of file
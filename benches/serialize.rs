@@ -0,0 +1,97 @@
+use std::time::Duration;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const REQ: &[u8] = b"GET /wp-content/uploads/pink.jpg HTTP/1.1\r\n\
+Host: www.kittyhell.com\r\n\
+User-Agent: Mozilla/5.0 (Macintosh; U; Intel Mac OS X 10.6; ja-JP-mac; rv:1.9.2.3) Gecko/20100401 Firefox/3.6.3 Pathtraq/0.9\r\n\
+Accept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\n\
+Accept-Language: ja,en-us;q=0.7,en;q=0.3\r\n\
+Accept-Encoding: gzip,deflate\r\n\
+Accept-Charset: Shift_JIS,utf-8;q=0.7,*;q=0.7\r\n\
+Keep-Alive: 115\r\n\
+Connection: keep-alive\r\n\
+Cookie: wp_ozh_wsa_visits=2; wp_ozh_wsa_visit_lasttime=xxxxxxxxxx; __utma=xxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.x; __utmz=xxxxxxxxx.xxxxxxxxxx.x.x.utmccn=(referral)|utmcsr=reader.livedoor.com|utmcct=/reader/|utmcmd=referral|padding=under256\r\n\r\n";
+
+// These particular headers don't all make semantic sense for a response, but they're syntactically valid
+const RESP: &[u8] = b"HTTP/1.1 200 OK\r\n\
+Date: Wed, 21 Oct 2015 07:28:00 GMT\r\n\
+Host: www.kittyhell.com\r\n\
+User-Agent: Mozilla/5.0 (Macintosh; U; Intel Mac OS X 10.6; ja-JP-mac; rv:1.9.2.3) Gecko/20100401 Firefox/3.6.3 Pathtraq/0.9\r\n\
+Accept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\n\
+Accept-Language: ja,en-us;q=0.7,en;q=0.3\r\n\
+Accept-Encoding: gzip,deflate\r\n\
+Accept-Charset: Shift_JIS,utf-8;q=0.7,*;q=0.7\r\n\
+Keep-Alive: 115\r\n\
+Connection: keep-alive\r\n\
+Cookie: wp_ozh_wsa_visits=2; wp_ozh_wsa_visit_lasttime=xxxxxxxxxx; __utma=xxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.x; __utmz=xxxxxxxxx.xxxxxxxxxx.x.x.utmccn=(referral)|utmcsr=reader.livedoor.com|utmcct=/reader/|utmcmd=referral|padding=under256\r\n\r\n";
+
+
+
+
+//--------------
+// Benchmarking request serialization
+//--------------
+
+
+
+fn req_as_bytes(c: &mut Criterion) {
+  let mut headers = [htpp::EMPTY_HEADER; 20];
+  let req = htpp::Request::parse(REQ, &mut headers).unwrap();
+  c.benchmark_group("req_as_bytes")
+  .bench_function("req_as_bytes", |b| b.iter(|| {
+    black_box(req.as_bytes());
+  }));
+}
+
+fn req_new(c: &mut Criterion) {
+  let headers = [htpp::Header::new("Host", b"www.kittyhell.com"), htpp::Header::new("Accept", b"*/*")];
+  c.benchmark_group("req_new")
+  .bench_function("req_new", |b| b.iter(|| {
+    black_box(htpp::Request::new(htpp::Method::Get, black_box("/wp-content/uploads/pink.jpg"), &headers, b"").as_bytes());
+  }));
+}
+
+
+
+
+//--------------
+// Benchmarking response serialization
+//--------------
+
+
+
+fn resp_as_bytes(c: &mut Criterion) {
+  let mut headers = [htpp::EMPTY_HEADER; 20];
+  let resp = htpp::Response::parse(RESP, &mut headers).unwrap();
+  c.benchmark_group("resp_as_bytes")
+  .bench_function("resp_as_bytes", |b| b.iter(|| {
+    black_box(resp.as_bytes());
+  }));
+}
+
+fn resp_new(c: &mut Criterion) {
+  let headers = [htpp::Header::new("Content-Length", b"0"), htpp::Header::new("Connection", b"keep-alive")];
+  c.benchmark_group("resp_new")
+  .bench_function("resp_new", |b| b.iter(|| {
+    black_box(htpp::Response::new(black_box(200), "OK", &headers, b"").as_bytes());
+  }));
+}
+
+
+
+
+//--------------
+// Running the benchmarks
+//--------------
+
+
+
+const WARMUP: Duration = Duration::from_millis(100);
+const MTIME: Duration = Duration::from_millis(100);
+const SAMPLES: usize = 200;
+criterion_group!{
+    name = benches;
+    config = Criterion::default().sample_size(SAMPLES).warm_up_time(WARMUP).measurement_time(MTIME);
+    targets = req_as_bytes, req_new, resp_as_bytes, resp_new
+}
+criterion_main!(benches);
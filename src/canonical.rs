@@ -0,0 +1,128 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Canonical forms of the query and headers of a request, for request signing and cache keys
+//!
+//! The output is byte-compatible with the `CanonicalQueryString` and `CanonicalHeaders` components of an AWS Signature Version 4 canonical request.
+//!
+//! Names and values are first percent-decoded, then encoded with the strict RFC 3986 set:
+//!
+//! | Bytes                                               | Encoded as                      |
+//! |-----------------------------------------------------|---------------------------------|
+//! | `A`-`Z`, `a`-`z`, `0`-`9`, `-`, `.`, `_`, `~`       | themselves                      |
+//! | any other byte, including space, `*`, `+`, and `%`  | `%XX` with uppercase hex digits |
+//!
+//! This differs from form encoding, which writes a space as `+`, leaves `*` as is, and encodes `~`.
+//! A `+` in the query is taken literally and encoded as `%2B`
+//!
+//! ```rust
+//! use htpp::{QueryParam, canonical::{sort_query, canonical_query_string}};
+//!
+//! let mut params = [QueryParam::new("b", "2"), QueryParam::new("a", "x y"), QueryParam::new("a", "*")];
+//! sort_query(&mut params);
+//! let mut out = Vec::new();
+//! canonical_query_string(&params, &mut out);
+//! assert!(out == b"a=%2A&a=x%20y&b=2");
+//! ```
+
+use std::cmp::Ordering;
+
+use crate::uri::PercentDecoded;
+use crate::{Header, QueryParam};
+
+
+/// Sorts the query parameters by their encoded name, then by their encoded value, as `canonical_query_string` expects them.
+/// Empty parameters such as the `EMPTY_QUERY` padding of a buffer are sorted to the start, and skipped by `canonical_query_string`
+#[inline]
+pub fn sort_query(params: &mut [QueryParam]) {
+  params.sort_by(|a, b| {
+    encoded(a.name).cmp(encoded(b.name)).then_with(|| encoded(a.val).cmp(encoded(b.val)))
+  });
+}
+
+/// Writes the parameters as `name=value` pairs joined with `&`, with names and values encoded as described in the module documentation.
+/// The parameters are written in the order they are passed, sort them with `sort_query` first. Parameters with an empty name are skipped
+#[inline]
+pub fn canonical_query_string(params: &[QueryParam], out: &mut Vec<u8>) {
+  for (index, param) in params.iter().filter(|param| !param.name.is_empty()).enumerate() {
+    if index > 0 {out.push(b'&');}
+    out.extend(encoded(param.name));
+    out.push(b'=');
+    out.extend(encoded(param.val));
+  }
+}
+
+/// Sorts the headers by case-insensitive name and writes a `name:value\n` line for every name.
+/// Names are lowercased, values are trimmed and runs of spaces inside them are collapsed to one space.
+/// The values of headers with the same name are joined with `,` in the order they were sent. Headers with an empty name are skipped
+///
+/// ```rust
+/// use htpp::{Header, canonical::canonical_headers};
+///
+/// let mut headers = [Header::new("X-Amz-Date", b"20150830T123600Z"), Header::new("Host", b" example.amazonaws.com ")];
+/// let mut out = Vec::new();
+/// canonical_headers(&mut headers, &mut out);
+/// assert!(out == b"host:example.amazonaws.com\nx-amz-date:20150830T123600Z\n");
+/// ```
+#[inline]
+pub fn canonical_headers(headers: &mut [Header], out: &mut Vec<u8>) {
+  // a stable sort keeps the values of repeated headers in the order they were sent
+  headers.sort_by(|a, b| compare_ignore_case(a.name, b.name));
+  let mut previous: Option<&str> = None;
+  for header in headers.iter().filter(|header| !header.name.is_empty()) {
+    match previous {
+      Some(name) if name.eq_ignore_ascii_case(header.name) => {
+        out.pop();
+        out.push(b',');
+      },
+      _ => {
+        out.extend(header.name.bytes().map(|character| character.to_ascii_lowercase()));
+        out.push(b':');
+      },
+    }
+    write_collapsed(header.val.trim_ascii(), out);
+    out.push(b'\n');
+    previous = Some(header.name);
+  }
+}
+
+
+#[inline]
+fn compare_ignore_case(a: &str, b: &str) -> Ordering {
+  a.bytes().map(|character| character.to_ascii_lowercase()).cmp(b.bytes().map(|character| character.to_ascii_lowercase()))
+}
+
+#[inline]
+// writes the value with every run of spaces replaced by a single space
+fn write_collapsed(val: &[u8], out: &mut Vec<u8>) {
+  let mut space = false;
+  for character in val {
+    if *character == b' ' {
+      if space {continue;}
+      space = true;
+    } else {
+      space = false;
+    }
+    out.push(*character);
+  }
+}
+
+#[inline]
+// the bytes of the string decoded and encoded again with the strict set, without allocating
+fn encoded(val: &str) -> impl Iterator<Item = u8> + '_ {
+  PercentDecoded::new(val.as_bytes()).flat_map(encode_byte)
+}
+
+#[inline]
+fn encode_byte(character: u8) -> impl Iterator<Item = u8> {
+  const HEX: &[u8; 16] = b"0123456789ABCDEF";
+  if character.is_ascii_alphanumeric() || b"-._~".contains(&character) {
+    [character, 0, 0].into_iter().take(1)
+  } else {
+    [b'%', HEX[(character >> 4) as usize], HEX[(character & 0xf) as usize]].into_iter().take(3)
+  }
+}
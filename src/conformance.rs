@@ -0,0 +1,102 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Checks of the laws parsers and serializers should obey, for the tests of this crate and of crates embedding its parsers.
+//! Enabled by the `test-util` feature
+//!
+//! Every check panics with a hex dump of both sides around the first differing byte when the law doesn't hold
+//!
+//! ```rust
+//! use htpp::{Request, EMPTY_HEADER, conformance::assert_roundtrip_parse_serialize};
+//!
+//! let mut headers = [EMPTY_HEADER; 10];
+//! assert_roundtrip_parse_serialize(
+//!   b"GET / HTTP/1.1\r\nHost: a.com\r\n\r\n",
+//!   |input| Request::parse(input, &mut headers),
+//!   |req| req.as_bytes(),
+//! );
+//! ```
+
+use std::fmt::{self, Debug, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::text::{hex_dump, HEX_ROW};
+
+
+/// Asserts that parsing the input and serializing the result gives back the input byte for byte, for inputs already in canonical form
+#[track_caller]
+#[inline]
+pub fn assert_roundtrip_parse_serialize<'i, T, E: Debug>(
+  input: &'i [u8],
+  parse: impl FnOnce(&'i [u8]) -> Result<T, E>,
+  serialize: impl FnOnce(&T) -> Vec<u8>,
+) {
+  let parsed = match parse(input) {
+    Ok(parsed) => parsed,
+    Err(err) => panic!("parsing failed with {err:?}\ninput:\n{}", hex_rows(input, 0, input.len())),
+  };
+  let serialized = serialize(&parsed);
+  if serialized != input {
+    panic!("serializing the parsed input doesn't give it back\n{}", divergence(input, &serialized));
+  }
+}
+
+/// Asserts that normalizing the input twice gives the same bytes as normalizing it once
+#[track_caller]
+#[inline]
+pub fn assert_idempotent_normalization<E: Debug>(input: &[u8], normalize: impl Fn(&[u8]) -> Result<Vec<u8>, E>) {
+  let once = match normalize(input) {
+    Ok(once) => once,
+    Err(err) => panic!("normalizing failed with {err:?}\ninput:\n{}", hex_rows(input, 0, input.len())),
+  };
+  let twice = match normalize(&once) {
+    Ok(twice) => twice,
+    Err(err) => panic!("normalizing the normalized input failed with {err:?}\nnormalized:\n{}", hex_rows(&once, 0, once.len())),
+  };
+  if twice != once {
+    panic!("normalizing the normalized input changes it\n{}", divergence(&once, &twice));
+  }
+}
+
+/// Asserts that parsing any prefix of the input, from the empty slice to the whole input, doesn't panic
+#[track_caller]
+#[inline]
+pub fn assert_never_panics_on_prefixes(input: &[u8], mut parse: impl FnMut(&[u8])) {
+  for len in 0..=input.len() {
+    if catch_unwind(AssertUnwindSafe(|| parse(&input[..len]))).is_err() {
+      panic!("parsing panicked on the prefix of {len} bytes\nprefix:\n{}", hex_rows(input, 0, len));
+    }
+  }
+}
+
+
+// the bytes shown before the first differing byte
+const CONTEXT: usize = 32;
+
+#[inline]
+// describes where two byte strings first differ with a hex dump of both around that byte
+fn divergence(expected: &[u8], actual: &[u8]) -> String {
+  let offset = expected.iter().zip(actual).position(|(a, b)| a != b).unwrap_or(expected.len().min(actual.len()));
+  let start = offset.saturating_sub(CONTEXT) / HEX_ROW * HEX_ROW;
+  let end = offset + CONTEXT;
+  format!(
+    "first difference at byte {offset}, expected {} bytes and got {}\nexpected:\n{}actual:\n{}",
+    expected.len(),
+    actual.len(),
+    hex_rows(expected, start, end.min(expected.len())),
+    hex_rows(actual, start, end.min(actual.len())),
+  )
+}
+
+#[inline]
+// the hex dump of the bytes from `start` to `end` with their offsets in `bytes`
+fn hex_rows(bytes: &[u8], start: usize, end: usize) -> String {
+  if start >= end {return "  (no bytes)\n".to_string();}
+  let mut out = String::new();
+  let _ = hex_dump(&bytes[start..end], start, "  ", &mut out);
+  out
+}
@@ -0,0 +1,179 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Parsing of the `Content-Disposition` header (RFC 6266) including RFC 8187 encoded `filename*` parameters
+//!
+//! ```rust
+//! use htpp::{Response, EMPTY_HEADER};
+//!
+//! let res = b"HTTP/1.1 200 OK\r\nContent-Disposition: attachment; filename=\"report.pdf\"\r\n\r\n";
+//! let mut headers = [EMPTY_HEADER; 10];
+//! let parsed = Response::parse(res, &mut headers).unwrap();
+//! let disposition = parsed.content_disposition().unwrap();
+//! assert!(disposition.is_attachment());
+//! assert!(disposition.filename().unwrap() == "report.pdf");
+//! ```
+
+use std::borrow::Cow;
+use std::{fmt, str};
+
+use crate::uri::{percent_byte, PercentDecoded};
+use crate::{Error, Result};
+
+
+/// A parsed `Content-Disposition` header value
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct ContentDisposition<'a> {
+    /// The disposition type such as `inline`, `attachment`, or `form-data`, as sent
+    pub disposition: &'a str,
+    // the parameters after the disposition type, already validated
+    params: &'a str,
+}
+impl<'a> ContentDisposition<'a> {
+  /// Parses a `Content-Disposition` header value such as `attachment; filename="x.txt"`
+  /// Returns an Err(Error::Malformed) if the disposition type isn't a token or a parameter isn't a `name=value` pair
+  /// with a token or quoted string value
+  #[inline]
+  pub fn parse(val: &'a [u8]) -> Result<ContentDisposition<'a>> {
+    let val = str::from_utf8(val).map_err(|_| Error::Malformed)?.trim_matches(is_ows);
+    let end = val.find(';').unwrap_or(val.len());
+    let disposition = val[..end].trim_end_matches(is_ows);
+    if disposition.is_empty() || !disposition.bytes().all(is_tchar) {return Err(Error::Malformed);}
+    let params = &val[end..];
+    let mut rest = params;
+    while let Some((_, next)) = next_param(rest)? {
+      rest = next;
+    }
+    Ok(ContentDisposition { disposition, params })
+  }
+
+  /// Whether the disposition type is `attachment`, compared case-insensitively
+  #[inline]
+  pub fn is_attachment(&self) -> bool {
+    self.disposition.eq_ignore_ascii_case("attachment")
+  }
+
+  /// Whether the disposition type is `inline`, compared case-insensitively
+  #[inline]
+  pub fn is_inline(&self) -> bool {
+    self.disposition.eq_ignore_ascii_case("inline")
+  }
+
+  /// The value of the first parameter with the given case-insensitive name, unquoted and unescaped
+  /// Values of parameters whose name ends with `*` are decoded as RFC 8187 extended values, `None` is returned
+  /// if they can't be decoded, only the `UTF-8` and `ISO-8859-1` charsets are supported
+  #[inline]
+  pub fn param(&self, name: &str) -> Option<Cow<'a, str>> {
+    let mut rest = self.params;
+    while let Ok(Some(((param, val), next))) = next_param(rest) {
+      if param.eq_ignore_ascii_case(name) {
+        return if name.ends_with('*') {decode_ext_value(val)} else {Some(unquote(val))};
+      }
+      rest = next;
+    }
+    None
+  }
+
+  /// The suggested file name, the `filename*` parameter is preferred over `filename` when it can be decoded
+  #[inline]
+  pub fn filename(&self) -> Option<Cow<'a, str>> {
+    self.param("filename*").or_else(|| self.param("filename"))
+  }
+}
+#[cfg(feature = "fmt")]
+impl<'a> fmt::Display for ContentDisposition<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.disposition, self.params)
+    }
+}
+
+
+#[inline]
+fn is_ows(character: char) -> bool {
+  character == ' ' || character == '\t'
+}
+
+#[inline]
+// whether the byte is allowed in a token
+fn is_tchar(character: u8) -> bool {
+  character.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&character)
+}
+
+#[inline]
+// parses the `; name=value` parameter at the start of the slice, returns the name, the raw value, and the rest of the slice
+// returns `None` once only whitespace is left
+fn next_param(slice: &str) -> Result<Option<((&str, &str), &str)>> {
+  let slice = slice.trim_start_matches(is_ows);
+  if slice.is_empty() {return Ok(None);}
+  let slice = slice.strip_prefix(';').ok_or(Error::Malformed)?.trim_start_matches(is_ows);
+  let (name, rest) = slice.split_once('=').ok_or(Error::Malformed)?;
+  let name = name.trim_end_matches(is_ows);
+  if name.is_empty() || !name.bytes().all(is_tchar) {return Err(Error::Malformed);}
+  let rest = rest.trim_start_matches(is_ows);
+  let len = if rest.starts_with('"') {
+    quoted_len(rest)?
+  } else {
+    rest.bytes().position(|character| !is_tchar(character)).unwrap_or(rest.len())
+  };
+  if len == 0 {return Err(Error::Malformed);}
+  Ok(Some(((name, &rest[..len]), &rest[len..])))
+}
+
+#[inline]
+// the length of the quoted string at the start of the slice including both quotes
+fn quoted_len(slice: &str) -> Result<usize> {
+  let mut escaped = false;
+  for (counter, character) in slice.bytes().enumerate().skip(1) {
+    match character {
+      _ if escaped => escaped = false,
+      b'\\' => escaped = true,
+      b'"' => return Ok(counter + 1),
+      _ if character.is_ascii_control() && character != b'\t' => return Err(Error::Malformed),
+      _ => {},
+    }
+  }
+  Err(Error::Malformed)
+}
+
+#[inline]
+// removes the quotes and escapes of a quoted string, tokens are returned as is
+fn unquote(val: &str) -> Cow<'_, str> {
+  let Some(inner) = val.strip_prefix('"').and_then(|val| val.strip_suffix('"')) else {
+    return Cow::Borrowed(val);
+  };
+  if !inner.contains('\\') {return Cow::Borrowed(inner);}
+  let mut unquoted = String::with_capacity(inner.len());
+  let mut escaped = false;
+  for character in inner.chars() {
+    if character == '\\' && !escaped {
+      escaped = true;
+      continue;
+    }
+    escaped = false;
+    unquoted.push(character);
+  }
+  Cow::Owned(unquoted)
+}
+
+#[inline]
+// decodes an RFC 8187 ext-value such as `UTF-8''%e2%82%ac%20rates`
+fn decode_ext_value(val: &str) -> Option<Cow<'_, str>> {
+  let mut parts = val.splitn(3, '\'');
+  let charset = parts.next()?;
+  let _language = parts.next()?;
+  let encoded = parts.next()?.as_bytes();
+  // unlike in a URL, a `%` must start a `%HH` sequence
+  if encoded.iter().enumerate().any(|(offset, character)| *character == b'%' && percent_byte(encoded, offset).is_none()) {return None;}
+  let decoded: Vec<u8> = PercentDecoded::new(encoded).collect();
+  if charset.eq_ignore_ascii_case("UTF-8") {
+    String::from_utf8(decoded).ok().map(Cow::Owned)
+  } else if charset.eq_ignore_ascii_case("ISO-8859-1") {
+    Some(Cow::Owned(decoded.iter().map(|byte| *byte as char).collect()))
+  } else {
+    None
+  }
+}
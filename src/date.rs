@@ -0,0 +1,85 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Overflow-safe arithmetic on the timestamps of HTTP dates, for targets without a wall clock
+//!
+//! Nothing here reads the current time, helpers take `now` as an `HttpDate` built by the caller.
+//! A date is a whole second between `HttpDate::MIN`, 1900-01-01 00:00:00 UTC, and `HttpDate::MAX`, 9999-12-31 23:59:59 UTC,
+//! the years an IMF-fixdate can hold. Arithmetic leaving that range fails or saturates instead of wrapping
+//!
+//! ```rust
+//! use htpp::date::HttpDate;
+//!
+//! let date = HttpDate::from_unix_secs(1445412480).unwrap();
+//! assert!(date.checked_add_secs(60).unwrap().duration_since(date) == Some(60));
+//! assert!(HttpDate::MAX.checked_add_secs(u32::MAX as u64).is_none());
+//! assert!(HttpDate::MAX.saturating_add_secs(u64::MAX) == HttpDate::MAX);
+//! ```
+
+
+// the seconds from 1900-01-01 to the unix epoch and from 1900-01-01 to 9999-12-31 23:59:59
+const UNIX_EPOCH: u64 = 2_208_988_800;
+const LAST_SECOND: u64 = 255_611_289_599;
+
+/// A point in time with a resolution of one second, between `HttpDate::MIN` and `HttpDate::MAX`
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct HttpDate {
+    // seconds since 1900-01-01 00:00:00 UTC
+    secs: u64,
+}
+impl HttpDate {
+  /// The earliest date, 1900-01-01 00:00:00 UTC
+  pub const MIN: HttpDate = HttpDate { secs: 0 };
+  /// The latest date, 9999-12-31 23:59:59 UTC
+  pub const MAX: HttpDate = HttpDate { secs: LAST_SECOND };
+
+  /// The date the given number of seconds after the unix epoch, negative for dates before 1970.
+  /// Returns `None` if the date is before `HttpDate::MIN` or after `HttpDate::MAX`
+  #[inline]
+  pub fn from_unix_secs(secs: i64) -> Option<HttpDate> {
+    let secs = secs.checked_add(UNIX_EPOCH as i64)?;
+    if !(0..=LAST_SECOND as i64).contains(&secs) {return None;}
+    Some(HttpDate { secs: secs as u64 })
+  }
+
+  /// The number of seconds from the unix epoch to the date, negative for dates before 1970
+  #[inline]
+  pub fn unix_secs(&self) -> i64 {
+    self.secs as i64 - UNIX_EPOCH as i64
+  }
+
+  /// The date `secs` seconds later, `None` if it is after `HttpDate::MAX`
+  #[inline]
+  pub fn checked_add_secs(&self, secs: u64) -> Option<HttpDate> {
+    let secs = self.secs.checked_add(secs).filter(|secs| *secs <= LAST_SECOND)?;
+    Some(HttpDate { secs })
+  }
+
+  /// The date `secs` seconds earlier, `None` if it is before `HttpDate::MIN`
+  #[inline]
+  pub fn checked_sub(&self, secs: u64) -> Option<HttpDate> {
+    Some(HttpDate { secs: self.secs.checked_sub(secs)? })
+  }
+
+  /// The date `secs` seconds later, `HttpDate::MAX` if it is after it
+  #[inline]
+  pub fn saturating_add_secs(&self, secs: u64) -> HttpDate {
+    self.checked_add_secs(secs).unwrap_or(HttpDate::MAX)
+  }
+
+  /// The date `secs` seconds earlier, `HttpDate::MIN` if it is before it
+  #[inline]
+  pub fn saturating_sub(&self, secs: u64) -> HttpDate {
+    self.checked_sub(secs).unwrap_or(HttpDate::MIN)
+  }
+
+  /// The number of seconds from `earlier` to this date, `None` if `earlier` is later than this date
+  #[inline]
+  pub fn duration_since(&self, earlier: HttpDate) -> Option<u64> {
+    self.secs.checked_sub(earlier.secs)
+  }
+}
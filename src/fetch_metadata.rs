@@ -0,0 +1,210 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Typed access to the `Sec-Fetch-*` and `Early-Data` request headers
+//!
+//! ```rust
+//! use htpp::{Request, EMPTY_HEADER, fetch_metadata::{self, Site, Policy}};
+//!
+//! let req = b"GET / HTTP/1.1\r\nSec-Fetch-Site: cross-site\r\nSec-Fetch-Mode: no-cors\r\nSec-Fetch-Dest: image\r\n\r\n";
+//! let mut headers = [EMPTY_HEADER; 10];
+//! let parsed = Request::parse(req, &mut headers).unwrap();
+//! let metadata = fetch_metadata::parse(&parsed);
+//! assert!(metadata.site == Site::CrossSite);
+//! assert!(!metadata.is_allowed(&Policy::default()));
+//! ```
+
+use std::fmt;
+
+use crate::{find_header, Header, Method, Request};
+
+
+macro_rules! token_enum {
+  ($(#[$doc:meta])* $name:ident { $($(#[$variant_doc:meta])* $variant:ident => $token:literal,)* }) => {
+    $(#[$doc])*
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+    pub enum $name<'a> {
+      $($(#[$variant_doc])* $variant,)*
+      /// The header is missing from the request
+      Unknown,
+      /// The header holds a token this crate doesn't know about
+      Other(&'a str),
+    }
+    impl<'a> $name<'a> {
+      #[inline]
+      fn from_header(header: Option<&Header<'a>>) -> Self {
+        let token = match header.and_then(|header| std::str::from_utf8(header.val).ok()) {
+          Some(token) => token.trim_matches(|c| c == ' ' || c == '\t'),
+          None => return Self::Unknown,
+        };
+        match token {
+          $($token => Self::$variant,)*
+          _ => Self::Other(token),
+        }
+      }
+    }
+    #[cfg(feature = "fmt")]
+    impl<'a> fmt::Display for $name<'a> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let token = match self {
+                $(Self::$variant => $token,)*
+                Self::Unknown => "",
+                Self::Other(token) => token,
+            };
+            f.write_str(token)
+        }
+    }
+  };
+}
+
+token_enum! {
+  /// The value of the `Sec-Fetch-Site` header
+  Site {
+    /// `same-origin`
+    SameOrigin => "same-origin",
+    /// `same-site`
+    SameSite => "same-site",
+    /// `cross-site`
+    CrossSite => "cross-site",
+    /// `none`, the request was initiated by the user rather than a site
+    None => "none",
+  }
+}
+
+token_enum! {
+  /// The value of the `Sec-Fetch-Mode` header
+  Mode {
+    /// `cors`
+    Cors => "cors",
+    /// `navigate`
+    Navigate => "navigate",
+    /// `no-cors`
+    NoCors => "no-cors",
+    /// `same-origin`
+    SameOrigin => "same-origin",
+    /// `websocket`
+    Websocket => "websocket",
+  }
+}
+
+token_enum! {
+  /// The value of the `Sec-Fetch-Dest` header
+  Dest {
+    /// `audio`
+    Audio => "audio",
+    /// `audioworklet`
+    AudioWorklet => "audioworklet",
+    /// `document`
+    Document => "document",
+    /// `embed`
+    Embed => "embed",
+    /// `empty`
+    Empty => "empty",
+    /// `font`
+    Font => "font",
+    /// `frame`
+    Frame => "frame",
+    /// `iframe`
+    Iframe => "iframe",
+    /// `image`
+    Image => "image",
+    /// `manifest`
+    Manifest => "manifest",
+    /// `object`
+    Object => "object",
+    /// `paintworklet`
+    PaintWorklet => "paintworklet",
+    /// `report`
+    Report => "report",
+    /// `script`
+    Script => "script",
+    /// `serviceworker`
+    ServiceWorker => "serviceworker",
+    /// `sharedworker`
+    SharedWorker => "sharedworker",
+    /// `style`
+    Style => "style",
+    /// `track`
+    Track => "track",
+    /// `video`
+    Video => "video",
+    /// `worker`
+    Worker => "worker",
+    /// `xslt`
+    Xslt => "xslt",
+  }
+}
+
+
+/// The fetch metadata of a request
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FetchMetadata<'a> {
+    /// The relationship between the initiator of the request and its target
+    pub site: Site<'a>,
+    /// The mode of the request
+    pub mode: Mode<'a>,
+    /// The destination of the request
+    pub dest: Dest<'a>,
+    /// Whether the request was triggered by user activation, `Sec-Fetch-User: ?1`
+    pub user: bool,
+    /// Whether the request was sent in TLS 1.3 early data, `Early-Data: 1`
+    pub early_data: bool,
+    method: Method<'a>,
+}
+
+/// The options of the resource isolation policy applied by [FetchMetadata::is_allowed]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Policy {
+    /// Allow cross-site navigations with a safe method such as `GET` so links from other sites keep working
+    pub allow_navigations: bool,
+    /// Deny requests sent in early data unless their method is safe to replay
+    pub reject_early_data: bool,
+}
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            allow_navigations: true,
+            reject_early_data: true,
+        }
+    }
+}
+
+impl<'a> FetchMetadata<'a> {
+  /// Applies the resource isolation policy to the request
+  /// Requests from browsers that don't send fetch metadata, same-site requests, and requests initiated by the user are allowed.
+  /// Cross-site requests are denied unless they are navigations with a safe method such as `GET` or `HEAD` to anything but an `<object>` or `<embed>`
+  /// and the policy allows navigations. Requests in early data are denied unless their method is safe, see `Method::is_safe`
+  #[inline]
+  pub fn is_allowed(&self, policy: &Policy) -> bool {
+    if policy.reject_early_data && self.early_data && !self.method.is_safe() {
+      return false;
+    }
+    match self.site {
+      Site::Unknown | Site::SameOrigin | Site::SameSite | Site::None => true,
+      _ => {
+        policy.allow_navigations
+          && self.mode == Mode::Navigate
+          && self.method.is_safe()
+          && !matches!(self.dest, Dest::Object | Dest::Embed)
+      }
+    }
+  }
+}
+
+/// Extracts the fetch metadata of a request
+/// Missing headers are reported as `Unknown` and unrecognized tokens are preserved in the `Other` variants
+#[inline]
+pub fn parse<'a>(req: &Request<'a, '_>) -> FetchMetadata<'a> {
+  FetchMetadata {
+    site: Site::from_header(find_header(req.headers, "Sec-Fetch-Site")),
+    mode: Mode::from_header(find_header(req.headers, "Sec-Fetch-Mode")),
+    dest: Dest::from_header(find_header(req.headers, "Sec-Fetch-Dest")),
+    user: find_header(req.headers, "Sec-Fetch-User").is_some_and(|header| header.val == b"?1"),
+    early_data: find_header(req.headers, "Early-Data").is_some_and(|header| header.val == b"1"),
+    method: req.method.clone(),
+  }
+}
@@ -0,0 +1,178 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! A C ABI over the request parser, enabled by the `ffi` feature
+//!
+//! `htpp_parse_request` parses a buffer into caller-provided C structs. Nothing is copied or allocated for the caller:
+//! every pointer written points into the parsed buffer, or into static memory for the method name, and stays valid as long as the buffer does.
+//! The strings are pointer and length pairs and are NOT null-terminated, the buffer holds them back to back.
+//! Build a C library from the crate with `cargo rustc --release --features ffi --crate-type staticlib` or `cdylib`
+//!
+//! ```c
+//! htpp_header headers[32];
+//! htpp_request req;
+//! int32_t status = htpp_parse_request(buf, len, &req, headers, 32);
+//! if (status == 0) printf("%.*s\n", (int)req.path_len, (const char *)req.path);
+//! ```
+
+use std::slice;
+
+use crate::{head_incomplete, Error, Header, Method, ParseOptions, Request, EMPTY_HEADER};
+use crate::fields::{parse_headers_until, Cursor};
+use crate::request::parse_request_line;
+
+
+/// `htpp_parse_request` returns it when the request was parsed, the error codes are the codes of `Error::code`
+pub const HTPP_OK: i32 = 0;
+/// `htpp_parse_request` returns it when a required pointer is null
+pub const HTPP_NULL_POINTER: i32 = -1;
+
+// the headers parsed at a time before they are copied to the caller's array
+const SCRATCH_HEADERS: usize = 32;
+
+/// The method of a parsed request, `HTPP_METHOD_OTHER` for an extension method whose name is in `htpp_request.method_name`
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[allow(non_camel_case_types)]
+pub enum htpp_method {
+    /// GET
+    HTPP_METHOD_GET = 0,
+    /// POST
+    HTPP_METHOD_POST = 1,
+    /// PUT
+    HTPP_METHOD_PUT = 2,
+    /// DELETE
+    HTPP_METHOD_DELETE = 3,
+    /// PATCH
+    HTPP_METHOD_PATCH = 4,
+    /// HEAD
+    HTPP_METHOD_HEAD = 5,
+    /// OPTIONS
+    HTPP_METHOD_OPTIONS = 6,
+    /// TRACE
+    HTPP_METHOD_TRACE = 7,
+    /// CONNECT
+    HTPP_METHOD_CONNECT = 8,
+    /// An extension method
+    HTPP_METHOD_OTHER = 9,
+}
+impl From<&Method<'_>> for htpp_method {
+    fn from(method: &Method<'_>) -> Self {
+        match method {
+            Method::Get => Self::HTPP_METHOD_GET,
+            Method::Post => Self::HTPP_METHOD_POST,
+            Method::Put => Self::HTPP_METHOD_PUT,
+            Method::Delete => Self::HTPP_METHOD_DELETE,
+            Method::Patch => Self::HTPP_METHOD_PATCH,
+            Method::Head => Self::HTPP_METHOD_HEAD,
+            Method::Options => Self::HTPP_METHOD_OPTIONS,
+            Method::Trace => Self::HTPP_METHOD_TRACE,
+            Method::Connect => Self::HTPP_METHOD_CONNECT,
+            Method::Other(_) => Self::HTPP_METHOD_OTHER,
+        }
+    }
+}
+
+/// A header of a parsed request, the name and value are not null-terminated
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[allow(non_camel_case_types)]
+pub struct htpp_header {
+    /// The start of the header name
+    pub name: *const u8,
+    /// The length of the header name in bytes
+    pub name_len: usize,
+    /// The start of the header value
+    pub val: *const u8,
+    /// The length of the header value in bytes
+    pub val_len: usize,
+}
+
+/// A parsed request, the strings are not null-terminated
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[allow(non_camel_case_types)]
+pub struct htpp_request {
+    /// The method of the request
+    pub method: htpp_method,
+    /// The start of the method token as sent, for every method
+    pub method_name: *const u8,
+    /// The length of the method token in bytes
+    pub method_name_len: usize,
+    /// The start of the request target
+    pub path: *const u8,
+    /// The length of the request target in bytes
+    pub path_len: usize,
+    /// The number of headers written to the start of the headers array
+    pub headers_len: usize,
+    /// The start of the body, the bytes after the header section
+    pub body: *const u8,
+    /// The length of the body in bytes
+    pub body_len: usize,
+}
+
+/// Parses the `len` bytes at `buf` like `Request::parse`, writing the request to `req` and its headers to the start of the `headers_cap` long `headers` array
+/// Returns `HTPP_OK` on success, `HTPP_NULL_POINTER` if `buf`, `req`, or `headers` with a non-zero `headers_cap` is null,
+/// or the `Error::code` of the parse error, such as 101 for an incomplete request or 400 for more headers than `headers_cap`.
+/// Headers are parsed through a small fixed scratch buffer on the stack and copied to `headers` a batch at a time, so nothing is allocated
+/// whatever `headers_cap` is. `req` is only written on success, but the headers before the error may have been written to `headers` on failure
+///
+/// # Safety
+///
+/// `buf` must point to `len` initialized bytes that aren't written to while `req` and `headers` are in use, since their pointers point into it.
+/// `req` must be valid for writes of one `htpp_request` and `headers` for writes of `headers_cap` `htpp_header`s
+#[no_mangle]
+#[inline]
+pub unsafe extern "C" fn htpp_parse_request(buf: *const u8, len: usize, req: *mut htpp_request, headers: *mut htpp_header, headers_cap: usize) -> i32 {
+  if buf.is_null() || req.is_null() || (headers.is_null() && headers_cap != 0) {return HTPP_NULL_POINTER;}
+  // SAFETY: the caller guarantees `buf` points to `len` initialized bytes that aren't mutated while borrowed
+  let slice = unsafe { slice::from_raw_parts(buf, len) };
+  let (method, path, mut offset) = match parse_request_line(slice, &ParseOptions::default()) {
+    Ok(line) => line,
+    Err(err) => return i32::from(head_incomplete(err, slice).code()),
+  };
+  let mut written = 0;
+  loop {
+    let mut scratch = [EMPTY_HEADER; SCRATCH_HEADERS];
+    let room = (headers_cap - written).min(SCRATCH_HEADERS);
+    let mut cursor = Cursor::default();
+    let result = parse_headers_until(&slice[offset..], &mut scratch[..room], &mut cursor, usize::MAX, &ParseOptions::default(), &mut ());
+    for (index, header) in scratch[..cursor.parsed].iter().enumerate() {
+      // SAFETY: `written + cursor.parsed` is at most `headers_cap` and the caller guarantees `headers` is valid for that many writes
+      unsafe { headers.add(written + index).write(to_c_header(header)) };
+    }
+    written += cursor.parsed;
+    offset += cursor.offset;
+    match result {
+      Ok(_) => break,
+      // the scratch buffer is full but `headers` has room for more
+      Err(Error::TooManyHeaders) if room == SCRATCH_HEADERS => continue,
+      Err(err) => return i32::from(head_incomplete(err, slice).code()),
+    }
+  }
+  let method_name = method.as_str();
+  let body = &slice[offset..];
+  let request = htpp_request {
+    method: htpp_method::from(&method),
+    method_name: method_name.as_ptr(),
+    method_name_len: method_name.len(),
+    path: path.as_ptr(),
+    path_len: path.len(),
+    headers_len: written,
+    body: body.as_ptr(),
+    body_len: body.len(),
+  };
+  // SAFETY: the caller guarantees `req` is valid for one write
+  unsafe { req.write(request) };
+  HTPP_OK
+}
+
+#[inline]
+// the C view of a header borrowing the same bytes
+fn to_c_header(header: &Header) -> htpp_header {
+  htpp_header { name: header.name.as_ptr(), name_len: header.name.len(), val: header.val.as_ptr(), val_len: header.val.len() }
+}
@@ -0,0 +1,574 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Parsing of HTTP header lines
+//!
+//! [Request](crate::Request) and [Response](crate::Response) use these functions to parse their headers.
+//! They are public so protocols sharing the HTTP header syntax but not its start line, like RTSP, SIP, or ICAP, can reuse them
+//!
+//! ```rust
+//! use htpp::{EMPTY_HEADER, fields::parse_headers};
+//!
+//! let msg = b"RTSP/1.0 200 OK\r\nCSeq: 1\r\nPublic: DESCRIBE, SETUP, PLAY\r\n\r\n";
+//! // skip the RTSP status line
+//! let start = msg.windows(2).position(|w| w == b"\r\n").unwrap() + 2;
+//! let mut headers = [EMPTY_HEADER; 10];
+//! parse_headers(&msg[start..], &mut headers).unwrap();
+//! assert!(headers[1].name == "Public");
+//! assert!(headers[1].val == b"DESCRIBE, SETUP, PLAY");
+//! ```
+
+use core::{str, fmt};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::stats::StatSink;
+use crate::text::{lossy_escape, DEFAULT_LIMIT};
+use crate::{EncodeError, Error, ParseOptions, Result, COLON, CR, HEADER_NAME_SAFE, HTAB, LF, SPACE};
+
+
+/// An empty header to make it easier to construct a header buffer to parse headers into
+pub const EMPTY_HEADER: Header = Header{name: "", val: &[]};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// An HTTP header
+pub struct Header<'a> {
+    /// The name of the header
+    pub name: &'a str,
+    /// The value of the header
+    pub val: &'a [u8],
+}
+#[cfg(feature = "fmt")]
+impl<'a> fmt::Display for Header<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.name.is_empty() {
+            return write!(f, "{}: {}", self.name, lossy_escape(self.val, DEFAULT_LIMIT));
+        };
+        f.write_str("")
+    }
+}
+impl<'a> Header<'a> {
+  /// Create a new HTTP header with the given name and value
+  pub fn new(name: &'a str, val: &'a [u8]) -> Self {
+    Self {
+        name,
+        val
+    }
+  }
+
+  /// Parses exactly one `Name: Value\r\n` header line at the start of the slice
+  /// Returns the header and the number of bytes read including the terminating `\r\n`.
+  /// Returns an Err(Error::Malformed) if the name is invalid or the line isn't terminated by `\r\n`
+  ///
+  /// ```rust
+  /// use htpp::Header;
+  ///
+  /// let (header, read) = Header::parse_line(b"CSeq: 2\r\nSession: 1\r\n").unwrap();
+  /// assert!(header == Header::new("CSeq", b"2"));
+  /// assert!(read == 9);
+  /// ```
+  #[inline]
+  pub fn parse_line(slice: &'a [u8]) -> Result<(Header<'a>, usize)> {
+    let (name, name_len) = parse_header_name(slice)?;
+    let (val, val_len) = parse_header_value(&slice[name_len..])?;
+    Ok((Header::new(name, val), name_len + val_len))
+  }
+
+  /// The comma-separated tokens of the value trimmed of whitespace and lowercased, to compare list values such as
+  /// `Connection: Keep-Alive, Upgrade` case-insensitively. Empty list elements are skipped and bytes that aren't valid UTF-8 are replaced with U+FFFD
+  ///
+  /// ```rust
+  /// use htpp::Header;
+  ///
+  /// let header = Header::new("Connection", b"Keep-Alive, Upgrade");
+  /// assert!(header.value_tokens_lower().collect::<Vec<_>>() == ["keep-alive", "upgrade"]);
+  /// ```
+  #[inline]
+  pub fn value_tokens_lower(&self) -> impl Iterator<Item = String> + 'a {
+    self.val.split(|character| *character == b',')
+      .map(<[u8]>::trim_ascii)
+      .filter(|token| !token.is_empty())
+      .map(|token| String::from_utf8_lossy(token).to_lowercase())
+  }
+}
+
+/// Parses a block of header lines terminated by an empty line into the passed `headers_buf`
+/// Returns the number of bytes read including the terminating `\r\n` and the number of headers written to the start of `headers_buf`.
+/// If there is more headers than the length of the buffer you pass, an Err(Error::TooManyHeaders) is returned.
+/// A line folded onto the previous one by starting it with a space or tab is rejected with an Err(Error::ObsFold)
+///
+/// ```rust
+/// use htpp::{EMPTY_HEADER, fields::parse_headers};
+///
+/// let block = b"CSeq: 2\r\nSession: 12345678\r\nTransport: RTP/AVP;unicast;client_port=8000-8001\r\n\r\n";
+/// let mut headers = [EMPTY_HEADER; 10];
+/// let (read, count) = parse_headers(block, &mut headers).unwrap();
+/// assert!(read == block.len());
+/// assert!(count == 3);
+/// assert!(headers[0].name == "CSeq");
+/// assert!(headers[2].val == b"RTP/AVP;unicast;client_port=8000-8001");
+/// ```
+#[inline]
+pub fn parse_headers<'a>(slice: &'a[u8], headers_buf: &mut [Header<'a>]) -> Result<(usize, usize)> {
+  let mut cursor = Cursor::default();
+  parse_headers_until(slice, headers_buf, &mut cursor, usize::MAX, &ParseOptions::default(), &mut ())?;
+  Ok((cursor.offset, cursor.parsed))
+}
+
+/// Counts the headers of a block of header lines terminated by an empty line, to size the buffer passed to `parse_headers`.
+/// The lines are validated like `parse_headers` does, so a block counted successfully parses into a buffer of that length.
+/// Returns an Err(Error::Incomplete) if the block ends before the empty line
+///
+/// ```rust
+/// use htpp::{EMPTY_HEADER, fields::{count_headers, parse_headers}};
+///
+/// let block = b"CSeq: 2\r\nSession: 12345678\r\n\r\n";
+/// let mut headers = vec![EMPTY_HEADER; count_headers(block).unwrap()];
+/// assert!(parse_headers(block, &mut headers).unwrap() == (block.len(), 2));
+/// ```
+#[inline]
+pub fn count_headers(slice: &[u8]) -> Result<usize> {
+  let mut offset = 0;
+  let mut count = 0;
+  loop {
+    let rest = &slice[offset..];
+    if rest.starts_with(b"\r\n") {return Ok(count);}
+    if partial_terminator(rest) {return Err(Error::Incomplete { needed: None });}
+    offset += Header::parse_line(rest)?.1;
+    count += 1;
+  }
+}
+
+/// An empty vectored header to make it easier to construct a buffer for `parse_headers_vectored`
+pub const EMPTY_VECTORED_HEADER: VectoredHeader = VectoredHeader{name: Cow::Borrowed(""), val: Cow::Borrowed(&[])};
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+/// A header parsed by `parse_headers_vectored`, it borrows the input unless the header line spans several buffers
+pub struct VectoredHeader<'a> {
+    /// The name of the header
+    pub name: Cow<'a, str>,
+    /// The value of the header
+    pub val: Cow<'a, [u8]>,
+}
+impl<'a> VectoredHeader<'a> {
+  /// A `Header` borrowing the name and value of this header
+  #[inline]
+  pub fn as_header(&self) -> Header<'_> {
+    Header::new(&self.name, &self.val)
+  }
+}
+
+/// Parses a block of header lines terminated by an empty line spread over several buffers, such as the buffers of a vectored read.
+/// Headers inside a single buffer borrow it, only the header lines spanning two or more buffers are copied.
+/// Returns the number of bytes read over all buffers including the terminating `\r\n` and the number of headers written to the start of `headers_buf`
+///
+/// ```rust
+/// use htpp::fields::{parse_headers_vectored, EMPTY_VECTORED_HEADER};
+///
+/// let bufs: [&[u8]; 2] = [b"CSeq: 2\r\nSess", b"ion: 12345678\r\n\r\n"];
+/// let mut headers = [EMPTY_VECTORED_HEADER; 10];
+/// let (read, count) = parse_headers_vectored(&bufs, &mut headers).unwrap();
+/// assert!(read == 30);
+/// assert!(count == 2);
+/// assert!(headers[1].name == "Session");
+/// assert!(*headers[1].val == *b"12345678");
+/// ```
+#[inline]
+pub fn parse_headers_vectored<'a>(bufs: &[&'a [u8]], headers_buf: &mut [VectoredHeader<'a>]) -> Result<(usize, usize)> {
+  let mut read = 0;
+  let mut parsed = 0;
+  let mut index = 0;
+  let mut offset = 0;
+  loop {
+    while bufs.get(index).is_some_and(|buf| offset == buf.len()) {
+      index += 1;
+      offset = 0;
+    }
+    let rest = &bufs.get(index).ok_or(Error::Incomplete { needed: None })?[offset..];
+    let header = match rest.windows(2).position(|window| window == b"\r\n") {
+      Some(0) => return Ok((read + 2, parsed)),
+      Some(end) => {
+        offset += end + 2;
+        read += end + 2;
+        if parsed >= headers_buf.len() {return Err(Error::TooManyHeaders);}
+        let (name, val) = parse_header_line(&rest[..(end+2)])?;
+        VectoredHeader { name: Cow::Borrowed(name), val: Cow::Borrowed(val) }
+      },
+      None => {
+        // the line continues in the next buffers, copy it until its `\r\n`
+        let mut line = rest.to_vec();
+        loop {
+          index += 1;
+          let buf = bufs.get(index).ok_or(Error::Incomplete { needed: None })?;
+          offset = match buf.windows(2).position(|window| window == b"\r\n") {
+            _ if line.last() == Some(&CR) && buf.first() == Some(&LF) => 1,
+            Some(end) => end + 2,
+            None => buf.len(),
+          };
+          line.extend(&buf[..offset]);
+          if line.ends_with(b"\r\n") {break;}
+        }
+        read += line.len();
+        if line == b"\r\n" {return Ok((read, parsed));}
+        if parsed >= headers_buf.len() {return Err(Error::TooManyHeaders);}
+        let (name, val) = parse_header_line(&line)?;
+        VectoredHeader { name: Cow::Owned(name.to_owned()), val: Cow::Owned(val.to_vec()) }
+      },
+    };
+    headers_buf[parsed] = header;
+    parsed += 1;
+  }
+}
+
+#[inline]
+// parses a single header line ending with its `\r\n`
+fn parse_header_line(line: &[u8]) -> Result<(&str, &[u8])> {
+  let (header, read) = Header::parse_line(line)?;
+  if read != line.len() {return Err(Error::Malformed);}
+  Ok((header.name, header.val))
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+// The progress of parsing a header block, it is kept between calls when parsing is resumed
+pub(crate) struct Cursor {
+  // the number of bytes of the header block read so far
+  pub(crate) offset: usize,
+  // the number of headers written to the headers buffer so far
+  pub(crate) parsed: usize,
+}
+
+#[cfg(feature = "serialize")]
+#[inline]
+// the length of the headers up to the first empty one when written by `write_headers`, including the empty line ending the block
+pub(crate) fn serialized_len(headers: &[Header]) -> usize {
+  headers.iter()
+    .take_while(|header| !header.name.is_empty())
+    .map(|header| header.name.len() + header.val.len() + 4)
+    .sum::<usize>() + 2
+}
+
+/// Compares two header lists ignoring what doesn't change their meaning: the order of the headers, the case of their names,
+/// and whitespace around their values. The values of each name are compared as a multiset, except for `Set-Cookie`
+/// whose order matters (RFC 6265). Headers named in `ignore`, such as `Date`, and headers with an empty name are skipped
+///
+/// ```rust
+/// use htpp::{Header, fields::headers_semantic_eq};
+///
+/// let a = [Header::new("Host", b"a.com"), Header::new("Date", b"Mon"), Header::new("Accept", b"*/*")];
+/// let b = [Header::new("accept", b" */*"), Header::new("HOST", b"a.com"), Header::new("Date", b"Tue")];
+/// assert!(headers_semantic_eq(&a, &b, &["Date"]));
+/// assert!(!headers_semantic_eq(&a, &b, &[]));
+/// ```
+#[inline]
+pub fn headers_semantic_eq(a: &[Header], b: &[Header], ignore: &[&str]) -> bool {
+  let relevant = |header: &&Header| !header.name.is_empty() && !ignore.iter().any(|name| name.eq_ignore_ascii_case(header.name));
+  if a.iter().filter(relevant).count() != b.iter().filter(relevant).count() {return false;}
+  // with as many headers on both sides, every name of `a` having the same values in `b` leaves no other name in `b`
+  a.iter().filter(relevant).all(|header| {
+    let values = |headers: &[Header<'_>]| -> Vec<Vec<u8>> {
+      headers.iter().filter(|other| other.name.eq_ignore_ascii_case(header.name)).map(|other| other.val.trim_ascii().to_vec()).collect()
+    };
+    let (mut ours, mut theirs) = (values(a), values(b));
+    if !header.name.eq_ignore_ascii_case("Set-Cookie") {
+      ours.sort_unstable();
+      theirs.sort_unstable();
+    }
+    ours == theirs
+  })
+}
+
+/// The headers up to the first empty one as a map from lowercased names to their values in the order they were sent.
+/// Values are kept apart and never joined with `,`, so every `Set-Cookie` stays its own value
+///
+/// ```rust
+/// use htpp::{Header, fields::to_map};
+///
+/// let headers = [Header::new("Set-Cookie", b"a=1"), Header::new("Host", b"a.com"), Header::new("set-cookie", b"b=2")];
+/// let map = to_map(&headers);
+/// assert!(map["set-cookie"] == [b"a=1".to_vec(), b"b=2".to_vec()]);
+/// ```
+#[inline]
+pub fn to_map(headers: &[Header]) -> HashMap<String, Vec<Vec<u8>>> {
+  let mut map: HashMap<String, Vec<Vec<u8>>> = HashMap::new();
+  for header in headers.iter().take_while(|header| !header.name.is_empty()) {
+    map.entry(header.name.to_ascii_lowercase()).or_default().push(header.val.to_vec());
+  }
+  map
+}
+
+/// The headers as a map like `to_map` but ordered by name, for output that doesn't change between runs such as snapshots
+#[inline]
+pub fn to_btreemap(headers: &[Header]) -> BTreeMap<String, Vec<Vec<u8>>> {
+  let mut map: BTreeMap<String, Vec<Vec<u8>>> = BTreeMap::new();
+  for header in headers.iter().take_while(|header| !header.name.is_empty()) {
+    map.entry(header.name.to_ascii_lowercase()).or_default().push(header.val.to_vec());
+  }
+  map
+}
+
+/// Headers borrowing the names and values of a map such as the one `to_map` or `to_btreemap` returns, a header for every value.
+/// Names listed in `order` come first in that order, compared case-insensitively, and the other names follow sorted.
+/// The values of a name keep their order in the map
+///
+/// ```rust
+/// use htpp::{Header, fields::{headers_from_map, to_btreemap}};
+///
+/// let map = to_btreemap(&[Header::new("Accept", b"*/*"), Header::new("Host", b"a.com")]);
+/// let headers = headers_from_map(&map, &["Host"]);
+/// assert!(headers == [Header::new("host", b"a.com"), Header::new("accept", b"*/*")]);
+/// ```
+#[inline]
+pub fn headers_from_map<'m>(map: impl IntoIterator<Item = (&'m String, &'m Vec<Vec<u8>>)>, order: &[&str]) -> Vec<Header<'m>> {
+  let positions: HashMap<String, usize> = order.iter().enumerate().rev().map(|(position, name)| (name.to_ascii_lowercase(), position)).collect();
+  let mut fields: Vec<(usize, &'m String, &'m Vec<Vec<u8>>)> = map.into_iter()
+    .map(|(name, values)| (positions.get(&name.to_ascii_lowercase()).copied().unwrap_or(usize::MAX), name, values))
+    .collect();
+  fields.sort_unstable_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+  fields.iter().flat_map(|(_, name, values)| values.iter().map(|val| Header::new(name, val))).collect()
+}
+
+/// A view of the values of every header named `name`, up to the first empty header, as the single comma-joined value
+/// RFC 9110 says they mean. Nothing is joined or allocated, the view walks the headers instead.
+/// Returns `None` for `Set-Cookie`, whose values can't be joined with `,` without changing their meaning
+///
+/// ```rust
+/// use htpp::{Header, fields::combined};
+///
+/// let headers = [Header::new("Cache-Control", b"no-cache,"), Header::new("Host", b"a.com"), Header::new("cache-control", b"max-age=0")];
+/// let val = combined(&headers, "Cache-Control").unwrap();
+/// assert!(val.contains_token("max-age=0"));
+/// #[cfg(feature = "fmt")]
+/// assert!(val.to_string() == "no-cache,, max-age=0");
+/// assert!(combined(&headers, "Set-Cookie").is_none());
+/// ```
+#[inline]
+pub fn combined<'h, 'a>(headers: &'h [Header<'a>], name: &'h str) -> Option<CombinedValue<'h, 'a>> {
+  if name.eq_ignore_ascii_case("Set-Cookie") {return None;}
+  Some(CombinedValue { headers, name })
+}
+
+/// The comma-joined value of repeated headers returned by [combined]. Iterating yields the raw value of each header in the order they were sent.
+/// The other methods look at the values not iterated over yet as if they were joined with `, ` after trimming the whitespace around each
+#[derive(Debug, Clone)]
+pub struct CombinedValue<'h, 'a> {
+    headers: &'h [Header<'a>],
+    name: &'h str,
+}
+impl<'h, 'a> CombinedValue<'h, 'a> {
+  /// Whether the joined value equals `other`, ignoring ASCII case
+  #[inline]
+  pub fn eq_ignore_ascii_case(&self, other: &str) -> bool {
+    self.joined().map(|character| character.to_ascii_lowercase()).eq(other.bytes().map(|character| character.to_ascii_lowercase()))
+  }
+
+  /// Whether an element of the comma-separated list, trimmed of whitespace, equals `token` ignoring ASCII case.
+  /// Every header starts a new element, so an element never spans two headers
+  #[inline]
+  pub fn contains_token(&self, token: &str) -> bool {
+    self.clone()
+      .flat_map(|val| val.split(|character| *character == b','))
+      .any(|element| element.trim_ascii().eq_ignore_ascii_case(token.as_bytes()))
+  }
+
+  #[inline]
+  // the bytes of the values trimmed of whitespace and joined with `, `
+  fn joined(&self) -> impl Iterator<Item = u8> + '_ {
+    self.clone().enumerate().flat_map(|(index, val)| {
+      let separator: &[u8] = if index == 0 {b""} else {b", "};
+      separator.iter().chain(val.trim_ascii()).copied()
+    })
+  }
+}
+impl<'h, 'a> Iterator for CombinedValue<'h, 'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+      while let Some((header, rest)) = self.headers.split_first() {
+        if header.name.is_empty() {break;}
+        self.headers = rest;
+        if header.name.eq_ignore_ascii_case(self.name) {return Some(header.val);}
+      }
+      self.headers = &[];
+      None
+    }
+}
+#[cfg(feature = "fmt")]
+impl<'h, 'a> fmt::Display for CombinedValue<'h, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      for (index, val) in self.clone().enumerate() {
+        if index > 0 {f.write_str(", ")?;}
+        write!(f, "{}", lossy_escape(val.trim_ascii(), DEFAULT_LIMIT))?;
+      }
+      Ok(())
+    }
+}
+impl PartialEq for CombinedValue<'_, '_> {
+    fn eq(&self, other: &Self) -> bool {
+      self.joined().eq(other.joined())
+    }
+}
+
+#[inline]
+// checks the headers up to the first empty one can be written without splitting the message:
+// names must be tokens and values must not hold control characters other than tabs
+pub(crate) fn validate_headers(headers: &[Header]) -> std::result::Result<(), EncodeError> {
+  for (header_index, header) in headers.iter().take_while(|header| !header.name.is_empty()).enumerate() {
+    if !header.name.bytes().all(|character| HEADER_NAME_SAFE.contains(character))
+      || header.val.iter().any(|character| *character != HTAB && character.is_ascii_control()) {
+      return Err(EncodeError::IllegalByte { header_index });
+    }
+  }
+  Ok(())
+}
+
+#[cfg(feature = "serialize")]
+#[inline]
+// checks the Content-Length headers up to the first empty header declare the length of the body
+// a value that isn't a decimal number is reported as an illegal byte in its header
+pub(crate) fn validate_content_length(headers: &[Header], actual: usize) -> std::result::Result<(), EncodeError> {
+  for (header_index, header) in headers.iter().take_while(|header| !header.name.is_empty()).enumerate() {
+    if !header.name.eq_ignore_ascii_case("Content-Length") {continue;}
+    let declared = std::str::from_utf8(header.val.trim_ascii()).ok()
+      .filter(|val| val.bytes().all(|character| character.is_ascii_digit()))
+      .and_then(|val| val.parse::<usize>().ok())
+      .ok_or(EncodeError::IllegalByte { header_index })?;
+    if declared != actual {return Err(EncodeError::LengthMismatch { declared, actual });}
+  }
+  Ok(())
+}
+
+#[cfg(feature = "serialize")]
+#[inline]
+// the headers up to the first empty one with the value of the first Content-Length header replaced by `length`,
+// later Content-Length headers dropped, and one added at the end if there is none
+pub(crate) fn with_content_length<'a>(headers: &[Header<'a>], length: &'a [u8]) -> Vec<Header<'a>> {
+  let mut replaced = false;
+  let mut headers: Vec<Header<'a>> = headers.iter().take_while(|header| !header.name.is_empty()).filter_map(|header| {
+    if !header.name.eq_ignore_ascii_case("Content-Length") {return Some(*header);}
+    if replaced {return None;}
+    replaced = true;
+    Some(Header::new(header.name, length))
+  }).collect();
+  if !replaced {headers.push(Header::new("Content-Length", length));}
+  headers
+}
+
+#[cfg(feature = "serialize")]
+#[inline]
+// writes the headers up to the first empty one followed by the empty line ending the block
+pub(crate) fn write_headers(headers: &[Header], bytes: &mut Vec<u8>) {
+  for header in headers.iter() {
+    if header.name.is_empty() {break;}
+    bytes.extend(header.name.as_bytes());
+    bytes.extend(b": ");
+    bytes.extend(header.val);
+    bytes.extend(b"\r\n");
+  }
+  bytes.extend(b"\r\n");
+}
+
+#[inline]
+// Parses the headers into the passed headers_buf starting at the cursor, the cursor is kept up to date even if an error is returned
+// Stops before the first header line starting at or past `limit` and returns false, returns true once the block is fully parsed
+// Every header and every line ending with a bare `\n` is reported to `stats`
+pub(crate) fn parse_headers_until<'a>(slice: &'a[u8], headers_buf: &mut [Header<'a>], cursor: &mut Cursor, limit: usize, options: &ParseOptions, stats: &mut impl StatSink) -> Result<bool> {
+  loop {
+    if let Some(read) = line_terminator(&slice[cursor.offset..], options) {
+      if read == 1 {stats.relaxed();}
+      cursor.offset += read;
+      return Ok(true);
+    }
+    if partial_terminator(&slice[cursor.offset..]) {return Err(Error::Incomplete { needed: None });}
+    if cursor.offset >= limit {return Ok(false);}
+    if cursor.parsed >= headers_buf.len() {return Err(Error::TooManyHeaders);}
+    let name = parse_header_name(&slice[cursor.offset..])?;
+    let val = parse_header_value_with_options(&slice[(cursor.offset+name.1)..], options)?;
+    stats.header(val.0.len());
+    if val.1 == val.0.len() + 1 {stats.relaxed();}
+    cursor.offset += name.1 + val.1;
+    headers_buf[cursor.parsed] = Header::new(name.0, val.0);
+    cursor.parsed += 1;
+  }
+}
+/// Parses the name of a header line such as `CSeq: 2\r\n`
+/// Returns the name and the number of bytes read including the `:` and a single space or tab after it.
+/// Returns an Err(Error::ObsFold) if the slice starts with a space or tab, an Err(Error::Malformed) if the name is empty
+/// or contains a character that isn't allowed in a header name, or an Err(Error::Incomplete) if the slice ends before the `:`
+///
+/// ```rust
+/// use htpp::fields::parse_header_name;
+///
+/// let (name, read) = parse_header_name(b"CSeq: 2\r\n").unwrap();
+/// assert!(name == "CSeq");
+/// assert!(read == 6);
+/// ```
+#[inline]
+pub fn parse_header_name(slice: &[u8]) -> Result<(&str, usize)> {
+  if matches!(slice.first(), Some(&SPACE | &HTAB)) {return Err(Error::ObsFold);}
+  for (counter, character) in slice.iter().enumerate() {
+    if HEADER_NAME_SAFE.contains(*character) {
+      continue;
+    } else if *character == COLON {
+      let name = &slice[..counter];
+      if name.is_empty() {return Err(Error::Malformed);}
+      if slice.get(counter+1) == Some(&SPACE) || slice.get(counter+1) == Some(&HTAB) {
+        //SAFETY: already checked that the input is valid ascii
+        return Ok( (unsafe { std::str::from_utf8_unchecked(name) }, counter+2));
+      }
+      //SAFETY: already checked that the input is valid ascii
+      return Ok( (unsafe { std::str::from_utf8_unchecked(name) }, counter+1));
+    }
+    return Err(Error::Malformed);
+  }
+  Err(Error::Incomplete { needed: None })
+}
+/// Parses the value of a header line, the slice must start right after the name returned by `parse_header_name`
+/// Returns the value and the number of bytes read including the terminating `\r\n`.
+/// Returns an Err(Error::Malformed) if the value isn't terminated by `\r\n`, or an Err(Error::Incomplete) if the slice ends before the `\r\n`
+///
+/// ```rust
+/// use htpp::fields::parse_header_value;
+///
+/// let (val, read) = parse_header_value(b"12345678\r\n").unwrap();
+/// assert!(val == b"12345678");
+/// assert!(read == 10);
+/// ```
+#[inline]
+pub fn parse_header_value(slice: &[u8]) -> Result<(&[u8], usize)> {
+  parse_header_value_with_options(slice, &ParseOptions::default())
+}
+#[inline]
+pub(crate) fn parse_header_value_with_options<'a>(slice: &'a [u8], options: &ParseOptions) -> Result<(&'a [u8], usize)> {
+  for (counter, character) in slice.iter().enumerate() {
+    if *character == LF && !options.allow_bare_lf {return Err(Error::Malformed);}
+    if *character == CR || *character == LF {
+      let val = &slice[..counter];
+      return match line_terminator(&slice[counter..], options) {
+        Some(read) => Ok((val, counter+read)),
+        None if partial_terminator(&slice[counter..]) => Err(Error::Incomplete { needed: None }),
+        None => Err(Error::Malformed),
+      };
+    }
+  }
+  Err(Error::Incomplete { needed: None })
+}
+
+#[inline]
+// the length of the line terminator the slice starts with, a bare `\n` is only a terminator if the options allow it
+pub(crate) fn line_terminator(slice: &[u8], options: &ParseOptions) -> Option<usize> {
+  match slice {
+    [CR, LF, ..] => Some(2),
+    [LF, ..] if options.allow_bare_lf => Some(1),
+    _ => None,
+  }
+}
+
+#[inline]
+// whether the slice could be a line terminator cut off by the end of the input
+pub(crate) fn partial_terminator(slice: &[u8]) -> bool {
+  matches!(slice, [] | [CR])
+}
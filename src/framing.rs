@@ -0,0 +1,98 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Helpers for finding how the body of a message is framed
+//!
+//! ```rust
+//! use htpp::{Header, framing::is_chunked};
+//!
+//! let headers = [Header::new("Transfer-Encoding", b"gzip"), Header::new("transfer-encoding", b" Chunked ")];
+//! assert!(is_chunked(&headers));
+//! ```
+
+use crate::{walk_chunks, Error, Header, ParseOptions, Result};
+
+
+/// The transfer codings of the `Transfer-Encoding` headers in the order they were applied
+/// Repeated `Transfer-Encoding` lines are merged in order, each coding is trimmed and empty list elements are skipped
+#[inline]
+pub fn transfer_codings<'h>(headers: &'h [Header]) -> impl Iterator<Item = &'h [u8]> {
+  headers.iter()
+    .filter(|header| header.name.eq_ignore_ascii_case("Transfer-Encoding"))
+    .flat_map(|header| header.val.split(|character| *character == b','))
+    .map(<[u8]>::trim_ascii)
+    .filter(|coding| !coding.is_empty())
+}
+
+/// Whether the message body is chunked, that is the last transfer coding is `chunked` compared case-insensitively
+#[inline]
+pub fn is_chunked(headers: &[Header]) -> bool {
+  transfer_codings(headers).last().is_some_and(is_chunked_coding)
+}
+
+/// Whether the message body is chunked like `is_chunked`
+/// Returns an Err(Error::Malformed) if `chunked` is applied anywhere but last, the body could not be delimited
+#[inline]
+pub fn try_is_chunked(headers: &[Header]) -> Result<bool> {
+  let mut chunked = false;
+  for coding in transfer_codings(headers) {
+    if chunked {return Err(Error::Malformed);}
+    chunked = is_chunked_coding(coding);
+  }
+  Ok(chunked)
+}
+
+/// Checks the transfer codings of a request: if there is any, `chunked` must be the last one and appear only there.
+/// A request body with another final coding can't be delimited, so the request is an Err(Error::Malformed)
+#[inline]
+pub fn validate_request_codings(headers: &[Header]) -> Result<()> {
+  if transfer_codings(headers).next().is_some() && !try_is_chunked(headers)? {
+    return Err(Error::Malformed);
+  }
+  Ok(())
+}
+
+#[inline]
+fn is_chunked_coding(coding: &[u8]) -> bool {
+  coding.eq_ignore_ascii_case(b"chunked")
+}
+
+/// Appends the data of the chunks of a chunked body to `out` and returns the length of the chunked body including its trailer section.
+/// Chunk extensions and trailer fields are dropped. Returns an Err(Error::Malformed) if the body isn't validly chunked,
+/// or an Err(Error::Incomplete) if it is cut off, with the number of missing bytes when the cut is in the data of a chunk
+///
+/// ```rust
+/// use htpp::framing::decode_chunked;
+///
+/// let mut out = Vec::new();
+/// assert!(decode_chunked(b"5\r\nhello\r\n1;ext=1\r\n!\r\n0\r\n\r\nnext", &mut out) == Ok(27));
+/// assert!(out == b"hello!");
+/// ```
+#[inline]
+pub fn decode_chunked(body: &[u8], out: &mut Vec<u8>) -> Result<usize> {
+  decode_chunked_with_options(body, out, &ParseOptions::default())
+}
+
+/// Decodes a chunked body like `decode_chunked` but with the given `ParseOptions`.
+/// Returns an Err(Error::BodyTooLarge) as soon as a chunk takes the data past `max_body_size`, before that chunk is appended to `out`
+///
+/// ```rust
+/// use htpp::{Error, ParseOptions, framing::decode_chunked_with_options};
+///
+/// let options = ParseOptions { max_body_size: Some(4), ..ParseOptions::default() };
+/// let mut out = Vec::new();
+/// let err = decode_chunked_with_options(b"3\r\nabc\r\n3\r\ndef\r\n0\r\n\r\n", &mut out, &options).unwrap_err();
+/// assert!(err == Error::BodyTooLarge { declared: None, limit: 4 });
+/// assert!(out == b"abc");
+/// ```
+#[inline]
+pub fn decode_chunked_with_options(body: &[u8], out: &mut Vec<u8>, options: &ParseOptions) -> Result<usize> {
+  walk_chunks(body, options.max_body_size, |data| {
+    out.extend_from_slice(data);
+    Ok(())
+  })
+}
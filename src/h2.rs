@@ -0,0 +1,115 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Building requests from HTTP/2 header fields, for frontends that decode HTTP/2 and hand requests to HTTP/1.1 code,
+//! and detecting the HTTP/2 connection preface on connections that may speak either version
+//!
+//! ```rust
+//! use htpp::{Header, Method, h2::request_from_pseudo_headers};
+//!
+//! let fields = [
+//!   Header::new(":method", b"GET"),
+//!   Header::new(":scheme", b"https"),
+//!   Header::new(":authority", b"example.com"),
+//!   Header::new(":path", b"/index.html"),
+//!   Header::new("accept", b"*/*"),
+//! ];
+//! let req = request_from_pseudo_headers(&fields, b"").unwrap();
+//! assert!(req.method == Method::Get);
+//! assert!(req.path == "/index.html");
+//! assert!(req.headers == &fields[4..]);
+//! ```
+
+use std::str;
+
+use crate::uri::{is_scheme, parse_authority};
+use crate::{find_header, Error, Header, Method, Request, Result};
+
+
+/// Builds a `Request` from decoded HTTP/2 header fields, the pseudo-headers must come before the regular fields.
+/// The method is taken from `:method` and the path from `:path`, or from `:authority` for a CONNECT request, which has no `:scheme` or `:path`.
+/// The regular fields become the headers of the request. `:scheme` and `:authority` are validated but not part of the request,
+/// get them with `pseudo_header`. The authority isn't turned into a `Host` header, the headers are borrowed as they are:
+/// a caller forwarding the request to an HTTP/1.1 server must write a `Host` header with the `:authority` when the fields have none.
+///
+/// Returns an Err(Error::Malformed) if `:method` is missing, a pseudo-header is unknown, repeated, or after a regular field,
+/// `:scheme` or `:path` is missing from a request other than CONNECT or present in a CONNECT request, `:authority` is missing from a CONNECT request,
+/// the scheme isn't a URI scheme, the authority isn't a `host[:port]`, an `http` or `https` request has neither `:authority` nor `Host`,
+/// a `Host` header differs from `:authority`, the method isn't a token, or the path or a header can't be sent in an HTTP/1.1 request
+#[inline]
+pub fn request_from_pseudo_headers<'a, 'headers>(fields: &'headers [Header<'a>], body: &'a [u8]) -> Result<Request<'a, 'headers>> {
+  let count = fields.iter().position(|field| !field.name.starts_with(':')).unwrap_or(fields.len());
+  let (pseudo, headers) = fields.split_at(count);
+  if headers.iter().any(|field| field.name.starts_with(':')) {return Err(Error::Malformed);}
+  for (index, field) in pseudo.iter().enumerate() {
+    if !matches!(field.name, ":method" | ":scheme" | ":authority" | ":path") {return Err(Error::Malformed);}
+    if pseudo[..index].iter().any(|other| other.name == field.name) {return Err(Error::Malformed);}
+  }
+  let method = pseudo_header(pseudo, ":method").ok_or(Error::Malformed)?;
+  let method = Method::from_bytes(method)?;
+  let scheme = pseudo_header(pseudo, ":scheme");
+  let authority = pseudo_header(pseudo, ":authority");
+  if let Some(authority) = authority {
+    parse_authority(authority).map_err(|_| Error::Malformed)?;
+  }
+  if let (Some(authority), Some(host)) = (authority, find_header(headers, "Host")) {
+    if !host.val.eq_ignore_ascii_case(authority) {return Err(Error::Malformed);}
+  }
+  let path = if method == Method::Connect {
+    // RFC 9113 section 8.5, a CONNECT request names only the authority to connect to
+    if scheme.is_some() || pseudo_header(pseudo, ":path").is_some() {return Err(Error::Malformed);}
+    authority.ok_or(Error::Malformed)?
+  } else {
+    // RFC 9113 section 8.3.1, the authority may come from `Host` instead of `:authority`
+    let scheme = scheme.filter(|scheme| is_scheme(scheme)).ok_or(Error::Malformed)?;
+    let needs_authority = scheme.eq_ignore_ascii_case(b"http") || scheme.eq_ignore_ascii_case(b"https");
+    if needs_authority && authority.is_none() && find_header(headers, "Host").is_none() {return Err(Error::Malformed);}
+    pseudo_header(pseudo, ":path").filter(|path| !path.is_empty()).ok_or(Error::Malformed)?
+  };
+  let path = str::from_utf8(path).map_err(|_| Error::Malformed)?;
+  Request::try_new(method, path, headers, body)
+}
+
+/// The connection preface a client speaking HTTP/2 with prior knowledge sends first, `Request::parse` rejects it as malformed
+pub const CONNECTION_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Whether the bytes received on a connection start with the HTTP/2 connection preface
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum PrefaceStatus {
+  /// The bytes start with the whole preface, hand the connection to an HTTP/2 implementation. The HTTP/2 frames follow the preface
+  Complete,
+  /// The bytes are a prefix of the preface, read more before deciding
+  Partial,
+  /// The bytes aren't the start of the preface, parse them as HTTP/1.1
+  Absent,
+}
+
+/// Checks if the bytes received at the start of a connection are the HTTP/2 connection preface `CONNECTION_PREFACE`
+///
+/// ```rust
+/// use htpp::h2::{is_connection_preface, PrefaceStatus};
+///
+/// assert!(is_connection_preface(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\0\0\x12\x04") == PrefaceStatus::Complete);
+/// assert!(is_connection_preface(b"PRI * HTTP/2.0\r\n") == PrefaceStatus::Partial);
+/// assert!(is_connection_preface(b"GET / HTTP/1.1\r\n\r\n") == PrefaceStatus::Absent);
+/// ```
+#[inline]
+pub fn is_connection_preface(buf: &[u8]) -> PrefaceStatus {
+  if buf.starts_with(CONNECTION_PREFACE) {
+    PrefaceStatus::Complete
+  } else if CONNECTION_PREFACE.starts_with(buf) {
+    PrefaceStatus::Partial
+  } else {
+    PrefaceStatus::Absent
+  }
+}
+
+/// The value of the pseudo-header with the given name such as `:authority`, `None` if it isn't among the leading pseudo-headers
+#[inline]
+pub fn pseudo_header<'a>(fields: &[Header<'a>], name: &str) -> Option<&'a [u8]> {
+  fields.iter().take_while(|field| field.name.starts_with(':')).find(|field| field.name == name).map(|field| field.val)
+}
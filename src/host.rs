@@ -0,0 +1,123 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Finding the authority a request is meant for, for gateways forwarding HTTP/1.1 requests over HTTP/2
+//!
+//! ```rust
+//! use htpp::{Request, EMPTY_HEADER, host::authority_for_forwarding};
+//!
+//! let req = b"GET / HTTP/1.1\r\nHost: WWW.Example.com:443\r\n\r\n";
+//! let mut headers = [EMPTY_HEADER; 10];
+//! let parsed = Request::parse(req, &mut headers).unwrap();
+//! let decision = authority_for_forwarding(&parsed, Some("www.example.com")).unwrap();
+//! assert!(decision.authority == "www.example.com");
+//! assert!(decision.sni_verified);
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::net::Ipv4Addr;
+
+use crate::uri::{classify_target, parse_authority, RequestTarget};
+use crate::Request;
+
+
+/// The authority to forward a request to, such as the value of the HTTP/2 `:authority` pseudo-header
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct AuthorityDecision<'a> {
+    /// The lowercased host with the port unless it is the default port of the scheme
+    pub authority: Cow<'a, str>,
+    /// Whether the host was compared to a TLS server name and matched, `false` if no server name was given
+    pub sni_verified: bool,
+}
+
+/// All errors that could result from finding the authority of a request
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum HostError {
+  /// The request has an origin-form or asterisk-form target and no `Host` header
+  Missing,
+  /// The request has more than one `Host` header
+  Duplicate,
+  /// The `Host` header isn't a valid host with an optional port
+  Invalid,
+  /// The host doesn't match the TLS server name the client presented. IP literals never match a server name
+  Misdirected,
+}
+impl HostError {
+  /// The status code to answer the request with, 421 Misdirected Request for `Misdirected` and 400 Bad Request otherwise
+  #[inline]
+  pub fn status(&self) -> u16 {
+    match self {
+      Self::Misdirected => 421,
+      _ => 400,
+    }
+  }
+}
+impl fmt::Display for HostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let err = match self {
+            Self::Missing => "missing host",
+            Self::Duplicate => "duplicate host header",
+            Self::Invalid => "invalid host",
+            Self::Misdirected => "host doesn't match the server name",
+        };
+        f.write_str(err)
+    }
+}
+impl std::error::Error for HostError {}
+
+
+/// Finds the authority of the request and checks it against the TLS server name `sni` if there is one.
+/// The authority of an absolute-form or authority-form target takes precedence over the `Host` header.
+/// The default port is that of the target's scheme, or of `https` when a server name is given and `http` otherwise
+#[inline]
+pub fn authority_for_forwarding<'a>(req: &Request<'a, '_>, sni: Option<&str>) -> Result<AuthorityDecision<'a>, HostError> {
+  let (scheme, host, port) = request_authority(req)?;
+  let scheme = scheme.unwrap_or(if sni.is_some() {"https"} else {"http"});
+  let sni_verified = match sni {
+    Some(sni) => {
+      let ip_literal = host.starts_with('[') || host.parse::<Ipv4Addr>().is_ok();
+      if ip_literal || !host.eq_ignore_ascii_case(sni) {return Err(HostError::Misdirected);}
+      true
+    },
+    None => false,
+  };
+  let port = port.filter(|port| Some(*port) != default_port(scheme));
+  let authority = match port {
+    Some(port) => Cow::Owned(format!("{}:{port}", host.to_ascii_lowercase())),
+    None if host.bytes().any(|character| character.is_ascii_uppercase()) => Cow::Owned(host.to_ascii_lowercase()),
+    None => Cow::Borrowed(host),
+  };
+  Ok(AuthorityDecision { authority, sni_verified })
+}
+
+#[inline]
+// the scheme if the target has one, the host, and the port of the request, taken from the target before the `Host` header
+pub(crate) fn request_authority<'a>(req: &Request<'a, '_>) -> Result<(Option<&'a str>, &'a str, Option<u16>), HostError> {
+  match classify_target(req.path.as_bytes()) {
+    Ok(RequestTarget::Absolute { scheme, host, port, .. }) => return Ok((Some(scheme), host, port)),
+    Ok(RequestTarget::Authority { host, port }) => return Ok((None, host, Some(port))),
+    _ => {},
+  }
+  let mut hosts = req.headers.iter().filter(|header| header.name.eq_ignore_ascii_case("Host"));
+  let header = hosts.next().ok_or(HostError::Missing)?;
+  if hosts.next().is_some() {return Err(HostError::Duplicate);}
+  let (host, port) = parse_authority(header.val.trim_ascii()).map_err(|_| HostError::Invalid)?;
+  Ok((None, host, port))
+}
+
+#[inline]
+// the default port of a scheme, schemes are case-insensitive
+pub(crate) fn default_port(scheme: &str) -> Option<u16> {
+  if scheme.eq_ignore_ascii_case("https") {
+    Some(443)
+  } else if scheme.eq_ignore_ascii_case("http") {
+    Some(80)
+  } else {
+    None
+  }
+}
@@ -0,0 +1,137 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Parsed messages as byte ranges into the parsed buffer instead of borrowed slices
+//!
+//! The ranges stay valid wherever the bytes of the message are copied, so they can be stored or sent
+//! to another process along with the buffer and turned back into a `Request` or `Response` there
+//!
+//! ```rust
+//! use htpp::{Request, EMPTY_HEADER, indices::EMPTY_HEADER_INDICES};
+//!
+//! let buf = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
+//! let mut header_indices = [EMPTY_HEADER_INDICES; 10];
+//! let indices = Request::parse_indices(buf, &mut header_indices).unwrap();
+//! assert!(indices.path == (4..15));
+//! let copy = buf.to_vec();
+//! let mut headers = [EMPTY_HEADER; 10];
+//! let req = indices.request(&copy, &header_indices, &mut headers).unwrap();
+//! assert!(req.headers[0].val == b"example.com");
+//! ```
+
+use std::ops::Range;
+use std::str;
+
+use crate::fields::partial_terminator;
+use crate::{Error, Header, Method, Request, Response, Result};
+
+
+/// An empty header indices to make it easier to construct a buffer for `Request::parse_indices` and `Response::parse_indices`
+pub const EMPTY_HEADER_INDICES: HeaderIndices = HeaderIndices { name: 0..0, val: 0..0 };
+
+/// The position of a header in the parsed buffer
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct HeaderIndices {
+    /// The range of the header name
+    pub name: Range<u32>,
+    /// The range of the header value, the same bytes as `Header::val`: a single space or tab after the colon is skipped but other whitespace around the value is kept
+    pub val: Range<u32>,
+}
+
+/// A request parsed by `Request::parse_indices`, the headers are at the start of the header indices buffer passed to it
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct RequestIndices {
+    /// The range of the method token
+    pub method: Range<u32>,
+    /// The range of the request target
+    pub path: Range<u32>,
+    /// The number of headers written to the header indices buffer
+    pub headers: usize,
+    /// The range of the body, from the end of the header section to the end of the buffer
+    pub body: Range<u32>,
+}
+impl RequestIndices {
+  /// The `Request` at these indices in `slice`, which must hold the same bytes as the parsed buffer but can be a copy of it.
+  /// `header_indices` is the buffer the request was parsed with, the headers are written to `headers_buf`.
+  /// Returns an Err(Error::Malformed) if a range doesn't fit `slice`, the method isn't a token, or the path or a header name isn't valid UTF-8,
+  /// or an Err(Error::TooManyHeaders) if there is more headers than the length of `headers_buf`
+  #[inline]
+  pub fn request<'a, 'headers>(&self, slice: &'a [u8], header_indices: &[HeaderIndices], headers_buf: &'headers mut [Header<'a>]) -> Result<Request<'a, 'headers>> {
+    let count = rehydrate_headers(slice, header_indices, self.headers, headers_buf)?;
+    let method = Method::from_bytes(range(slice, &self.method)?)?;
+    let path = str::from_utf8(range(slice, &self.path)?).map_err(|_| Error::Malformed)?;
+    Ok(Request::new(method, path, &headers_buf[..count], range(slice, &self.body)?))
+  }
+}
+
+/// A response parsed by `Response::parse_indices`, the headers are at the start of the header indices buffer passed to it
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ResponseIndices {
+    /// The status code of the response
+    pub status: u16,
+    /// The range of the reason phrase
+    pub reason: Range<u32>,
+    /// The number of headers written to the header indices buffer
+    pub headers: usize,
+    /// The range of the body, from the end of the header section to the end of the buffer
+    pub body: Range<u32>,
+}
+impl ResponseIndices {
+  /// The `Response` at these indices in `slice` like `RequestIndices::request`
+  #[inline]
+  pub fn response<'a, 'headers>(&self, slice: &'a [u8], header_indices: &[HeaderIndices], headers_buf: &'headers mut [Header<'a>]) -> Result<Response<'a, 'headers>> {
+    let count = rehydrate_headers(slice, header_indices, self.headers, headers_buf)?;
+    let reason = str::from_utf8(range(slice, &self.reason)?).map_err(|_| Error::Malformed)?;
+    Ok(Response::new(self.status, reason, &headers_buf[..count], range(slice, &self.body)?))
+  }
+}
+
+
+#[inline]
+// the range of `part` inside `slice`, an Err(Error::Malformed) if it doesn't fit in a u32
+// empty parts such as a missing reason phrase may not point into the slice, they get an empty range at the start
+pub(crate) fn indices(slice: &[u8], part: &[u8]) -> Result<Range<u32>> {
+  let start = (part.as_ptr() as usize).checked_sub(slice.as_ptr() as usize).filter(|start| *start <= slice.len());
+  let Some(start) = start else {
+    return if part.is_empty() {Ok(0..0)} else {Err(Error::Malformed)};
+  };
+  let start = u32::try_from(start).map_err(|_| Error::Malformed)?;
+  let end = u32::try_from(part.len()).ok().and_then(|len| start.checked_add(len)).ok_or(Error::Malformed)?;
+  Ok(start..end)
+}
+
+#[inline]
+// parses the header lines of `slice` starting at `offset` into `buf`, returns the offset of the body and the number of headers
+pub(crate) fn parse_header_indices(slice: &[u8], mut offset: usize, buf: &mut [HeaderIndices]) -> Result<(usize, usize)> {
+  let mut count = 0;
+  loop {
+    let rest = &slice[offset..];
+    if rest.starts_with(b"\r\n") {return Ok((offset + 2, count));}
+    if partial_terminator(rest) {return Err(Error::Incomplete { needed: None });}
+    let (header, read) = Header::parse_line(rest)?;
+    let slot = buf.get_mut(count).ok_or(Error::TooManyHeaders)?;
+    *slot = HeaderIndices { name: indices(slice, header.name.as_bytes())?, val: indices(slice, header.val)? };
+    count += 1;
+    offset += read;
+  }
+}
+
+#[inline]
+fn range<'a>(slice: &'a [u8], range: &Range<u32>) -> Result<&'a [u8]> {
+  slice.get((range.start as usize)..(range.end as usize)).ok_or(Error::Malformed)
+}
+
+#[inline]
+fn rehydrate_headers<'a>(slice: &'a [u8], header_indices: &[HeaderIndices], count: usize, headers_buf: &mut [Header<'a>]) -> Result<usize> {
+  let header_indices = header_indices.get(..count).ok_or(Error::Malformed)?;
+  if count > headers_buf.len() {return Err(Error::TooManyHeaders);}
+  for (header, indices) in headers_buf.iter_mut().zip(header_indices) {
+    let name = str::from_utf8(range(slice, &indices.name)?).map_err(|_| Error::Malformed)?;
+    *header = Header::new(name, range(slice, &indices.val)?);
+  }
+  Ok(count)
+}
@@ -0,0 +1,243 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Flagging anomalies in parsed requests that are cheap to see for a parser, as a building block for request filters
+//!
+//! This is not a web application firewall, the built-in rules only look at how the request is encoded.
+//! More rules can be added as closures receiving the parsed request
+//!
+//! ```rust
+//! use htpp::{Request, EMPTY_HEADER, inspect::{inspect, Part, RuleSet, ENCODED_CRLF}};
+//!
+//! let mut headers = [EMPTY_HEADER; 10];
+//! let req = Request::parse(b"GET /a%0d%0aSet-Cookie:x HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+//! let findings = inspect(&req, &RuleSet::new());
+//! let finding = findings.iter().next().unwrap();
+//! assert!(finding.rule == ENCODED_CRLF && finding.part == Part::Target && finding.offset == 2);
+//! ```
+
+use crate::framing::transfer_codings;
+use crate::uri::percent_byte;
+use crate::{Header, Request};
+
+
+/// A percent-encoded CR or LF in the target, which could split a header line if decoded into one
+pub const ENCODED_CRLF: &str = "encoded-crlf";
+/// A percent-encoded overlong UTF-8 sequence in the target, such as `%c0%af` for `/`, used to slip past filters
+pub const OVERLONG_ENCODING: &str = "overlong-encoding";
+/// A `chunked` transfer coding not written in lowercase, which some implementations don't recognize
+pub const CHUNKED_CASE: &str = "chunked-case";
+/// More query parameters than `RuleSet::max_query_params`
+pub const QUERY_PARAM_COUNT: &str = "query-param-count";
+/// A header name that differs only by case from the name of an earlier header
+pub const CASE_DUPLICATE_HEADER: &str = "case-duplicate-header";
+/// A `Content-Length` value that isn't a plain decimal number without leading zeros
+pub const CONTENT_LENGTH_FORM: &str = "content-length-form";
+
+/// How serious a finding is
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum Severity {
+  /// Unusual but seen from legitimate clients
+  Low,
+  /// Likely to be handled differently by different implementations
+  Medium,
+  /// Rarely sent for any reason other than an attack
+  High,
+}
+
+/// The part of the request a finding is in
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Part {
+  /// The request target
+  Target,
+  /// The name of the header at this index of `Request::headers`
+  HeaderName(usize),
+  /// The value of the header at this index of `Request::headers`
+  HeaderValue(usize),
+  /// The body
+  Body,
+}
+
+/// An anomaly found by a rule
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Finding {
+    /// The id of the rule, one of the constants of this module for the built-in rules
+    pub rule: &'static str,
+    /// How serious the finding is
+    pub severity: Severity,
+    /// The part of the request the finding is in
+    pub part: Part,
+    /// The offset of the finding in the bytes of `part`
+    pub offset: usize,
+}
+
+/// The findings of an inspection in the order the rules found them
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct Findings {
+    findings: Vec<Finding>,
+}
+impl Findings {
+  /// Adds a finding, for custom rules
+  #[inline]
+  pub fn push(&mut self, finding: Finding) {
+    self.findings.push(finding);
+  }
+
+  /// An iterator over the findings
+  #[inline]
+  pub fn iter(&self) -> impl Iterator<Item = &Finding> {
+    self.findings.iter()
+  }
+
+  /// Whether nothing was found
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.findings.is_empty()
+  }
+
+  /// The number of findings
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.findings.len()
+  }
+
+  /// The highest severity of the findings, `None` if nothing was found
+  #[inline]
+  pub fn max_severity(&self) -> Option<Severity> {
+    self.findings.iter().map(|finding| finding.severity).max()
+  }
+
+  #[inline]
+  fn add(&mut self, rule: &'static str, severity: Severity, part: Part, offset: usize) {
+    self.push(Finding { rule, severity, part, offset });
+  }
+}
+
+/// A custom rule, it receives the parsed request and adds its findings
+pub type Rule<'r> = Box<dyn Fn(&Request, &mut Findings) + 'r>;
+
+/// The rules `inspect` runs, the built-in rules and any custom rules added with `RuleSet::with_rule`
+pub struct RuleSet<'r> {
+    /// The number of query parameters above which `QUERY_PARAM_COUNT` is reported
+    pub max_query_params: usize,
+    disabled: Vec<&'static str>,
+    rules: Vec<Rule<'r>>,
+}
+impl<'r> RuleSet<'r> {
+  /// All built-in rules with a limit of 64 query parameters and no custom rules
+  #[inline]
+  pub fn new() -> Self {
+    Self { max_query_params: 64, disabled: Vec::new(), rules: Vec::new() }
+  }
+
+  /// Disables the built-in rule with the given id
+  #[inline]
+  pub fn disable(mut self, rule: &'static str) -> Self {
+    self.disabled.push(rule);
+    self
+  }
+
+  /// Adds a custom rule, custom rules run after the built-in rules in the order they were added
+  #[inline]
+  pub fn with_rule(mut self, rule: impl Fn(&Request, &mut Findings) + 'r) -> Self {
+    self.rules.push(Box::new(rule));
+    self
+  }
+
+  #[inline]
+  fn enabled(&self, rule: &str) -> bool {
+    !self.disabled.contains(&rule)
+  }
+}
+impl<'r> Default for RuleSet<'r> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+
+/// Runs the rules over the request and returns what they found
+#[inline]
+pub fn inspect(req: &Request, rules: &RuleSet) -> Findings {
+  let mut findings = Findings::default();
+  let target = req.path.as_bytes();
+  if rules.enabled(ENCODED_CRLF) || rules.enabled(OVERLONG_ENCODING) {
+    inspect_encodings(target, rules, &mut findings);
+  }
+  if rules.enabled(QUERY_PARAM_COUNT) {
+    inspect_query(target, rules.max_query_params, &mut findings);
+  }
+  for (index, header) in req.headers.iter().enumerate() {
+    if rules.enabled(CASE_DUPLICATE_HEADER) && req.headers[..index].iter()
+      .any(|earlier| earlier.name != header.name && earlier.name.eq_ignore_ascii_case(header.name)) {
+      findings.add(CASE_DUPLICATE_HEADER, Severity::Medium, Part::HeaderName(index), 0);
+    }
+    if rules.enabled(CHUNKED_CASE) && header.name.eq_ignore_ascii_case("Transfer-Encoding") {
+      for coding in transfer_codings(std::slice::from_ref(header)) {
+        if coding.eq_ignore_ascii_case(b"chunked") && coding != b"chunked" {
+          let offset = coding.as_ptr() as usize - header.val.as_ptr() as usize;
+          findings.add(CHUNKED_CASE, Severity::Medium, Part::HeaderValue(index), offset);
+        }
+      }
+    }
+    if rules.enabled(CONTENT_LENGTH_FORM) && header.name.eq_ignore_ascii_case("Content-Length") {
+      if let Some(offset) = non_canonical_length(header.val) {
+        findings.add(CONTENT_LENGTH_FORM, Severity::Medium, Part::HeaderValue(index), offset);
+      }
+    }
+  }
+  for rule in &rules.rules {
+    rule(req, &mut findings);
+  }
+  findings
+}
+
+
+#[inline]
+// reports percent-encoded CR and LF and overlong UTF-8 sequences in the target
+fn inspect_encodings(target: &[u8], rules: &RuleSet, findings: &mut Findings) {
+  for offset in (0..target.len()).filter(|offset| target[*offset] == b'%') {
+    let Some(byte) = percent_byte(target, offset) else {continue;};
+    if matches!(byte, b'\r' | b'\n') && rules.enabled(ENCODED_CRLF) {
+      findings.add(ENCODED_CRLF, Severity::High, Part::Target, offset);
+    }
+    if rules.enabled(OVERLONG_ENCODING) {
+      // a C0 or C1 lead byte is always overlong, E0 and F0 are overlong with a continuation byte below A0 and 90
+      let overlong = match byte {
+        0xc0 | 0xc1 => true,
+        0xe0 => percent_byte(target, offset + 3).is_some_and(|next| (0x80..0xa0).contains(&next)),
+        0xf0 => percent_byte(target, offset + 3).is_some_and(|next| (0x80..0x90).contains(&next)),
+        _ => false,
+      };
+      if overlong {
+        findings.add(OVERLONG_ENCODING, Severity::High, Part::Target, offset);
+      }
+    }
+  }
+}
+
+#[inline]
+// reports the first query parameter past the limit
+fn inspect_query(target: &[u8], max: usize, findings: &mut Findings) {
+  let Some(start) = target.iter().position(|character| *character == b'?') else {return;};
+  let mut offset = start + 1;
+  for (index, param) in target[offset..].split(|character| *character == b'&').enumerate() {
+    if index == max {
+      findings.add(QUERY_PARAM_COUNT, Severity::Low, Part::Target, offset);
+      return;
+    }
+    offset += param.len() + 1;
+  }
+}
+
+#[inline]
+// the offset of the first byte making the length non-canonical, such as a leading zero or anything but a digit
+fn non_canonical_length(val: &[u8]) -> Option<usize> {
+  if let Some(offset) = val.iter().position(|character| !character.is_ascii_digit()) {return Some(offset);}
+  if val.is_empty() {return Some(0);}
+  (val.len() > 1 && val[0] == b'0').then_some(0)
+}
@@ -18,11 +18,13 @@
 //! ```rust
 //! use htpp::{Request, EMPTY_HEADER};
 //! 
-//! let req = b"GET /index.html HTTP/1.1\r\n\r\n";
+//! let req = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
 //! let mut headers = [EMPTY_HEADER; 10];
 //! let parsed = Request::parse(req, &mut headers).unwrap();
 //! assert!(parsed.method == htpp::Method::Get);
 //! assert!(parsed.path == "/index.html");
+//! // only the parsed headers are kept, not the whole buffer
+//! assert!(parsed.headers.len() == 1);
 //! ```
 //! You can create a request as follows:
 //! 
@@ -62,31 +64,57 @@
 //! After parsing a request, you can also parse the path part of the request inclusing query parameters as follows:
 //! 
 //! ```rust
-//! use htpp::{Request, EMPTY_QUERY, Url, EMPTY_HEADER};
+//! use htpp::{Request, EMPTY_QUERY, EMPTY_HEADER};
 //! 
 //! let req = b"GET /index.html?query1=value&query2=value HTTP/1.1\r\n\r\n";
 //! let mut headers = [EMPTY_HEADER; 10];
 //! let parsed_req = Request::parse(req, &mut headers).unwrap();
 //! let mut queries_buf = [EMPTY_QUERY; 10];
-//! let url = Url::parse(parsed_req.path.as_bytes(), &mut queries_buf).unwrap();
+//! let url = parsed_req.url(&mut queries_buf).unwrap();
 //! assert!(url.path == "/index.html");
 //! assert!(url.query_params.unwrap()[0].name == "query1");
 //! assert!(url.query_params.unwrap()[0].val == "value");
+//! // the raw query needs no buffer
+//! assert!(parsed_req.query_str() == Some("query1=value&query2=value"));
 //! ```
 //! 
 
 use core::{str, fmt};
+use std::borrow::Cow;
 
 
 #[cfg(test)]
 mod tests;
 mod request;
 mod response;
-mod uri;
+mod parser;
+pub mod uri;
+pub mod fields;
+pub mod text;
+pub mod fetch_metadata;
+pub mod sig;
+pub mod framing;
+pub mod content_disposition;
+pub mod host;
+pub mod indices;
+pub mod canonical;
+pub mod h2;
+#[cfg(feature = "fmt")]
+pub mod pretty;
+pub mod normalize;
+pub mod inspect;
+pub mod date;
+pub mod stats;
+#[cfg(any(test, feature = "test-util"))]
+pub mod conformance;
+#[cfg(any(test, feature = "ffi"))]
+pub mod ffi;
 
-pub use request::{Method, Request};
-pub use response::Response;
-pub use uri::{Url, QueryParam, EMPTY_QUERY, UrlError};
+pub use request::{Method, Request, PartialRequest, ParseOutcome, ParseState};
+pub use response::{Response, KeepAlive};
+pub use parser::{RequestParser, ParseProgress};
+pub use fields::{Header, EMPTY_HEADER};
+pub use uri::{Url, OwnedUrl, QueryParam, EMPTY_QUERY, UrlError, Origin, TargetForm};
 
 
 const SPACE: u8 = 32;
@@ -98,12 +126,31 @@ const HTAB: u8 = 9;
 pub type Result<T> = std::result::Result<T, Error>;
 
 macro_rules! byte_map {
-    ($($flag:expr,)*) => ([
-        $($flag != 0,)*
-    ])
+    ($($flag:expr,)*) => (ByteSet::pack([
+        $($flag,)*
+    ]))
 }
 
-static URL_SAFE: [bool; 256] = byte_map! [
+// a set of bytes packed one bit per byte value, 32 bytes instead of the 256 of a [bool; 256]
+struct ByteSet([u32; 8]);
+
+impl ByteSet {
+  const fn pack(flags: [u8; 256]) -> ByteSet {
+    let mut words = [0u32; 8];
+    let mut byte = 0;
+    while byte < 256 {
+      if flags[byte] != 0 {words[byte / 32] |= 1 << (byte % 32);}
+      byte += 1;
+    }
+    ByteSet(words)
+  }
+  #[inline]
+  fn contains(&self, byte: u8) -> bool {
+    self.0[(byte >> 5) as usize] & (1 << (byte & 31)) != 0
+  }
+}
+
+static URL_SAFE: ByteSet = byte_map! [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 //  \w !  "  #  $  %  &  '  (  )  *  +  ,  -  .  /
@@ -129,7 +176,7 @@ static URL_SAFE: [bool; 256] = byte_map! [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
-static HEADER_NAME_SAFE: [bool; 256] = byte_map![
+static HEADER_NAME_SAFE: ByteSet = byte_map![
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 //  \w !  "  #  $  %  &  '  (  )  *  +  ,  -  .  /
@@ -154,6 +201,32 @@ static HEADER_NAME_SAFE: [bool; 256] = byte_map![
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
+// the tchar bytes of RFC 9110 tokens such as method names
+static TOKEN_SAFE: ByteSet = byte_map![
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+//  \w !  "  #  $  %  &  '  (  )  *  +  ,  -  .  /
+    0, 1, 0, 1, 1, 1, 1, 1, 0, 0, 1, 1, 0, 1, 1, 0,
+//  0  1  2  3  4  5  6  7  8  9  :  ;  <  =  >  ?
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0,
+//  @  A  B  C  D  E  F  G  H  I  J  K  L  M  N  O
+    0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+//  P  Q  R  S  T  U  V  W  X  Y  Z  [  \  ]  ^  _
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1,
+//  `  a  b  c  d  e  f  g  h  i  j  k  l  m  n  o
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+//  p  q  r  s  t  u  v  w  x  y  z  {  |  }  ~  del
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 0, 1, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
 
 
 #[derive(Debug, PartialEq, Eq)]
@@ -162,126 +235,334 @@ pub enum Error{
     /// The request is malformed and doesn't adhere to the standard
     Malformed,
     /// The request has more headers than the length of the buffer you passed
-    TooManyHeaders
+    TooManyHeaders,
+    /// The input ends before the message does, it is valid so far and more bytes may complete it
+    Incomplete {
+        /// The least number of bytes that must follow before parsing can succeed, `None` if it can't be told from the input.
+        /// It is a lower bound, more bytes may still be needed once they arrive
+        needed: Option<usize>,
+    },
+    /// A header line starts with a space or tab, the obsolete line folding continuing the previous value that RFC 9112 lets recipients reject
+    ObsFold,
+    /// The method of the request isn't among the methods passed to `Request::parse_allowed`, holding the method token as sent.
+    /// The token is only copied for an extension method, turn it back into a `Method` with `Method::from_bytes`
+    MethodNotAllowed(Cow<'static, str>),
+    /// The body is longer than `ParseOptions::max_body_size`, the server can answer with 413 Content Too Large
+    BodyTooLarge {
+        /// The length declared by `Content-Length`, `None` for a chunked body found too long while it was decoded
+        declared: Option<usize>,
+        /// The maximum body size of the options
+        limit: usize,
+    },
+}
+impl Error {
+  /// A stable numeric code for the error, meant for telemetry. Codes never change meaning across minor releases.
+  /// The hundreds digit is the category: 1xx structural, 2xx header, 3xx target, 4xx limits
+  ///
+  /// | Code | Error |
+  /// |------|-------|
+  /// | 100 | `Error::Malformed` |
+  /// | 101 | `Error::Incomplete` |
+  /// | 200 | `Error::ObsFold` |
+  /// | 300 | `UrlError::Path` |
+  /// | 301 | `UrlError::Query` |
+  /// | 302 | `UrlError::Authority` |
+  /// | 400 | `Error::TooManyHeaders` |
+  /// | 401 | `UrlError::TooManyQueryParams` |
+  /// | 402 | `Error::MethodNotAllowed` |
+  /// | 403 | `Error::BodyTooLarge` |
+  #[inline]
+  pub fn code(&self) -> u16 {
+    match self {
+      Self::Malformed => 100,
+      Self::Incomplete { .. } => 101,
+      Self::ObsFold => 200,
+      Self::TooManyHeaders => 400,
+      Self::MethodNotAllowed(_) => 402,
+      Self::BodyTooLarge { .. } => 403,
+    }
+  }
+
+  /// The least number of bytes needed to complete an `Error::Incomplete` message, `None` for other errors or if it can't be told.
+  /// `Request::parse` and `Response::parse` report the bytes still missing from the `\r\n\r\n` ending the header section,
+  /// and `Request::parse_exact` the bytes still missing from a body declared by `Content-Length`
+  ///
+  /// ```rust
+  /// use htpp::{Request, EMPTY_HEADER};
+  ///
+  /// let mut headers = [EMPTY_HEADER; 10];
+  /// let err = Request::parse_exact(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nhello", &mut headers).unwrap_err();
+  /// assert!(err.needed() == Some(5));
+  /// ```
+  #[inline]
+  pub fn needed(&self) -> Option<usize> {
+    match self {
+      Self::Incomplete { needed } => *needed,
+      _ => None,
+    }
+  }
+
+  /// The category of the error, it matches the hundreds digit of `code`
+  #[inline]
+  pub fn category(&self) -> ErrorCategory {
+    ErrorCategory::from_code(self.code())
+  }
 }
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("malformed request")
+        let err = match self {
+            Self::Incomplete { .. } => "incomplete message",
+            Self::ObsFold => "obsolete line folding",
+            Self::MethodNotAllowed(_) => "method not allowed",
+            Self::BodyTooLarge { .. } => "body too large",
+            _ => "malformed request",
+        };
+        f.write_str(err)
     }
 }
 impl std::error::Error for Error {}
 
-
-#[derive(Debug, PartialEq, Eq)]
-/// Possible http versions
-pub enum HttpVer {
-    /// Http 1.1
-    One,
-    /// Http 2.0
-    Two,
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// All errors that could result from serializing a message with a checked method such as `Response::try_as_bytes`
+pub enum EncodeError {
+    /// The start line holds a byte that would end it early, such as a CR or LF in the reason phrase or a space in the path
+    IllegalStartLine,
+    /// The header at `header_index` has a name that isn't a token or a value holding a control character other than a tab
+    IllegalByte {
+        /// The index of the offending header
+        header_index: usize,
+    },
+    /// The `Content-Length` header doesn't match the length of the body, the peer would wait for more bytes or cut the body short
+    LengthMismatch {
+        /// The length declared by the header
+        declared: usize,
+        /// The length of the body
+        actual: usize,
+    },
 }
-impl fmt::Display for HttpVer {
+impl fmt::Display for EncodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let version = match self {
-            Self::One => "HTTP/1.1",
-            Self::Two => "HTTP/2.0"
-        };
-        f.write_str(version)
+        match self {
+            Self::IllegalStartLine => f.write_str("illegal byte in the start line"),
+            Self::IllegalByte { header_index } => write!(f, "illegal byte in header {header_index}"),
+            Self::LengthMismatch { declared, actual } => write!(f, "content-length of {declared} for a body of {actual} bytes"),
+        }
     }
 }
+impl std::error::Error for EncodeError {}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The category of an `Error` or `UrlError`, the hundreds digit of its code
+pub enum ErrorCategory {
+    /// The message doesn't follow the HTTP syntax, codes 1xx
+    Structural,
+    /// A header is invalid, codes 2xx
+    Header,
+    /// The request target or URL is invalid, codes 3xx
+    Target,
+    /// A caller provided buffer or limit was exceeded, codes 4xx
+    Limit,
+}
+impl ErrorCategory {
+  #[inline]
+  fn from_code(code: u16) -> Self {
+    match code / 100 {
+      2 => Self::Header,
+      3 => Self::Target,
+      4 => Self::Limit,
+      _ => Self::Structural,
+    }
+  }
+}
 
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+/// Options to relax or tighten parsing, the default options are strict
+pub struct ParseOptions {
+    /// Accept lines terminated by a bare `\n` instead of `\r\n`, some legacy peers send them
+    pub allow_bare_lf: bool,
+    /// Reject response status lines without a reason phrase, such as `HTTP/1.1 200\r\n`
+    pub require_reason: bool,
+    /// Reject response status lines whose reason phrase differs from the canonical one of a registered status code,
+    /// such as `HTTP/1.1 200 Not Found`. Comparison ignores ASCII case, and unregistered codes and empty reasons are accepted
+    pub require_canonical_reason: bool,
+    /// Skip empty lines before the request line, such as the `\r\n` some clients send after the body of a previous request.
+    /// RFC 9112 says a server SHOULD ignore at least one of them, but they are rejected by default
+    pub allow_leading_crlf: bool,
+    /// The most bytes a body may have, checked against `Content-Length` before any of the body is read and summed up over the chunks
+    /// of a chunked body. Only the entry points that frame the body, such as `Request::parse_exact_with_options` and
+    /// `framing::decode_chunked_with_options`, check it, parsing the head alone never fails because of it
+    pub max_body_size: Option<usize>,
+}
 
-// ---------------------
-// Parsing HTTP headers
-// ---------------------
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+/// Options for the checked serialization of `Request::try_as_bytes_with_options` and `Response::try_as_bytes_with_options`
+pub struct EncodeOptions {
+    /// Write a `Content-Length` header matching the body instead of checking it, replacing any sent one.
+    /// Messages with a `Transfer-Encoding` header are written as is
+    pub auto_content_length: bool,
+    /// The body is left out on purpose while `Content-Length` tells the length it would have,
+    /// as in a response to a HEAD request or a 304 Not Modified. The header is neither checked nor replaced if the body is empty
+    pub bodiless: bool,
+}
 
-/// An empty header to make it easier to construct a header buffer to parse headers into
-pub const EMPTY_HEADER: Header = Header{name: "", val: &[]};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
-/// An HTTP header
-pub struct Header<'a> {
-    /// The name of the header
-    pub name: &'a str,
-    /// The value of the header
-    pub val: &'a [u8],
+/// Possible http versions
+pub enum HttpVer {
+    /// Http 1.0, only parsed by `HttpVer::parse`, requests and responses must be HTTP/1.1 or HTTP/2.0
+    OneZero,
+    /// Http 1.1
+    One,
+    /// Http 2.0
+    Two,
 }
-impl<'a> fmt::Display for Header<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if !self.name.is_empty() {
-            let header = match str::from_utf8(self.val) {
-                Ok(v) => {
-                    format!("{}: {v}", self.name)
-                },
-                Err(_) => {
-                    format!("{}: {:?}", self.name, self.val)
-                },
-            };
-            return f.write_str(header.as_str());
-        };
-        f.write_str("")
+impl HttpVer {
+  /// Parses the `HTTP/x.y` version token at the start of the slice and returns the version and the bytes read, always 8.
+  /// Nothing after the token is consumed, the caller checks the delimiter that follows it.
+  /// Returns an Err(Error::Incomplete) if the slice ends inside a supported version, or an Err(Error::Malformed) for any other version such as `HTTP/1.2`
+  ///
+  /// ```rust
+  /// use htpp::{Error, HttpVer};
+  ///
+  /// assert!(HttpVer::parse(b"HTTP/1.1 200 OK") == Ok((HttpVer::One, 8)));
+  /// assert!(HttpVer::parse(b"HTTP/1.0") == Ok((HttpVer::OneZero, 8)));
+  /// assert!(HttpVer::parse(b"HTTP/1.") == Err(Error::Incomplete { needed: None }));
+  /// assert!(HttpVer::parse(b"HTTP/1.2") == Err(Error::Malformed));
+  /// ```
+  #[inline]
+  pub fn parse(slice: &[u8]) -> Result<(HttpVer, usize)> {
+    let mut incomplete = false;
+    for version in [HttpVer::OneZero, HttpVer::One, HttpVer::Two] {
+      let token = version.as_bytes();
+      if slice.starts_with(token) {
+        return Ok((version, token.len()));
+      }
+      incomplete |= token.starts_with(slice);
     }
-}
-impl<'a> Header<'a> {
-  /// Create a new HTTP header with the given name and value
-  pub fn new(name: &'a str, val: &'a [u8]) -> Self {
-    Self {
-        name,
-        val
+    if incomplete {Err(Error::Incomplete { needed: None })} else {Err(Error::Malformed)}
+  }
+
+  /// The version token as sent on the wire such as `HTTP/1.1`
+  #[inline]
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::OneZero => "HTTP/1.0",
+      Self::One => "HTTP/1.1",
+      Self::Two => "HTTP/2.0",
     }
   }
+
+  /// The version token as sent on the wire like `as_str`
+  #[inline]
+  pub fn as_bytes(&self) -> &'static [u8] {
+    self.as_str().as_bytes()
+  }
+}
+#[cfg(feature = "fmt")]
+impl fmt::Display for HttpVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
+
+
 #[inline]
-// Parses the headers into the passed headers_buf
-fn parse_headers<'a>(slice: &'a[u8], headers_buf: &mut [crate::Header<'a>]) -> Result<usize> {
-  let mut offset = 0;
-  let mut iteration = 0;
-  while &slice[offset..(offset+2)] != b"\r\n" {
-    if iteration >= headers_buf.len() {return Err(Error::TooManyHeaders);}
-    let name = parse_header_name(&slice[offset..])?;
-    offset += name.1;
-    let val = parse_header_value(&slice[offset..])?;
-    offset += val.1;
-    headers_buf[iteration] = Header::new(name.0, val.0);
-    iteration += 1;
-  }
-  Ok(offset+2)
+// finds the first header with the given name, header names are case-insensitive
+fn find_header<'h, 'a>(headers: &'h [Header<'a>], name: &str) -> Option<&'h Header<'a>> {
+  headers.iter().find(|header| header.name.eq_ignore_ascii_case(name))
 }
+
 #[inline]
-// parses the header name and removes the `:` character and any spaces after it
-fn parse_header_name(slice: &[u8]) -> Result<(&str, usize)> {
-  for (counter, character) in slice.iter().enumerate() {
-    if HEADER_NAME_SAFE[*character as usize] {
-      continue;
-    } else if *character == COLON {
-      let name = &slice[..counter];
-      if slice[counter+1] == SPACE || slice[counter+1] == 9 {
-        //SAFETY: already checked that the input is valid ascii
-        return Ok( (unsafe { std::str::from_utf8_unchecked(name) }, counter+2));
-      }
-      //SAFETY: already checked that the input is valid ascii
-      return Ok( (unsafe { std::str::from_utf8_unchecked(name) }, counter+1));
+// the value of the Content-Length headers or None if there is none, differing or invalid values are malformed
+fn content_length(headers: &[Header]) -> Result<Option<usize>> {
+  let mut length = None;
+  for header in headers.iter().filter(|header| header.name.eq_ignore_ascii_case("Content-Length")) {
+    if header.val.is_empty() || header.val.len() > 19 || !header.val.iter().all(u8::is_ascii_digit) {
+      return Err(Error::Malformed);
     }
-    return Err(Error::Malformed);
+    let val = header.val.iter().fold(0usize, |length, digit| length * 10 + (digit - b'0') as usize);
+    if length.is_some_and(|length| length != val) {return Err(Error::Malformed);}
+    length = Some(val);
   }
-  unreachable!();
+  Ok(length)
 }
+
 #[inline]
-fn parse_header_value(slice: &[u8]) -> Result<(&[u8], usize)> {
-  for (counter, character) in slice.iter().enumerate() {
-    if *character == CR {
-      let val = &slice[..counter];
-      if slice[counter+1] == LF {
-        return Ok((val, counter+2));
+// the length of a chunked body including the last chunk and the trailer section
+fn chunked_body_len(slice: &[u8], max_len: Option<usize>) -> Result<usize> {
+  walk_chunks(slice, max_len, |_| Ok(()))
+}
+
+#[inline]
+// walks the chunks of a chunked body passing the data of each to `on_chunk`, and returns the length of the body
+// including the last chunk and the trailer section. Chunk extensions and trailer fields are skipped.
+// Stops with an Err(Error::BodyTooLarge) as soon as a size line takes the data past `max_len`, before the chunk is read,
+// and with an Err(Error::Incomplete) if the slice ends before the body does but is valid so far
+pub(crate) fn walk_chunks(slice: &[u8], max_len: Option<usize>, mut on_chunk: impl FnMut(&[u8]) -> Result<()>) -> Result<usize> {
+  let mut offset = 0;
+  let mut total = 0usize;
+  loop {
+    let line = &slice[offset..];
+    // chunk extensions after `;` are ignored
+    let size = line.split(|character| *character == b';' || *character == CR).next().unwrap_or_default();
+    if size.len() > 15 || !size.iter().all(u8::is_ascii_hexdigit) {return Err(Error::Malformed);}
+    // the size ends the line or is followed by its extensions
+    let after_size = &line[size.len()..];
+    let line_end = match find_crlf(line) {
+      Ok(line_end) => line_end,
+      Err(_) if !line.contains(&LF) && matches!(after_size, [] | [CR] | [b';', ..]) => return Err(Error::Incomplete { needed: None }),
+      Err(err) => return Err(err),
+    };
+    if size.is_empty() || !(size.len() == line_end || after_size.starts_with(b";")) {return Err(Error::Malformed);}
+    let size = usize::from_str_radix(str::from_utf8(size).map_err(|_| Error::Malformed)?, 16).map_err(|_| Error::Malformed)?;
+    offset += line_end + 2;
+    if size == 0 {break;}
+    total = total.saturating_add(size);
+    if let Some(limit) = max_len.filter(|limit| total > *limit) {
+      return Err(Error::BodyTooLarge { declared: None, limit });
+    }
+    let chunk_end = offset.checked_add(size).and_then(|end| end.checked_add(2)).ok_or(Error::Malformed)?;
+    if slice.len() < chunk_end {
+      // the data is cut off, or its CRLF is and what there is of it is right
+      if slice.len() <= offset + size || b"\r\n".starts_with(&slice[(offset + size)..]) {
+        return Err(Error::Incomplete { needed: Some(chunk_end - slice.len()) });
       }
       return Err(Error::Malformed);
     }
+    if &slice[(offset + size)..chunk_end] != b"\r\n" {return Err(Error::Malformed);}
+    on_chunk(&slice[offset..(offset + size)])?;
+    offset = chunk_end;
+  }
+  // the trailer section ends with an empty line
+  loop {
+    let line_end = match find_crlf(&slice[offset..]) {
+      Ok(line_end) => line_end,
+      Err(_) if !slice[offset..].contains(&LF) => return Err(Error::Incomplete { needed: None }),
+      Err(err) => return Err(err),
+    };
+    offset += line_end + 2;
+    if line_end == 0 {return Ok(offset);}
   }
-  Err(Error::Malformed)
 }
 
+#[inline]
+// fills in the bytes still missing from the `\r\n\r\n` ending the header section of an incomplete message
+// the terminator less what the slice already ends with is a lower bound, a line may still be unfinished
+fn head_incomplete(err: Error, slice: &[u8]) -> Error {
+  const TERMINATOR: &[u8] = b"\r\n\r\n";
+  match err {
+    Error::Incomplete { needed: None } => {
+      let matched = (1..TERMINATOR.len()).rev().find(|len| slice.ends_with(&TERMINATOR[..*len])).unwrap_or(0);
+      Error::Incomplete { needed: Some(TERMINATOR.len() - matched) }
+    },
+    err => err,
+  }
+}
 
-
+#[inline]
+fn find_crlf(slice: &[u8]) -> Result<usize> {
+  slice.windows(2).position(|window| window == b"\r\n").ok_or(Error::Malformed)
+}
@@ -0,0 +1,221 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Normalizing messages to a canonical byte form, such as before storing captured messages in a corpus
+//!
+//! ```rust
+//! use htpp::normalize::{message, NormalizeOptions};
+//!
+//! let mut out = Vec::new();
+//! let report = message(b"GET  / HTTP/1.1\nHost:example.com \n\n", &NormalizeOptions::default(), &mut out).unwrap();
+//! assert!(out == b"GET / HTTP/1.1\r\nhost: example.com\r\n\r\n");
+//! assert!(report.line_endings && report.separators && report.whitespace_trimmed && report.names_recased);
+//! ```
+
+use crate::framing::{decode_chunked, is_chunked, transfer_codings};
+use crate::{Error, Header, Request, Response, Result, EMPTY_HEADER};
+
+
+/// How header names are cased in the normalized message
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub enum NameCase {
+  /// All lowercase such as `content-length`, the form HTTP/2 uses
+  #[default]
+  Lower,
+  /// Every `-` separated word capitalized such as `Content-Length`
+  Canonical,
+  /// As sent
+  AsSent,
+}
+
+/// Which transformations `message` applies
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct NormalizeOptions {
+    /// How header names are cased
+    pub name_case: NameCase,
+    /// Sort the headers by lowercased name, headers with the same name keep their order
+    pub sort_headers: bool,
+    /// Decode a chunked body and frame it with a `Content-Length` header instead. A body with other transfer codings
+    /// such as `gzip, chunked` is left chunked, RFC 9112 section 6.3 doesn't allow `Content-Length` next to `Transfer-Encoding`
+    pub dechunk: bool,
+}
+impl Default for NormalizeOptions {
+  fn default() -> Self {
+    Self { name_case: NameCase::Lower, sort_headers: false, dechunk: true }
+  }
+}
+
+/// The transformations `message` applied, each flag is set only if the message was changed by it
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct NormalizeReport {
+    /// Bare `\n` line endings were replaced by `\r\n`
+    pub line_endings: bool,
+    /// Runs of whitespace separating the first three parts of the start line were replaced by a single space.
+    /// The rest of the line, such as a reason phrase, is kept as sent
+    pub separators: bool,
+    /// Obsolete line folding was replaced by a single space, lossy for values that depended on the line break
+    pub unfolded: bool,
+    /// Whitespace around header values was removed, or a single space added after the colon
+    pub whitespace_trimmed: bool,
+    /// Header names were recased
+    pub names_recased: bool,
+    /// A chunked body was decoded and framed with `Content-Length`, lossy as chunk extensions and trailer fields are dropped
+    pub dechunked: bool,
+    /// The headers were reordered
+    pub headers_reordered: bool,
+}
+impl NormalizeReport {
+  /// Whether the message was already in normal form
+  #[inline]
+  pub fn is_unchanged(&self) -> bool {
+    *self == NormalizeReport::default()
+  }
+}
+
+
+/// Writes the normal form of the request or response in `input` to `out` and reports which transformations changed it.
+/// The normal form has `\r\n` line endings, a single space between the first three parts of the start line, headers written as `name: value`
+/// with trimmed values and no line folding, and names cased as configured. Normalizing a normalized message doesn't change it.
+///
+/// Returns an Err(Error::Incomplete) if the input ends before the header section does,
+/// or an Err(Error::Malformed) if the message can't be parsed, or a chunked body is invalid or followed by more bytes.
+/// On error `out` is left unchanged
+#[inline]
+pub fn message(input: &[u8], opts: &NormalizeOptions, out: &mut Vec<u8>) -> Result<NormalizeReport> {
+  let mut report = NormalizeReport::default();
+  let (head, body_start) = normalize_head(input, &mut report)?;
+  let mut headers = vec![EMPTY_HEADER; head.iter().filter(|character| **character == b'\n').count()];
+  let (start_line, headers) = if head.starts_with(b"HTTP/") {
+    let res = Response::parse(&head, &mut headers)?;
+    (head_start_line(&head), res.headers)
+  } else {
+    let req = Request::parse(&head, &mut headers)?;
+    (head_start_line(&head), req.headers)
+  };
+  let mut body = &input[body_start..];
+  let mut decoded = Vec::new();
+  let mut fields: Vec<(String, Vec<u8>)> = headers.iter().map(|header| (header.name.to_string(), header.val.to_vec())).collect();
+  // only a body chunked and nothing else can be framed with `Content-Length`
+  if opts.dechunk && is_chunked(headers) && transfer_codings(headers).count() == 1 {
+    if decode_chunked(body, &mut decoded)? != body.len() {return Err(Error::Malformed);}
+    body = &decoded;
+    fields.retain(|(name, _)| !name.eq_ignore_ascii_case("Transfer-Encoding") && !name.eq_ignore_ascii_case("Content-Length"));
+    fields.push(("Content-Length".to_string(), body.len().to_string().into_bytes()));
+    report.dechunked = true;
+  }
+  for (name, _) in fields.iter_mut() {
+    let recased = match opts.name_case {
+      NameCase::Lower => name.to_ascii_lowercase(),
+      NameCase::Canonical => canonical_case(name),
+      NameCase::AsSent => continue,
+    };
+    report.names_recased |= recased != *name;
+    *name = recased;
+  }
+  if opts.sort_headers {
+    let sorted = fields.windows(2).all(|pair| pair[0].0.to_ascii_lowercase() <= pair[1].0.to_ascii_lowercase());
+    fields.sort_by_key(|(name, _)| name.to_ascii_lowercase());
+    report.headers_reordered = !sorted;
+  }
+  out.extend_from_slice(start_line);
+  for (name, val) in &fields {
+    out.extend_from_slice(name.as_bytes());
+    out.extend_from_slice(b": ");
+    out.extend_from_slice(val);
+    out.extend_from_slice(b"\r\n");
+  }
+  out.extend_from_slice(b"\r\n");
+  out.extend_from_slice(body);
+  Ok(report)
+}
+
+
+#[inline]
+// the start line including its `\r\n`
+fn head_start_line(head: &[u8]) -> &[u8] {
+  let end = head.iter().position(|character| *character == b'\n').map_or(head.len(), |end| end + 1);
+  &head[..end]
+}
+
+#[inline]
+// rewrites the header section of the input with `\r\n` endings, single spaces in the start line, unfolded lines, and `name: value` headers
+// returns the rewritten header section and the offset of the body in the input
+fn normalize_head(input: &[u8], report: &mut NormalizeReport) -> Result<(Vec<u8>, usize)> {
+  let mut head = Vec::with_capacity(input.len());
+  let mut offset = 0;
+  let mut first = true;
+  let mut has_header = false;
+  loop {
+    let end = input[offset..].iter().position(|character| *character == b'\n').ok_or(Error::Incomplete { needed: None })?;
+    let mut line = &input[offset..(offset + end)];
+    offset += end + 1;
+    match line.strip_suffix(b"\r") {
+      Some(stripped) => line = stripped,
+      None => report.line_endings = true,
+    }
+    if line.is_empty() {
+      head.extend_from_slice(b"\r\n");
+      return Ok((head, offset));
+    }
+    if first {
+      let start = head.len();
+      normalize_start_line(line, &mut head);
+      report.separators |= head[start..] != *line;
+      first = false;
+    } else if line[0] == b' ' || line[0] == b'\t' {
+      // an obs-fold continues the value of the previous header
+      if !has_header {return Err(Error::Malformed);}
+      head.truncate(head.len() - 2);
+      let continuation = line.trim_ascii();
+      if !continuation.is_empty() {
+        if !head.ends_with(b": ") {head.push(b' ');}
+        head.extend_from_slice(continuation);
+      }
+      report.unfolded = true;
+    } else {
+      let colon = line.iter().position(|character| *character == b':').ok_or(Error::Malformed)?;
+      let (name, raw) = (&line[..colon], &line[(colon + 1)..]);
+      let val = raw.trim_ascii();
+      report.whitespace_trimmed |= raw.len() != val.len() + 1 || raw[0] != b' ';
+      head.extend_from_slice(name);
+      head.extend_from_slice(b": ");
+      head.extend_from_slice(val);
+      has_header = true;
+    }
+    head.extend_from_slice(b"\r\n");
+  }
+}
+
+#[inline]
+// writes the start line with each of the two runs of spaces and tabs separating its first three parts replaced by a single space
+// the rest of the line is written as is, a reason phrase may hold any whitespace
+fn normalize_start_line(line: &[u8], head: &mut Vec<u8>) {
+  let is_blank = |character: &u8| *character == b' ' || *character == b'\t';
+  let mut rest = line;
+  for _ in 0..2 {
+    let part_end = rest.iter().position(is_blank).unwrap_or(rest.len());
+    head.extend_from_slice(&rest[..part_end]);
+    rest = &rest[part_end..];
+    let blanks = rest.iter().position(|character| !is_blank(character)).unwrap_or(rest.len());
+    if blanks == 0 {break;}
+    head.push(b' ');
+    rest = &rest[blanks..];
+  }
+  head.extend_from_slice(rest);
+}
+
+#[inline]
+// capitalizes every `-` separated word of the name and lowercases the rest
+fn canonical_case(name: &str) -> String {
+  let mut cased = String::with_capacity(name.len());
+  let mut word_start = true;
+  for character in name.chars() {
+    cased.push(if word_start {character.to_ascii_uppercase()} else {character.to_ascii_lowercase()});
+    word_start = character == '-';
+  }
+  cased
+}
@@ -0,0 +1,158 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+use std::ops::Range;
+
+use crate::{head_incomplete, Error, Header, Method, ParseOptions, Request, Result, LF};
+use crate::indices::{indices, HeaderIndices, RequestIndices};
+use crate::request::parse_request_line;
+
+
+/// What `RequestParser::feed` found in the bytes fed so far
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ParseProgress {
+  /// The request line isn't complete yet
+  NeedMore,
+  /// The request line and this many headers were parsed, the header section isn't complete yet
+  Headers(usize),
+  /// The header section is complete, the request line and headers take this many bytes. Get the request with `RequestParser::request`
+  Complete(usize),
+}
+
+/// An incremental request parser for non-blocking servers, bytes are fed as they arrive.
+/// Every byte is scanned once, parsing continues where the last call to `feed` stopped instead of starting over.
+/// The bytes are copied into the parser so the buffers passed to `feed` can be reused.
+/// The header section is bounded in size and in number of headers, see `RequestParser::with_limits`
+///
+/// ```rust
+/// use htpp::{RequestParser, ParseProgress, EMPTY_HEADER};
+///
+/// let mut parser = RequestParser::new();
+/// assert!(parser.feed(b"GET / HTTP/1.1\r\nHo").unwrap() == ParseProgress::Headers(0));
+/// assert!(parser.feed(b"st: example.com\r\n\r\n").unwrap() == ParseProgress::Complete(37));
+/// let mut headers = [EMPTY_HEADER; 10];
+/// let req = parser.request(&mut headers).unwrap();
+/// assert!(req.headers[0].val == b"example.com");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RequestParser {
+    buf: Vec<u8>,
+    // the start of the first line that wasn't parsed yet
+    line_start: usize,
+    // the offset up to which the current line was searched for its end
+    searched: usize,
+    // the ranges of the method and the request target
+    request_line: Option<(Range<u32>, Range<u32>)>,
+    headers: Vec<HeaderIndices>,
+    head_len: Option<usize>,
+    max_head_size: usize,
+    max_headers: usize,
+}
+impl Default for RequestParser {
+    fn default() -> Self {
+      Self::with_limits(Self::DEFAULT_MAX_HEAD_SIZE, Self::DEFAULT_MAX_HEADERS)
+    }
+}
+impl RequestParser {
+  /// The most bytes the request line and headers may take, including the empty line ending them, unless set with `RequestParser::with_limits`
+  pub const DEFAULT_MAX_HEAD_SIZE: usize = 64 * 1024;
+  /// The most headers a request may have unless set with `RequestParser::with_limits`
+  pub const DEFAULT_MAX_HEADERS: usize = 100;
+
+  /// Creates a parser that has not been fed any bytes, with the default limits
+  #[inline]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a parser that has not been fed any bytes and accepts at most `max_head_size` bytes of request line and headers,
+  /// including the empty line ending them, and at most `max_headers` headers
+  #[inline]
+  pub fn with_limits(max_head_size: usize, max_headers: usize) -> Self {
+    Self {
+      buf: Vec::new(),
+      line_start: 0,
+      searched: 0,
+      request_line: None,
+      headers: Vec::new(),
+      head_len: None,
+      max_head_size,
+      max_headers,
+    }
+  }
+
+  /// Feeds the next bytes of the request to the parser and parses the lines they complete
+  /// Bytes fed after the header section is complete are kept as the start of the body.
+  /// Returns an Err(Error::Malformed) as soon as a complete line is malformed or the header section grows past the maximum size,
+  /// and an Err(Error::TooManyHeaders) once there is more headers than the maximum. The parser should be dropped after an error
+  #[inline]
+  pub fn feed(&mut self, bytes: &[u8]) -> Result<ParseProgress> {
+    self.buf.extend_from_slice(bytes);
+    if let Some(head_len) = self.head_len {return Ok(ParseProgress::Complete(head_len));}
+    while let Some(end) = self.buf[self.searched..].iter().position(|character| *character == LF) {
+      let line_end = self.searched + end + 1;
+      if line_end > self.max_head_size {return Err(Error::Malformed);}
+      let line = &self.buf[self.line_start..line_end];
+      match &self.request_line {
+        None => {
+          let (method, path, read) = parse_request_line(line, &ParseOptions::default())?;
+          if read != line.len() {return Err(Error::Malformed);}
+          self.request_line = Some((indices(&self.buf, &line[..method.token().len()])?, indices(&self.buf, path.as_bytes())?));
+        },
+        Some(_) if line == b"\r\n" => {
+          self.head_len = Some(line_end);
+          return Ok(ParseProgress::Complete(line_end));
+        },
+        Some(_) => {
+          if self.headers.len() >= self.max_headers {return Err(Error::TooManyHeaders);}
+          let (header, read) = Header::parse_line(line)?;
+          if read != line.len() {return Err(Error::Malformed);}
+          self.headers.push(HeaderIndices { name: indices(&self.buf, header.name.as_bytes())?, val: indices(&self.buf, header.val)? });
+        },
+      }
+      self.line_start = line_end;
+      self.searched = line_end;
+    }
+    if self.buf.len() > self.max_head_size {return Err(Error::Malformed);}
+    self.searched = self.buf.len();
+    match self.request_line {
+      None => Ok(ParseProgress::NeedMore),
+      Some(_) => Ok(ParseProgress::Headers(self.headers.len())),
+    }
+  }
+
+  /// The parsed request, borrowing the bytes fed to the parser. The headers are written to `headers_buf` without parsing them again
+  /// Returns an Err(Error::Incomplete) if the header section isn't complete yet,
+  /// or an Err(Error::TooManyHeaders) if there is more headers than the length of `headers_buf`
+  #[inline]
+  pub fn request<'p, 'headers>(&'p self, headers_buf: &'headers mut [Header<'p>]) -> Result<Request<'p, 'headers>> {
+    self.indices()?.request(&self.buf, &self.headers, headers_buf)
+  }
+
+  /// The position of each part of the parsed request in the bytes fed to the parser, like `Request::parse_indices` returns.
+  /// The header positions are in `RequestParser::header_indices`. Returns an Err(Error::Incomplete) if the header section isn't complete yet
+  #[inline]
+  pub fn indices(&self) -> Result<RequestIndices> {
+    let (Some(head_len), Some((method, path))) = (self.head_len, &self.request_line) else {
+      return Err(head_incomplete(Error::Incomplete { needed: None }, &self.buf));
+    };
+    let body = indices(&self.buf, &self.buf[head_len..])?;
+    Ok(RequestIndices { method: method.clone(), path: path.clone(), headers: self.headers.len(), body })
+  }
+
+  /// The position of each header parsed so far in the bytes fed to the parser
+  #[inline]
+  pub fn header_indices(&self) -> &[HeaderIndices] {
+    &self.headers
+  }
+
+  /// The bytes fed to the parser so far
+  #[inline]
+  pub fn buf(&self) -> &[u8] {
+    &self.buf
+  }
+}
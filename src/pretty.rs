@@ -0,0 +1,199 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Pretty printing of messages for debugging, with aligned headers, hex dumps of binary bodies, and annotated chunks
+//!
+//! The dump is streamed to the writer piece by piece and never built in memory. Enabled by the `fmt` feature, on by default
+//!
+//! ```rust
+//! use htpp::{Request, EMPTY_HEADER, pretty::{dump_request, DumpOptions}};
+//!
+//! let mut headers = [EMPTY_HEADER; 10];
+//! let req = Request::parse(b"GET / HTTP/1.1\r\nHost: example.com\r\nCookie: id=1\r\n\r\n", &mut headers).unwrap();
+//! let mut out = String::new();
+//! dump_request(&req, &DumpOptions::default(), &mut out).unwrap();
+//! assert!(out == "GET / HTTP/1.1\nHost   : example.com\nCookie : <redacted>\n");
+//! ```
+
+use std::{fmt, io, str};
+
+use crate::framing::is_chunked;
+use crate::text::{hex_dump, lossy_escape};
+use crate::{walk_chunks, Header, Request, Response};
+
+
+/// The headers whose values are replaced by `<redacted>` in dumps by default, compared case-insensitively
+pub const DEFAULT_REDACTED: &[&str] = &["Authorization", "Proxy-Authorization", "Cookie", "Set-Cookie"];
+
+/// How a message is dumped
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct DumpOptions<'a> {
+    /// Whether to color the start line, header names, and annotations with ANSI escape codes
+    pub color: bool,
+    /// The maximum length of a header line, longer values are cut off with a marker telling how many bytes were left out
+    pub width: usize,
+    /// The maximum number of body bytes dumped, the rest of the body is replaced by a marker telling how many bytes were left out
+    pub max_body_bytes: usize,
+    /// The names of the headers whose values are replaced by `<redacted>`, compared case-insensitively
+    pub redact: &'a [&'a str],
+}
+impl<'a> Default for DumpOptions<'a> {
+  fn default() -> Self {
+    Self { color: false, width: 100, max_body_bytes: 4096, redact: DEFAULT_REDACTED }
+  }
+}
+
+
+/// Writes a readable dump of the request to `out`.
+/// Header names are aligned, text bodies are written as text and binary bodies as a hex dump with 16 bytes per row.
+/// A chunked body has every chunk annotated with its size, the chunk data is dumped like a body
+#[inline]
+pub fn dump_request<W: fmt::Write>(req: &Request, opts: &DumpOptions, out: &mut W) -> fmt::Result {
+  start_line(opts, out, format_args!("{} {} HTTP/1.1", req.method, req.path))?;
+  dump_message(req.headers, req.body, opts, out)
+}
+
+/// Writes a readable dump of the response to `out` like `dump_request`
+#[inline]
+pub fn dump_response<W: fmt::Write>(res: &Response, opts: &DumpOptions, out: &mut W) -> fmt::Result {
+  start_line(opts, out, format_args!("HTTP/1.1 {} {}", res.status, res.reason))?;
+  dump_message(res.headers, res.body, opts, out)
+}
+
+/// Writes the dump of `dump_request` to an `io::Write` such as a terminal
+#[inline]
+pub fn dump_request_io<W: io::Write>(req: &Request, opts: &DumpOptions, out: &mut W) -> io::Result<()> {
+  let mut adapter = IoAdapter { out, err: None };
+  dump_request(req, opts, &mut adapter).map_err(|_| adapter.into_err())
+}
+
+/// Writes the dump of `dump_response` to an `io::Write` such as a terminal
+#[inline]
+pub fn dump_response_io<W: io::Write>(res: &Response, opts: &DumpOptions, out: &mut W) -> io::Result<()> {
+  let mut adapter = IoAdapter { out, err: None };
+  dump_response(res, opts, &mut adapter).map_err(|_| adapter.into_err())
+}
+
+
+const BOLD: &str = "\x1b[1m";
+const CYAN: &str = "\x1b[36m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+// writes to an `io::Write` keeping the first io error, which `fmt::Error` can't carry
+struct IoAdapter<'w, W: io::Write> {
+  out: &'w mut W,
+  err: Option<io::Error>,
+}
+impl<'w, W: io::Write> IoAdapter<'w, W> {
+  fn into_err(self) -> io::Error {
+    self.err.unwrap_or_else(|| io::Error::other("formatting error"))
+  }
+}
+impl<'w, W: io::Write> fmt::Write for IoAdapter<'w, W> {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    self.out.write_all(s.as_bytes()).map_err(|err| {
+      self.err = Some(err);
+      fmt::Error
+    })
+  }
+}
+
+#[inline]
+fn start_line<W: fmt::Write>(opts: &DumpOptions, out: &mut W, line: fmt::Arguments) -> fmt::Result {
+  if opts.color {
+    writeln!(out, "{BOLD}{line}{RESET}")
+  } else {
+    writeln!(out, "{line}")
+  }
+}
+
+#[inline]
+// writes an annotation such as a chunk size or a truncation marker on its own line
+fn annotation<W: fmt::Write>(opts: &DumpOptions, out: &mut W, text: fmt::Arguments) -> fmt::Result {
+  if opts.color {
+    writeln!(out, "{DIM}-- {text} --{RESET}")
+  } else {
+    writeln!(out, "-- {text} --")
+  }
+}
+
+#[inline]
+fn dump_message<W: fmt::Write>(headers: &[Header], body: &[u8], opts: &DumpOptions, out: &mut W) -> fmt::Result {
+  dump_headers(headers, opts, out)?;
+  if body.is_empty() {return Ok(());}
+  out.write_char('\n')?;
+  let mut budget = opts.max_body_bytes;
+  if is_chunked(headers) {
+    dump_chunked(body, opts, &mut budget, out)
+  } else {
+    dump_body(body, opts, &mut budget, out)
+  }
+}
+
+#[inline]
+fn dump_headers<W: fmt::Write>(headers: &[Header], opts: &DumpOptions, out: &mut W) -> fmt::Result {
+  let headers = || headers.iter().filter(|header| !header.name.is_empty());
+  let name_width = headers().map(|header| header.name.len()).max().unwrap_or(0);
+  let val_width = opts.width.saturating_sub(name_width + 3);
+  for header in headers() {
+    if opts.color {
+      write!(out, "{CYAN}{:name_width$}{RESET} : ", header.name)?;
+    } else {
+      write!(out, "{:name_width$} : ", header.name)?;
+    }
+    if opts.redact.iter().any(|name| name.eq_ignore_ascii_case(header.name)) {
+      out.write_str("<redacted>\n")?;
+    } else {
+      writeln!(out, "{}", lossy_escape(header.val, val_width))?;
+    }
+  }
+  Ok(())
+}
+
+#[inline]
+// writes each chunk of a chunked body with its size, anything after the last chunk or that can't be parsed is dumped as is
+fn dump_chunked<W: fmt::Write>(body: &[u8], opts: &DumpOptions, budget: &mut usize, out: &mut W) -> fmt::Result {
+  let mut index = 0;
+  // the offset after the last chunk walked, every chunk passed to the closure is a subslice of `body` followed by a CRLF
+  let mut consumed = 0;
+  let mut written = Ok(());
+  let walked = walk_chunks(body, None, |data| {
+    consumed = data.as_ptr() as usize - body.as_ptr() as usize + data.len() + 2;
+    written = annotation(opts, out, format_args!("chunk {index}: {} bytes", data.len())).and_then(|_| dump_body(data, opts, budget, out));
+    index += 1;
+    written.map_err(|_| crate::Error::Malformed)
+  });
+  written?;
+  let mut rest = &body[consumed..];
+  if walked.is_ok() {
+    annotation(opts, out, format_args!("last chunk"))?;
+    let line_end = rest.windows(2).position(|window| window == b"\r\n").unwrap_or_default();
+    rest = &rest[(line_end + 2)..];
+  }
+  if rest.is_empty() || rest == b"\r\n" {return Ok(());}
+  annotation(opts, out, format_args!("{} bytes after the chunks", rest.len()))?;
+  dump_body(rest, opts, budget, out)
+}
+
+#[inline]
+// writes the body as text if it's printable UTF-8 and as a hex dump otherwise, spending at most `budget` bytes
+fn dump_body<W: fmt::Write>(body: &[u8], opts: &DumpOptions, budget: &mut usize, out: &mut W) -> fmt::Result {
+  let shown = &body[..body.len().min(*budget)];
+  *budget -= shown.len();
+  match str::from_utf8(shown) {
+    Ok(text) if !text.chars().any(|character| character.is_control() && !matches!(character, '\r' | '\n' | '\t')) => {
+      out.write_str(text)?;
+      if !text.is_empty() && !text.ends_with('\n') {out.write_char('\n')?;}
+    },
+    _ => hex_dump(shown, 0, "", out)?,
+  }
+  if shown.len() < body.len() {
+    annotation(opts, out, format_args!("{} more bytes not shown", body.len() - shown.len()))?;
+  }
+  Ok(())
+}
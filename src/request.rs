@@ -5,15 +5,25 @@
     clippy::undocumented_unsafe_blocks
 )]
 
+use std::borrow::Cow;
 use std::{clone, fmt};
+use std::str::FromStr;
 
-use crate::{Error, HttpVer, Result, SPACE, URL_SAFE, Header, parse_headers};
+use crate::{EncodeError, EncodeOptions, Error, HttpVer, ParseOptions, Result, CR, LF, SPACE, URL_SAFE, TOKEN_SAFE, Header, Origin, find_header, head_incomplete, content_length, chunked_body_len};
+use crate::fields::{headers_semantic_eq, line_terminator, parse_headers, parse_headers_until, partial_terminator, validate_headers, Cursor};
+#[cfg(feature = "serialize")]
+use crate::fields::{serialized_len, validate_content_length, with_content_length, write_headers};
+use crate::framing::validate_request_codings;
+use crate::indices::{indices, parse_header_indices, HeaderIndices, RequestIndices};
+use crate::uri::{scan_target, classify_target, target_form, QueryParam, RequestTarget, TargetForm, Url, UrlError};
+use crate::stats::StatSink;
+use crate::text::{lossy_escape, DEFAULT_LIMIT};
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 /// A parsed HTTP request
 pub struct Request<'a, 'headers> {
-    /// The HTTP request method. Either `Method::Get`, `Method::Post`, or `Method::Put`
-    pub method: Method,
+    /// The HTTP request method, `Method::Other` for a method without its own variant
+    pub method: Method<'a>,
     /// The target URL for the request
     pub path: &'a str,
     /// The HTTP request headers
@@ -25,7 +35,7 @@ impl<'a, 'headers> Request<'a, 'headers> {
   /// Construct a new Response from its parts
   /// Use an empty `&str` to create a `Respose` with no body
   #[inline]
-  pub fn new(method: Method, path: &'a str, headers: &'headers [crate::Header<'a>], body: &'a [u8]) -> Self {
+  pub fn new(method: Method<'a>, path: &'a str, headers: &'headers [crate::Header<'a>], body: &'a [u8]) -> Self {
     Self {
       method,
       path,
@@ -34,40 +44,409 @@ impl<'a, 'headers> Request<'a, 'headers> {
     }
   }
 
+  /// Construct a new Request from its parts, checking that they can't break out of their place in the message.
+  /// Returns an Err(Error::Malformed) if a `Method::Other` is empty or isn't a valid token, if the path contains a space or a control character like `\r` or `\n`,
+  /// if a header name isn't a valid token, or if a header value contains a control character other than a tab
+  ///
+  /// ```rust
+  /// use htpp::{Method, Request, Error};
+  ///
+  /// assert!(Request::try_new(Method::Get, "/a\r\nHost: evil", &[], b"") == Err(Error::Malformed));
+  /// assert!(Request::try_new(Method::Other("GET /a"), "/", &[], b"") == Err(Error::Malformed));
+  /// ```
+  #[inline]
+  pub fn try_new(method: Method<'a>, path: &'a str, headers: &'headers [crate::Header<'a>], body: &'a [u8]) -> Result<Self> {
+    if !valid_start_line(&method, path) {
+      return Err(Error::Malformed);
+    }
+    validate_headers(headers).map_err(|_| Error::Malformed)?;
+    Ok(Self::new(method, path, headers, body))
+  }
+
+  /// The byte representation of the Request like `as_bytes`, checking that no part can end its line early and smuggle in another message
+  /// Returns an Err(EncodeError::IllegalStartLine) if a `Method::Other` isn't a valid token or the path contains a space or a control character,
+  /// an Err(EncodeError::IllegalByte) for the first header with an invalid name or a control character other than a tab in its value,
+  /// or an Err(EncodeError::LengthMismatch) if a `Content-Length` header doesn't match the length of the body
+  #[cfg(feature = "serialize")]
+  #[inline]
+  pub fn try_as_bytes(&self) -> std::result::Result<Vec<u8>, EncodeError> {
+    self.try_as_bytes_with_options(&EncodeOptions::default())
+  }
+
+  /// The byte representation of the Request like `try_as_bytes` but with the given `EncodeOptions`
+  #[cfg(feature = "serialize")]
+  #[inline]
+  pub fn try_as_bytes_with_options(&self, options: &EncodeOptions) -> std::result::Result<Vec<u8>, EncodeError> {
+    if !valid_start_line(&self.method, self.path) {
+      return Err(EncodeError::IllegalStartLine);
+    }
+    validate_headers(self.headers)?;
+    if options.bodiless && self.body.is_empty() {return Ok(self.as_bytes());}
+    if !options.auto_content_length || find_header(self.headers, "Transfer-Encoding").is_some() {
+      validate_content_length(self.headers, self.body.len())?;
+      return Ok(self.as_bytes());
+    }
+    let length = self.body.len().to_string();
+    let headers = with_content_length(self.headers, length.as_bytes());
+    Ok(Request::new(self.method.clone(), self.path, &headers, self.body).as_bytes())
+  }
+
   #[inline]
   /// The byte representation of the Request transmittible over wire
+  #[cfg(feature = "serialize")]
   pub fn as_bytes(&self) -> Vec<u8> {
-    let mut bytes = Vec::new();
-    bytes.extend(format!("{} {} HTTP/1.1\r\n", self.method, self.path).as_bytes());
-    for header in self.headers.iter() {
-      if header.name.is_empty() {break;}
-      bytes.extend(header.name.as_bytes());
-      bytes.extend(b": ");
-      bytes.extend(header.val);
-      bytes.extend(b"\r\n");
-    }
-    bytes.extend(b"\r\n");
+    let len = self.request_line_len() + serialized_len(self.headers) + self.body.len();
+    let mut bytes = Vec::with_capacity(len);
+    bytes.extend(self.method.token());
+    bytes.push(SPACE);
+    bytes.extend(self.path.as_bytes());
+    bytes.extend(b" HTTP/1.1\r\n");
+    write_headers(self.headers, &mut bytes);
     bytes.extend(self.body);
     bytes
+  }
+  /// The byte representation of the Request like `as_bytes` but with the headers sorted by lowercased name, for output that doesn't
+  /// depend on the order the headers were added in such as the input of a signature. Headers with the same name keep their order
+  #[cfg(feature = "serialize")]
+  #[inline]
+  pub fn as_bytes_sorted(&self) -> Vec<u8> {
+    let mut headers: Vec<Header> = self.headers.iter().take_while(|header| !header.name.is_empty()).copied().collect();
+    headers.sort_by(|a, b| {
+      a.name.bytes().map(|character| character.to_ascii_lowercase()).cmp(b.name.bytes().map(|character| character.to_ascii_lowercase()))
+    });
+    Request::new(self.method.clone(), self.path, &headers, self.body).as_bytes()
+  }
+  /// The length of the request line `METHOD SP path SP HTTP/1.1\r\n` as written by `as_bytes`
+  #[cfg(feature = "serialize")]
+  #[inline]
+  pub fn request_line_len(&self) -> usize {
+    self.method.token().len() + self.path.len() + 12
   }
    /// Parses the bytes of an HTTP request into a `Request`
-   /// It parses headers into the `header_buf` you pass, if there is more headers than the length of the buffer you pass, an Err(Error::TooManyHeaders) is returned
+   /// It parses headers into the `header_buf` you pass, if there is more headers than the length of the buffer you pass, an Err(Error::TooManyHeaders) is returned.
+   /// The `headers` of the returned `Request` only hold the parsed headers, not the rest of the buffer.
+   /// If the slice ends before the header section does but is valid so far, an Err(Error::Incomplete) is returned instead of Err(Error::Malformed)
+  /// Empty lines before the request line are rejected, use `Request::parse_with_options` with `allow_leading_crlf` to skip them
   #[inline]
   pub fn parse(slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>]) -> Result<Request<'a, 'headers>> {
-    if slice.len() < 14 {return Err(Error::Malformed);}
-    let mut offset = 0;
-    let (method, read) = parse_method(slice)?;
+    let (method, path, mut offset) = parse_request_line(slice, &ParseOptions::default()).map_err(|err| head_incomplete(err, slice))?;
+    let (read, count) = parse_headers(&slice[offset..], headers_buf).map_err(|err| head_incomplete(err, slice))?;
     offset += read;
-    let (path, read) = parse_path(&slice[offset..])?;
-    offset += read;
-    if slice[offset..].len() < 10 {return Err(Error::Malformed);}
-    parse_http_version(&slice[offset..])?;
-    offset += 10;
-    let read = parse_headers(&slice[offset..], headers_buf)?;
+    Ok(Request::new(method, path, &headers_buf[..count], &slice[offset..]))
+  }
+
+  /// Parses the bytes of an HTTP request into a `Request` like `Request::parse` if its method is one of `allowed`.
+  /// The method is checked before the rest of the request is parsed, an Err(Error::MethodNotAllowed) with the method is returned if it isn't allowed.
+  /// The error can't borrow the request, so the token of a method without its own variant is copied into it
+  ///
+  /// ```rust
+  /// use htpp::{Error, Method, Request, EMPTY_HEADER};
+  ///
+  /// let mut headers = [EMPTY_HEADER; 10];
+  /// let err = Request::parse_allowed(b"PUT / HTTP/1.1\r\n\r\n", &mut headers, &[Method::Get, Method::Post]).unwrap_err();
+  /// assert!(err == Error::MethodNotAllowed("PUT".into()));
+  /// let err = Request::parse_allowed(b"PURGE / HTTP/1.1\r\n\r\n", &mut headers, &[Method::Get]).unwrap_err();
+  /// assert!(err == Error::MethodNotAllowed("PURGE".into()));
+  /// ```
+  #[inline]
+  pub fn parse_allowed(slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>], allowed: &[Method]) -> Result<Request<'a, 'headers>> {
+    if let Ok((method, _)) = parse_method(slice) {
+      if !allowed.contains(&method) {
+        let token = match KNOWN_METHODS.into_iter().find(|known| *known == method) {
+          Some(known) => Cow::Borrowed(known.as_str()),
+          None => Cow::Owned(method.as_str().to_owned()),
+        };
+        return Err(Error::MethodNotAllowed(token));
+      }
+    }
+    Request::parse(slice, headers_buf)
+  }
+
+  /// Parses the bytes of an HTTP request into a `Request` like `Request::parse` but with the given `ParseOptions`
+  /// `allow_bare_lf` applies to the request line, the header lines and, with `allow_leading_crlf`, to the skipped empty lines
+  #[inline]
+  pub fn parse_with_options(slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>], options: &ParseOptions) -> Result<Request<'a, 'headers>> {
+    Request::parse_with_stats(slice, headers_buf, options, &mut ())
+  }
+
+  /// Parses the bytes of an HTTP request like `Request::parse_with_options`, reporting each header, the length of the head,
+  /// and the use of a lenient option to `stats`. Skipped empty lines before the request line count as lenient
+  #[inline]
+  pub fn parse_with_stats(slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>], options: &ParseOptions, stats: &mut impl StatSink) -> Result<Request<'a, 'headers>> {
+    let mut offset = 0;
+    if options.allow_leading_crlf {
+      while let Some(read) = line_terminator(&slice[offset..], options) {
+        offset += read;
+      }
+      if partial_terminator(&slice[offset..]) {return Err(head_incomplete(Error::Incomplete { needed: None }, slice));}
+      if offset > 0 {stats.relaxed();}
+    }
+    let (method, path, read) = parse_request_line(&slice[offset..], options).map_err(|err| head_incomplete(err, slice))?;
     offset += read;
-    Ok(Request::new(method, path, headers_buf, &slice[offset..]))
+    if slice.get(offset - 2) != Some(&CR) {stats.relaxed();}
+    let mut cursor = Cursor::default();
+    parse_headers_until(&slice[offset..], headers_buf, &mut cursor, usize::MAX, options, stats).map_err(|err| head_incomplete(err, slice))?;
+    offset += cursor.offset;
+    stats.head(offset);
+    Ok(Request::new(method, path, &headers_buf[..cursor.parsed], &slice[offset..]))
+  }
+
+  /// Parses the bytes of an HTTP request like `Request::parse` but returns the position of each part instead of borrowing them
+  /// The position of each header is written to `header_indices`, if there is more headers than its length an Err(Error::TooManyHeaders) is returned.
+  /// Turn the result back into a `Request` with `RequestIndices::request`. Returns an Err(Error::Malformed) if the slice is longer than `u32::MAX` bytes
+  #[inline]
+  pub fn parse_indices(slice: &[u8], header_indices: &mut [HeaderIndices]) -> Result<RequestIndices> {
+    let (method, path, offset) = parse_request_line(slice, &ParseOptions::default()).map_err(|err| head_incomplete(err, slice))?;
+    let method = indices(slice, &slice[..method.token().len()])?;
+    let path = indices(slice, path.as_bytes())?;
+    let (offset, headers) = parse_header_indices(slice, offset, header_indices).map_err(|err| head_incomplete(err, slice))?;
+    let body = indices(slice, &slice[offset..])?;
+    Ok(RequestIndices { method, path, headers, body })
+  }
+
+  /// Parses the bytes of an HTTP request into a `Request` making sure the slice holds exactly one request
+  /// The end of the body is found from the `Content-Length` or chunked `Transfer-Encoding` headers, a request with neither has no body.
+  /// If any bytes are left after the end of the request an Err(Error::Malformed) is returned.
+  /// If the body is shorter than its `Content-Length`, an Err(Error::Incomplete) with the number of missing bytes is returned,
+  /// and if a chunked body is cut off an Err(Error::Incomplete) is returned too
+  #[inline]
+  pub fn parse_exact(slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>]) -> Result<Request<'a, 'headers>> {
+    Request::parse(slice, headers_buf)?.framed_exact(&ParseOptions::default())
+  }
+
+  /// Parses the bytes of an HTTP request making sure the slice holds exactly one request like `Request::parse_exact`, but with the given `ParseOptions`.
+  /// A `Content-Length` over `max_body_size` is an Err(Error::BodyTooLarge) before any of the body is looked at,
+  /// a chunked body is one as soon as its chunks add up to more than `max_body_size`
+  ///
+  /// ```rust
+  /// use htpp::{Error, ParseOptions, Request, EMPTY_HEADER};
+  ///
+  /// let options = ParseOptions { max_body_size: Some(1024), ..ParseOptions::default() };
+  /// let mut headers = [EMPTY_HEADER; 10];
+  /// let err = Request::parse_exact_with_options(b"POST / HTTP/1.1\r\nContent-Length: 2000000000\r\n\r\n", &mut headers, &options).unwrap_err();
+  /// assert!(err == Error::BodyTooLarge { declared: Some(2000000000), limit: 1024 });
+  /// ```
+  #[inline]
+  pub fn parse_exact_with_options(slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>], options: &ParseOptions) -> Result<Request<'a, 'headers>> {
+    Request::parse_with_options(slice, headers_buf, options)?.framed_exact(options)
+  }
+
+  #[inline]
+  // checks the body of a parsed request ends exactly at the end of its framing
+  fn framed_exact(self, options: &ParseOptions) -> Result<Self> {
+    let length = content_length(self.headers)?;
+    let body_len = match find_header(self.headers, "Transfer-Encoding") {
+      Some(_) if length.is_some() => return Err(Error::Malformed),
+      Some(_) => {
+        validate_request_codings(self.headers)?;
+        chunked_body_len(self.body, options.max_body_size)?
+      },
+      None => match (length, options.max_body_size) {
+        (Some(declared), Some(limit)) if declared > limit => return Err(Error::BodyTooLarge { declared: Some(declared), limit }),
+        _ => length.unwrap_or(0),
+      },
+    };
+    if self.body.len() < body_len && length.is_some() {
+      return Err(Error::Incomplete { needed: Some(body_len - self.body.len()) });
+    }
+    if self.body.len() != body_len {return Err(Error::Malformed);}
+    Ok(self)
+  }
+
+  /// Parses the bytes of an HTTP request into a `Request` while examining at most about `max_work_bytes` bytes of input
+  /// If the budget runs out before the headers are parsed, `ParseOutcome::Yielded` is returned and parsing continues with `Request::resume_bounded`.
+  /// The budget is checked before each header line, so a call may read past it by at most one line
+  #[inline]
+  pub fn parse_bounded(slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>], max_work_bytes: usize) -> Result<ParseOutcome<'a, 'headers>> {
+    let (method, path, read) = parse_request_line(slice, &ParseOptions::default()).map_err(|err| head_incomplete(err, slice))?;
+    let state = ParseState { method, path, head: read, cursor: Cursor::default() };
+    Request::resume_bounded(slice, headers_buf, state, max_work_bytes.saturating_sub(read))
+  }
+
+  /// Continues a parse started by `Request::parse_bounded` from the `state` it yielded, examining at most about `max_work_bytes` more bytes
+  /// The `slice` and `headers_buf` must be the same ones passed to the call that yielded,
+  /// an Err(Error::Malformed) is returned if the state points past the end of either
+  #[inline]
+  pub fn resume_bounded(slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>], state: ParseState<'a>, max_work_bytes: usize) -> Result<ParseOutcome<'a, 'headers>> {
+    let ParseState { method, path, head, mut cursor } = state;
+    let limit = cursor.offset.saturating_add(max_work_bytes);
+    // a state from another slice or buffer may point past the end of this one
+    let rest = slice.get(head..).ok_or(Error::Malformed)?;
+    if cursor.offset > rest.len() || cursor.parsed > headers_buf.len() {return Err(Error::Malformed);}
+    if !parse_headers_until(rest, headers_buf, &mut cursor, limit, &ParseOptions::default(), &mut ()).map_err(|err| head_incomplete(err, slice))? {
+      return Ok(ParseOutcome::Yielded { state: ParseState { method, path, head, cursor } });
+    }
+    let body = rest.get(cursor.offset..).ok_or(Error::Malformed)?;
+    Ok(ParseOutcome::Complete(Request::new(method, path, &headers_buf[..cursor.parsed], body)))
+  }
+
+  /// Parses the bytes of an HTTP request, recovering as much of it as possible if it is malformed
+  /// Returns the parts that were parsed before the first error along with that error, or `None` if the whole request is valid.
+  /// Meant for diagnosing malformed traffic, use `Request::parse` to handle requests
+  #[inline]
+  pub fn parse_partial(slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>]) -> (PartialRequest<'a, 'headers>, Option<Error>) {
+    let mut partial = PartialRequest { method: None, path: None, headers: &[], complete: false, offset: 0 };
+    let (method, read) = match parse_method(slice) {
+      Ok(v) => v,
+      Err(err) => return (partial, Some(head_incomplete(err, slice))),
+    };
+    let path = parse_path(&slice[read..], &method);
+    partial.method = Some(method);
+    partial.offset += read;
+    let (path, read) = match path {
+      Ok(v) => v,
+      Err(err) => return (partial, Some(head_incomplete(err, slice))),
+    };
+    partial.path = Some(path);
+    partial.offset += read;
+    match parse_http_version(&slice[partial.offset..], &ParseOptions::default()) {
+      Ok((_, read)) => partial.offset += read,
+      Err(err) => return (partial, Some(head_incomplete(err, slice))),
+    }
+    partial.salvage_headers(slice, headers_buf)
+  }
+
+  /// Parses the request line and as many of the leading header lines of an HTTP request as are valid
+  /// Unlike `Request::parse_partial`, nothing is returned if the request line is malformed.
+  /// Parsing stops at the first malformed header line, which is never included. The returned `PartialRequest`
+  /// is only `complete` if the whole header section is valid, the error and the `offset` tell why and where parsing stopped otherwise
+  #[inline]
+  pub fn parse_salvage(slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>]) -> (PartialRequest<'a, 'headers>, Option<Error>) {
+    let mut partial = PartialRequest { method: None, path: None, headers: &[], complete: false, offset: 0 };
+    let (method, path, read) = match parse_request_line(slice, &ParseOptions::default()) {
+      Ok(v) => v,
+      Err(err) => return (partial, Some(head_incomplete(err, slice))),
+    };
+    partial.method = Some(method);
+    partial.path = Some(path);
+    partial.offset = read;
+    partial.salvage_headers(slice, headers_buf)
+  }
+
+  /// The language ranges of the `Accept-Language` headers with their quality values, sorted by descending quality
+  /// The quality is the `q` parameter of a range, matched case-insensitively among its other parameters.
+  /// Ranges without a `q` parameter have a quality of 1, ranges with an invalid quality are skipped
+  #[inline]
+  pub fn accept_language(&self) -> Vec<(&'a str, f32)> {
+    let mut languages = Vec::new();
+    for header in self.headers.iter().filter(|header| header.name.eq_ignore_ascii_case("Accept-Language")) {
+      let Ok(val) = std::str::from_utf8(header.val) else {continue;};
+      for range in val.split(',') {
+        let mut params = range.split(';');
+        let tag = params.next().unwrap_or_default().trim();
+        if tag.is_empty() {continue;}
+        // the weight is the parameter named `q` in any case, among any others, with optional whitespace around the `=`
+        let weight = params.filter_map(|param| param.split_once('=')).find(|(name, _)| name.trim().eq_ignore_ascii_case("q"));
+        let quality = match weight {
+          None => Some(1.0),
+          Some((_, q)) => q.trim().parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q)),
+        };
+        if let Some(quality) = quality {
+          languages.push((tag, quality));
+        }
+      }
+    }
+    languages.sort_by(|a, b| b.1.total_cmp(&a.1));
+    languages
+  }
+
+  /// Parses the path and query parameters of the request target into a `Url`, the query parameters are parsed into `queries_buf`
+  /// The scheme and authority of absolute-form targets are skipped.
+  /// Returns an Err(UrlError::Path) for asterisk-form and authority-form targets, they have no path
+  #[inline]
+  pub fn url<'queries>(&self, queries_buf: &'queries mut [QueryParam<'a>]) -> std::result::Result<Url<'a, 'queries>, UrlError> {
+    match classify_target(self.path.as_bytes())? {
+      // an empty path of an absolute-form target is the same as `/`
+      RequestTarget::Absolute { path: "", .. } => Ok(Url::new("/", None)),
+      RequestTarget::Origin(path) | RequestTarget::Absolute { path, .. } => Url::parse(path.as_bytes(), queries_buf),
+      _ => Err(UrlError::Path),
+    }
+  }
+
+  /// The form of the request target, proxies use it to tell requests for themselves from requests to forward
+  /// The form is found from the shape of the target, it isn't validated again
+  #[inline]
+  pub fn target_form(&self) -> TargetForm {
+    target_form(self.path.as_bytes())
+  }
+
+  /// The raw query of the request target between the `?` and a `#` fragment, it isn't validated or split into parameters
+  /// Returns `None` if the target has no query, asterisk-form and authority-form targets never have one
+  #[inline]
+  pub fn query_str(&self) -> Option<&'a str> {
+    let target = self.path.split_once('#').map_or(self.path, |(target, _)| target);
+    target.split_once('?').map(|(_, query)| query)
+  }
+
+  /// The names and values of the headers as tuples, empty headers are skipped
+  #[inline]
+  pub fn header_pairs(&self) -> impl Iterator<Item = (&'a str, &'a [u8])> + 'headers {
+    self.headers.iter().filter(|header| !header.name.is_empty()).map(|header| (header.name, header.val))
+  }
+
+  /// An iterator over the populated headers for which `pred` returns `true`, in the order they were sent
+  #[inline]
+  pub fn headers_matching<F: Fn(&Header<'a>) -> bool + 'headers>(&self, pred: F) -> impl Iterator<Item = &'headers Header<'a>> + 'headers {
+    self.headers.iter().filter(move |header| !header.name.is_empty() && pred(header))
+  }
+
+  /// An iterator over the `name=value` pairs of the `Cookie` headers in the order they were sent, including repeated names
+  /// Values are split from names on the first `=` only and are not unquoted. Pairs without `=` or that aren't valid UTF-8 are skipped
+  #[inline]
+  pub fn cookies(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'headers {
+    self.headers.iter()
+      .filter(|header| header.name.eq_ignore_ascii_case("Cookie"))
+      .flat_map(|header| header.val.split(|character| *character == b';'))
+      .filter_map(|pair| {
+        let (name, val) = std::str::from_utf8(pair.trim_ascii()).ok()?.split_once('=')?;
+        Some((name.trim_end(), val.trim_start()))
+      })
+  }
+
+  /// The value of the first cookie with the given name, names are case-sensitive
+  #[inline]
+  pub fn cookie(&self, name: &str) -> Option<&'a str> {
+    self.cookies().find(|(cookie, _)| *cookie == name).map(|(_, val)| val)
+  }
+
+  /// Whether both requests mean the same: the methods, targets, and bodies are equal
+  /// and the headers are equal as compared by `fields::headers_semantic_eq`
+  #[inline]
+  pub fn semantic_eq(&self, other: &Request) -> bool {
+    self.method == other.method && self.path == other.path && self.body == other.body
+      && headers_semantic_eq(self.headers, other.headers, &[])
+  }
+
+  /// The first header with the given name, names are compared case-insensitively
+  /// The returned header keeps the name casing of the request
+  #[inline]
+  pub fn header_entry(&self, name: &str) -> Option<&'headers Header<'a>> {
+    find_header(self.headers, name)
+  }
+
+  /// The parsed `Origin` header of the request
+  /// Returns `None` if the header is missing or its value is not a valid origin
+  #[inline]
+  pub fn origin(&self) -> Option<Origin<'a>> {
+    let header = find_header(self.headers, "Origin")?;
+    Origin::parse(header.val).ok()
+  }
+
+  /// The value of the `Expect` header without surrounding whitespace, `None` if there is no `Expect` header or it isn't valid UTF-8
+  /// `100-continue` is the only expectation defined, a server should answer any other with `Response::for_expectation`
+  #[inline]
+  pub fn expect(&self) -> Option<&'a str> {
+    let header = find_header(self.headers, "Expect")?;
+    std::str::from_utf8(header.val.trim_ascii()).ok()
+  }
+
+  /// Whether the client sent `Expect: 100-continue` and waits for a `Response::continue_interim` before sending the body
+  #[inline]
+  pub fn expects_continue(&self) -> bool {
+    self.expect().is_some_and(|expectation| expectation.eq_ignore_ascii_case("100-continue"))
   }
 }
+#[cfg(feature = "fmt")]
 impl<'a, 'headers> fmt::Display for Request<'a, 'headers> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
       let mut headers = String::new();
@@ -75,78 +454,248 @@ impl<'a, 'headers> fmt::Display for Request<'a, 'headers> {
         if header.to_string().is_empty() {continue;} 
         headers.push_str(&format!("{}\r\n", header));
       }
-      let body = match std::str::from_utf8(self.body) {
-        Ok(v) => {
-          v.to_string()
-        },
-        Err(_) => {
-          format!("{:?}", self.body)
-        },
-      };
+      let body = lossy_escape(self.body, DEFAULT_LIMIT);
       f.write_str(format!("{} {} HTTP/1.1\r\n{}\r\n{}", self.method, self.path, headers, body).as_str())
     }
 }
 
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
-/// The http method of a request. Only GET, POST, and PUT are supported
-pub enum Method {
+/// The parts of a request recovered by `Request::parse_partial` or `Request::parse_salvage`
+pub struct PartialRequest<'a, 'headers> {
+    /// The HTTP request method or `None` if it couldn't be parsed
+    pub method: Option<Method<'a>>,
+    /// The target URL for the request or `None` if it couldn't be parsed
+    pub path: Option<&'a str>,
+    /// The headers that were parsed before the first malformed one
+    pub headers: &'headers [crate::Header<'a>],
+    /// Whether the request line and the whole header section were parsed
+    pub complete: bool,
+    /// The offset of the first byte that wasn't parsed, the start of the body if the request is complete
+    pub offset: usize,
+}
+impl<'a, 'headers> PartialRequest<'a, 'headers> {
+  #[inline]
+  // parses the headers after the request line, which ends at `self.offset`
+  fn salvage_headers(mut self, slice: &'a [u8], headers_buf: &'headers mut [crate::Header<'a>]) -> (Self, Option<Error>) {
+    let mut cursor = Cursor::default();
+    let result = parse_headers_until(&slice[self.offset..], headers_buf, &mut cursor, usize::MAX, &ParseOptions::default(), &mut ());
+    self.headers = &headers_buf[..cursor.parsed];
+    self.offset += cursor.offset;
+    self.complete = result.is_ok();
+    (self, result.err().map(|err| head_incomplete(err, slice)))
+  }
+}
+
+
+/// The result of `Request::parse_bounded` and `Request::resume_bounded`
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum ParseOutcome<'a, 'headers> {
+  /// The request was fully parsed
+  Complete(Request<'a, 'headers>),
+  /// The work budget ran out, pass the state to `Request::resume_bounded` to continue
+  Yielded {
+    /// The progress made so far
+    state: ParseState<'a>,
+  },
+}
+
+/// The progress of a request parse that ran out of its work budget
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ParseState<'a> {
+    method: Method<'a>,
+    path: &'a str,
+    // the length of the request line
+    head: usize,
+    cursor: Cursor,
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+/// The http method of a request. Methods without their own variant, such as `PURGE`, are kept as `Method::Other`
+pub enum Method<'a> {
   /// The http GET method  
   Get,
   /// The http POST method
   Post,
   /// The http PUT method
   Put,
+  /// The http DELETE method
+  Delete,
+  /// The http PATCH method
+  Patch,
+  /// The http HEAD method, like GET but the response has no body
+  Head,
+  /// The http OPTIONS method, the only method accepting the `*` target
+  Options,
+  /// The http TRACE method, asking the server to echo the request back
+  Trace,
+  /// The http CONNECT method, asking a proxy for a tunnel. Its target is the authority of the destination such as `example.com:443`
+  Connect,
+  /// An extension method, any token other than the methods above. Method tokens are case-sensitive so `get` is an extension method
+  Other(&'a str),
 }
-impl fmt::Display for Method {
+#[cfg(feature = "fmt")]
+impl fmt::Display for Method<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let method = match self {
-            Self::Get => "GET",
-            Self::Post => "POST",
-            Self::Put => "PUT",
-        };
-        f.write_str(method)
+        f.write_str(self.as_str())
     }
 }
+impl<'a> Method<'a> {
+  /// Compares the method to a raw method token such as `b"GET"`
+  /// Method tokens are case-sensitive so `b"get"` doesn't match `Method::Get`
+  #[inline]
+  pub fn eq_bytes(&self, token: &[u8]) -> bool {
+    self.token() == token
+  }
+  /// Whether the method is safe, read-only by definition, as defined by RFC 9110: GET, HEAD, OPTIONS, and TRACE.
+  /// Extension methods are not known to be safe
+  #[inline]
+  pub fn is_safe(&self) -> bool {
+    matches!(self, Self::Get | Self::Head | Self::Options | Self::Trace)
+  }
+  /// Whether sending the request several times has the same effect as sending it once, as defined by RFC 9110:
+  /// the safe methods, PUT, and DELETE. A client can retry such a request automatically. Extension methods are not known to be idempotent
+  #[inline]
+  pub fn is_idempotent(&self) -> bool {
+    self.is_safe() || matches!(self, Self::Put | Self::Delete)
+  }
+  /// Whether a response to a request with this method can carry a body, `false` for `HEAD`
+  /// whose response has no body even when it has a `Content-Length` header
+  #[inline]
+  pub fn expects_response_body(&self) -> bool {
+    !matches!(self, Self::Head)
+  }
+  /// The method token as sent on the wire such as `"GET"`, without allocating.
+  /// It is `'static` for every variant but `Method::Other`, which borrows the token it was parsed from
+  #[inline]
+  pub fn as_str(&self) -> &'a str {
+    match self {
+      Self::Get => "GET",
+      Self::Post => "POST",
+      Self::Put => "PUT",
+      Self::Delete => "DELETE",
+      Self::Patch => "PATCH",
+      Self::Head => "HEAD",
+      Self::Options => "OPTIONS",
+      Self::Trace => "TRACE",
+      Self::Connect => "CONNECT",
+      Self::Other(method) => method,
+    }
+  }
+  #[inline]
+  // the method token as sent on the wire
+  pub(crate) fn token(&self) -> &'a [u8] {
+    self.as_str().as_bytes()
+  }
+  /// The method of a raw method token such as `b"GET"`, without the space that follows it in a request line.
+  /// A token without its own variant is a `Method::Other`.
+  /// Returns an Err(Error::Malformed) if the token is empty or has a byte that isn't allowed in a token
+  ///
+  /// ```rust
+  /// use htpp::{Error, Method};
+  ///
+  /// assert!(Method::from_bytes(b"GET") == Ok(Method::Get));
+  /// assert!(Method::from_bytes(b"PROPFIND") == Ok(Method::Other("PROPFIND")));
+  /// assert!(Method::from_bytes(b"GET ") == Err(Error::Malformed));
+  /// ```
+  #[inline]
+  pub fn from_bytes(token: &'a [u8]) -> Result<Method<'a>> {
+    if token.is_empty() || !token.iter().all(|character| TOKEN_SAFE.contains(*character)) {return Err(Error::Malformed);}
+    if let Some(known) = KNOWN_METHODS.into_iter().find(|known| known.token() == token) {return Ok(known);}
+    //SAFETY: already checked that the token is ascii
+    Ok(Method::Other(unsafe { std::str::from_utf8_unchecked(token) }))
+  }
+}
+impl FromStr for Method<'static> {
+    type Err = Error;
+
+    /// Parses a method with its own variant like `Method::from_bytes`.
+    /// The method can't borrow the string, so an extension method returns an Err(Error::Malformed), use `Method::from_bytes` to keep them
+    fn from_str(method: &str) -> Result<Self> {
+        KNOWN_METHODS.into_iter().find(|known| known.eq_bytes(method.as_bytes())).ok_or(Error::Malformed)
+    }
+}
+impl PartialEq<&[u8]> for Method<'_> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.eq_bytes(other)
+    }
+}
+
+// the methods with their own variant
+const KNOWN_METHODS: [Method<'static>; 9] = [Method::Get, Method::Post, Method::Put, Method::Delete, Method::Patch, Method::Head, Method::Options, Method::Trace, Method::Connect];
+
+#[inline]
+// whether the method and path can't end the request line early, an extension method must be a non-empty token
+fn valid_start_line(method: &Method, path: &str) -> bool {
+  Method::from_bytes(method.token()).is_ok() && !path.bytes().any(|character| character == SPACE || character.is_ascii_control())
+}
+
+#[inline]
+//parses the request line and returns the method, path, and the length of the line
+// each stage returns an Err(Error::Incomplete) if the slice ends while the line is still valid
+pub(crate) fn parse_request_line<'a>(slice: &'a [u8], options: &ParseOptions) -> Result<(Method<'a>, &'a str, usize)> {
+  let mut offset = 0;
+  let (method, read) = parse_method(slice)?;
+  offset += read;
+  let (path, read) = parse_path(&slice[offset..], &method)?;
+  offset += read;
+  let (_, read) = parse_http_version(&slice[offset..], options)?;
+  offset += read;
+  Ok((method, path, offset))
+}
 
 #[inline]
 //parses the method and removes white space after it
-fn parse_method(slice: &[u8]) -> Result<(Method, usize)> {
-  if &slice[0..4] == b"GET " {
-    return Ok((Method::Get, 4));
-  } else if &slice[0..5] == b"POST " {
-    return Ok((Method::Post, 5));
-  } else if &slice[0..4] == b"PUT " {
-    return Ok((Method::Put, 4));
+fn parse_method(slice: &[u8]) -> Result<(Method<'_>, usize)> {
+  // the methods with their own variant are matched directly, only other tokens are scanned
+  for method in KNOWN_METHODS {
+    let token = method.token();
+    if slice.get(token.len()) == Some(&SPACE) && slice.starts_with(token) {return Ok((method, token.len() + 1));}
+  }
+  let len = slice.iter().position(|character| !TOKEN_SAFE.contains(*character)).unwrap_or(slice.len());
+  match slice.get(len) {
+    Some(&SPACE) => Ok((Method::from_bytes(&slice[..len])?, len + 1)),
+    None => Err(Error::Incomplete { needed: None }),
+    Some(_) => Err(Error::Malformed),
   }
-  Err(Error::Malformed)
 }
 
 #[inline]
-// parses the path and removes the space after making sure it only contains URL safe characters
-fn parse_path(slice: &[u8]) -> Result<(&str, usize)> {
-  for (counter, character) in slice.iter().enumerate() {
-    if URL_SAFE[*character as usize] {
-      continue;
-    } else if *character == SPACE {
-      let path = &slice[..counter];
-      if path.is_empty() {return Err(Error::Malformed);}
-      //SAFETY: already checked that the input is valid ascii
-      return Ok( (unsafe { std::str::from_utf8_unchecked(path) }, counter+1));
-    }
+// parses the path and removes the space after making sure it is a valid request target, `*` is only valid for OPTIONS
+// an authority such as `example.com:443` is a valid target for any method, it is the only one CONNECT uses
+fn parse_path<'a>(slice: &'a [u8], method: &Method) -> Result<(&'a str, usize)> {
+  let len = match scan_target(slice) {
+    Ok(len) => len,
+    // a percent-encoding cut off by the end of the slice
+    Err(_) if slice.iter().rposition(|character| *character == b'%')
+      .is_some_and(|start| slice.len() - start < 3 && slice[(start+1)..].iter().all(u8::is_ascii_hexdigit)) => return Err(Error::Incomplete { needed: None }),
+    Err(_) => return Err(Error::Malformed),
+  };
+  match slice.get(len) {
+    Some(&SPACE) => {},
+    None => return Err(Error::Incomplete { needed: None }),
+    Some(_) => return Err(Error::Malformed),
+  }
+  let path = &slice[..len];
+  if classify_target(path).map_err(|_| Error::Malformed)? == RequestTarget::Asterisk && *method != Method::Options {
     return Err(Error::Malformed);
   }
-  Err(Error::Malformed)
+  //SAFETY: already checked that the input is valid ascii
+  Ok( (unsafe { std::str::from_utf8_unchecked(path) }, len+1))
 }
 
 #[inline]
-//removes the \r\n after
-fn parse_http_version(slice: &[u8]) -> Result<HttpVer> {
-  if &slice[0..10] == b"HTTP/1.1\r\n" {
-    Ok(HttpVer::One)
-  } else if &slice[0..10] == b"HTTP/2.0\r\n" {
-    Ok(HttpVer::Two)
-  } else {Err(Error::Malformed)}
+// parses the version and the \r\n ending the request line
+fn parse_http_version(slice: &[u8], options: &ParseOptions) -> Result<(HttpVer, usize)> {
+  let (version, read) = HttpVer::parse(slice)?;
+  // a `Request` has no version and is written back as HTTP/1.1, so HTTP/1.0 requests aren't parsed
+  if version == HttpVer::OneZero {return Err(Error::Malformed);}
+  match line_terminator(&slice[read..], options) {
+    Some(terminator) => Ok((version, read + terminator)),
+    None if partial_terminator(&slice[read..]) => Err(Error::Incomplete { needed: None }),
+    None => Err(Error::Malformed),
+  }
 }
 
 
@@ -7,7 +7,14 @@
 )]
 
 use std::fmt;
-use crate::{Error, HttpVer, Result, CR, LF, SPACE, Header, parse_headers, HEADER_NAME_SAFE};
+use crate::{EncodeError, EncodeOptions, Error, HttpVer, ParseOptions, Result, CR, LF, SPACE, HTAB, Header, find_header, head_incomplete};
+use crate::fields::{headers_semantic_eq, parse_headers_until, line_terminator, partial_terminator, validate_headers, Cursor};
+#[cfg(feature = "serialize")]
+use crate::fields::{serialized_len, validate_content_length, with_content_length, write_headers};
+use crate::stats::StatSink;
+use crate::text::{lossy_escape, DEFAULT_LIMIT};
+use crate::content_disposition::ContentDisposition;
+use crate::indices::{indices, parse_header_indices, HeaderIndices, ResponseIndices};
 
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
@@ -24,7 +31,7 @@ pub struct Response<'a, 'headers> {
 }
 impl<'a, 'headers> Response<'a, 'headers> {
   /// Construct a new `Response` from its parts.
-  /// Use an empty `&str` to create a `Respose` with no reason phrase
+  /// Use an empty `&str` to create a `Respose` with no reason phrase, the status code is still followed by a space when serialized
   /// Use an empty `&str` to create a `Respose` with no body
   pub fn new(status: u16, reason: &'a str, headers: &'headers [Header<'a>], body: &'a [u8]) -> Response<'a, 'headers> {
     Self {
@@ -34,39 +41,335 @@ impl<'a, 'headers> Response<'a, 'headers> {
       body
     }
   }
-  /// The byte representation of the `Response` transmittible over wire
+  /// Whether both responses mean the same: the statuses and bodies are equal and the headers are equal
+  /// as compared by `fields::headers_semantic_eq`, reason phrases are ignored
   #[inline]
-  pub fn as_bytes(&self) -> Vec<u8> {
-    let mut bytes = Vec::new();
-    if self.reason.is_empty() {
-      bytes.extend(format!("HTTP/1.1 {}\r\n", self.status).as_bytes());
+  pub fn semantic_eq(&self, other: &Response) -> bool {
+    self.status == other.status && self.body == other.body && headers_semantic_eq(self.headers, other.headers, &[])
+  }
+
+  /// The first header with the given name, names are compared case-insensitively
+  /// The returned header keeps the name casing of the response
+  #[inline]
+  pub fn header_entry(&self, name: &str) -> Option<&'headers Header<'a>> {
+    find_header(self.headers, name)
+  }
+
+  /// The parsed `Content-Disposition` header of the response
+  /// Returns `None` if the header is missing or its value is malformed
+  #[inline]
+  pub fn content_disposition(&self) -> Option<ContentDisposition<'a>> {
+    ContentDisposition::parse(find_header(self.headers, "Content-Disposition")?.val).ok()
+  }
+
+  /// The parameters of the `Keep-Alive` header such as `timeout=5, max=1000`, both `None` if there is no such header.
+  /// The legacy form holding only a number such as `Keep-Alive: 115` is read as the timeout.
+  /// Parameter names are case-insensitive, unknown parameters and values that aren't numbers are skipped
+  ///
+  /// ```rust
+  /// use htpp::{KeepAlive, Response, EMPTY_HEADER};
+  ///
+  /// let mut headers = [EMPTY_HEADER; 10];
+  /// let res = Response::parse(b"HTTP/1.1 200 OK\r\nKeep-Alive: timeout=5, max=1000\r\n\r\n", &mut headers).unwrap();
+  /// assert!(res.keep_alive_params() == KeepAlive { timeout: Some(5), max: Some(1000) });
+  /// ```
+  #[inline]
+  pub fn keep_alive_params(&self) -> KeepAlive {
+    let mut params = KeepAlive::default();
+    let Some(header) = find_header(self.headers, "Keep-Alive") else {return params;};
+    let Ok(val) = std::str::from_utf8(header.val) else {return params;};
+    if let Ok(timeout) = val.trim().parse::<u64>() {
+      params.timeout = Some(timeout);
+      return params;
+    }
+    for param in val.split(',') {
+      let Some((name, val)) = param.split_once('=') else {continue;};
+      let Ok(val) = val.trim().parse::<u64>() else {continue;};
+      match name.trim() {
+        name if name.eq_ignore_ascii_case("timeout") => params.timeout = Some(val),
+        name if name.eq_ignore_ascii_case("max") => params.max = Some(val),
+        _ => {},
+      }
+    }
+    params
+  }
+
+  /// The body decoded to a `String` using the `charset` parameter of the `Content-Type` header, UTF-8 if there is none
+  /// Charsets are looked up by their WHATWG Encoding Standard labels, so `ISO-8859-1` and `US-ASCII` decode as `windows-1252`.
+  /// Returns `None` for unknown charsets or if the body isn't valid in its charset. Requires the `encoding` feature
+  ///
+  /// ```rust
+  /// use htpp::{Response, EMPTY_HEADER};
+  ///
+  /// let mut headers = [EMPTY_HEADER; 10];
+  /// let res = Response::parse(b"HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=iso-8859-1\r\n\r\ncaf\xe9", &mut headers).unwrap();
+  /// assert!(res.text().unwrap() == "caf\u{e9}");
+  /// ```
+  #[cfg(any(test, feature = "encoding"))]
+  #[inline]
+  pub fn text(&self) -> Option<String> {
+    let charset = find_header(self.headers, "Content-Type").and_then(|header| charset(header.val)).unwrap_or(b"utf-8");
+    let encoding = encoding_rs::Encoding::for_label(charset)?;
+    encoding.decode_without_bom_handling_and_without_replacement(self.body).map(|text| text.into_owned())
+  }
+
+  /// The canonical reason phrase of a status code registered by RFC 9110, such as `Not Found` for 404
+  /// Returns `None` for status codes that aren't registered
+  #[inline]
+  pub fn canonical_reason(status: u16) -> Option<&'static str> {
+    let reason = match status {
+      100 => "Continue",
+      101 => "Switching Protocols",
+      200 => "OK",
+      201 => "Created",
+      202 => "Accepted",
+      203 => "Non-Authoritative Information",
+      204 => "No Content",
+      205 => "Reset Content",
+      206 => "Partial Content",
+      300 => "Multiple Choices",
+      301 => "Moved Permanently",
+      302 => "Found",
+      303 => "See Other",
+      304 => "Not Modified",
+      305 => "Use Proxy",
+      307 => "Temporary Redirect",
+      308 => "Permanent Redirect",
+      400 => "Bad Request",
+      401 => "Unauthorized",
+      402 => "Payment Required",
+      403 => "Forbidden",
+      404 => "Not Found",
+      405 => "Method Not Allowed",
+      406 => "Not Acceptable",
+      407 => "Proxy Authentication Required",
+      408 => "Request Timeout",
+      409 => "Conflict",
+      410 => "Gone",
+      411 => "Length Required",
+      412 => "Precondition Failed",
+      413 => "Content Too Large",
+      414 => "URI Too Long",
+      415 => "Unsupported Media Type",
+      416 => "Range Not Satisfiable",
+      417 => "Expectation Failed",
+      421 => "Misdirected Request",
+      422 => "Unprocessable Content",
+      426 => "Upgrade Required",
+      428 => "Precondition Required",
+      429 => "Too Many Requests",
+      431 => "Request Header Fields Too Large",
+      451 => "Unavailable For Legal Reasons",
+      500 => "Internal Server Error",
+      501 => "Not Implemented",
+      502 => "Bad Gateway",
+      503 => "Service Unavailable",
+      504 => "Gateway Timeout",
+      505 => "HTTP Version Not Supported",
+      _ => return None,
+    };
+    Some(reason)
+  }
+
+  /// A response with no headers and no body telling the client why its request couldn't be parsed
+  /// `Error::Malformed`, `Error::Incomplete`, and `Error::ObsFold` map to 400 Bad Request, `Error::TooManyHeaders` to 431 Request Header Fields Too Large,
+  /// `Error::MethodNotAllowed` to 405 Method Not Allowed, and `Error::BodyTooLarge` to 413 Content Too Large.
+  /// A 405 response must also list the allowed methods in an `Allow` header
+  ///
+  /// ```rust
+  /// use htpp::{Error, Response};
+  ///
+  /// #[cfg(feature = "serialize")]
+/// assert!(Response::from_error(&Error::Malformed).as_bytes() == b"HTTP/1.1 400 Bad Request\r\n\r\n");
+  /// ```
+  #[inline]
+  pub fn from_error(err: &Error) -> Response<'static, 'static> {
+    let status = match err {
+      Error::Malformed | Error::Incomplete { .. } | Error::ObsFold => 400,
+      Error::TooManyHeaders => 431,
+      Error::MethodNotAllowed(_) => 405,
+      Error::BodyTooLarge { .. } => 413,
+    };
+    Response::new(status, Response::canonical_reason(status).unwrap_or_default(), &[], b"")
+  }
+
+  /// The reason phrase of the response, or the canonical reason phrase of its status code if the reason phrase is empty
+  /// Returns an empty string if both are missing
+  #[inline]
+  pub fn status_text(&self) -> &'a str {
+    if !self.reason.is_empty() {return self.reason;}
+    Response::canonical_reason(self.status).unwrap_or_default()
+  }
+
+  /// Whether the response has a reason phrase that differs from the canonical one of its status code
+  /// Proxies can use it to keep intentionally nonstandard reason phrases
+  #[inline]
+  pub fn has_custom_reason(&self) -> bool {
+    !self.reason.is_empty() && Response::canonical_reason(self.status) != Some(self.reason)
+  }
+
+  /// Whether the response to a CONNECT request opens the tunnel, that is whether its status is 2xx.
+  /// The bytes after the header section are then the start of the tunnel and must not be parsed as HTTP.
+  /// The response doesn't tell which request it answers, only call it for the response to a CONNECT
+  #[inline]
+  pub fn is_tunnel_established(&self) -> bool {
+    (200..300).contains(&self.status)
+  }
+
+  /// The `100 Continue` interim response telling a client that expects it to send the request body
+  ///
+  /// ```rust
+  /// use htpp::Response;
+  ///
+  /// #[cfg(feature = "serialize")]
+/// assert!(Response::continue_interim().as_bytes() == b"HTTP/1.1 100 Continue\r\n\r\n");
+  /// ```
+  #[inline]
+  pub fn continue_interim() -> Response<'static, 'static> {
+    Response::new(100, "Continue", &[], b"")
+  }
+  /// A redirection response with the given status and its canonical reason phrase, a `Location` header, and an empty body with `Content-Length: 0`
+  /// The two headers are written to `headers_buf`.
+  /// Returns an Err(Error::Malformed) if the status isn't one of the redirects 301, 302, 303, 307, and 308,
+  /// or the location contains a space or a control character
+  ///
+  /// ```rust
+  /// use htpp::{Response, EMPTY_HEADER};
+  ///
+  /// let mut headers = [EMPTY_HEADER; 2];
+  /// let res = Response::redirect(301, "/new", &mut headers).unwrap();
+  /// #[cfg(feature = "serialize")]
+/// assert!(res.as_bytes() == b"HTTP/1.1 301 Moved Permanently\r\nLocation: /new\r\nContent-Length: 0\r\n\r\n");
+  /// ```
+  #[inline]
+  pub fn redirect(status: u16, location: &'a str, headers_buf: &'headers mut [Header<'a>; 2]) -> Result<Response<'a, 'headers>> {
+    if !matches!(status, 301 | 302 | 303 | 307 | 308) || location.bytes().any(|character| character == SPACE || character.is_ascii_control()) {
+      return Err(Error::Malformed);
+    }
+    *headers_buf = [Header::new("Location", location.as_bytes()), Header::new("Content-Length", b"0")];
+    Ok(Response::new(status, Response::canonical_reason(status).unwrap_or_default(), headers_buf, b""))
+  }
+  /// The response a server should send for the `Expect` header of the request, or `None` if the request doesn't have one
+  /// That is a `continue_interim` for `100-continue` and a `417 Expectation Failed` for any other expectation,
+  /// in which case the request must not be processed
+  ///
+  /// ```rust
+  /// use htpp::{Request, Response, EMPTY_HEADER};
+  ///
+  /// let mut headers = [EMPTY_HEADER; 10];
+  /// let req = Request::parse(b"PUT / HTTP/1.1\r\nExpect: 200-ok\r\n\r\n", &mut headers).unwrap();
+  /// assert!(Response::for_expectation(&req).unwrap().status == 417);
+  /// ```
+  #[inline]
+  pub fn for_expectation(req: &crate::Request) -> Option<Response<'static, 'static>> {
+    req.expect()?;
+    if req.expects_continue() {
+      Some(Response::continue_interim())
     } else {
-      bytes.extend(format!("HTTP/1.1 {} {}\r\n", self.status, self.reason).as_bytes());
+      Some(Response::new(417, "Expectation Failed", &[], b""))
     }
-    for header in self.headers.iter() {
-      if header.name.is_empty() {break;}
-      bytes.extend(header.name.as_bytes());
-      bytes.extend(b": ");
-      bytes.extend(header.val);
-      bytes.extend(b"\r\n");
+  }
+  /// The byte representation of the `Response` like `as_bytes`, checking that no part can end its line early and smuggle in another message
+  /// Returns an Err(EncodeError::IllegalStartLine) if the reason phrase contains a control character other than a tab,
+  /// an Err(EncodeError::IllegalByte) for the first header with an invalid name or a control character other than a tab in its value,
+  /// or an Err(EncodeError::LengthMismatch) if a `Content-Length` header doesn't match the length of the body.
+  /// Responses to HEAD requests and 304 responses carry a `Content-Length` without a body, write them with `EncodeOptions::bodiless`
+  #[cfg(feature = "serialize")]
+  #[inline]
+  pub fn try_as_bytes(&self) -> std::result::Result<Vec<u8>, EncodeError> {
+    self.try_as_bytes_with_options(&EncodeOptions::default())
+  }
+  /// The byte representation of the `Response` like `try_as_bytes` but with the given `EncodeOptions`
+  ///
+  /// ```rust
+  /// use htpp::{EncodeOptions, Header, Response};
+  ///
+  /// let headers = [Header::new("Content-Length", b"100")];
+  /// let res = Response::new(200, "OK", &headers, b"hello");
+  /// let options = EncodeOptions { auto_content_length: true, ..Default::default() };
+  /// assert!(res.try_as_bytes_with_options(&options).unwrap() == b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+  /// ```
+  #[cfg(feature = "serialize")]
+  #[inline]
+  pub fn try_as_bytes_with_options(&self, options: &EncodeOptions) -> std::result::Result<Vec<u8>, EncodeError> {
+    if self.reason.bytes().any(|character| character != HTAB && character.is_ascii_control()) {
+      return Err(EncodeError::IllegalStartLine);
+    }
+    validate_headers(self.headers)?;
+    if options.bodiless && self.body.is_empty() {return Ok(self.as_bytes());}
+    if !options.auto_content_length || find_header(self.headers, "Transfer-Encoding").is_some() {
+      validate_content_length(self.headers, self.body.len())?;
+      return Ok(self.as_bytes());
     }
+    let length = self.body.len().to_string();
+    let headers = with_content_length(self.headers, length.as_bytes());
+    Ok(Response::new(self.status, self.reason, &headers, self.body).as_bytes())
+  }
+  /// The byte representation of the `Response` transmittible over wire
+  #[cfg(feature = "serialize")]
+  #[inline]
+  pub fn as_bytes(&self) -> Vec<u8> {
+    let mut status = [0; 5];
+    let status = status_digits(self.status, &mut status);
+    let len = 9 + status.len() + 1 + self.reason.len() + 2 + serialized_len(self.headers) + self.body.len();
+    let mut bytes = Vec::with_capacity(len);
+    bytes.extend(b"HTTP/1.1 ");
+    bytes.extend(status);
+    // the space before the reason phrase is required by the grammar even if the reason phrase is empty
+    bytes.push(SPACE);
+    bytes.extend(self.reason.as_bytes());
     bytes.extend(b"\r\n");
+    write_headers(self.headers, &mut bytes);
     bytes.extend(self.body);
     bytes
   }
   /// Parses the bytes of an HTTP response into a `Response`
-  /// It parses headers into the `header_buf` you pass, if there is more headers than the length of the buffer you pass, an Err(Error::TooManyHeaders) is returned
+  /// It parses headers into the `header_buf` you pass, if there is more headers than the length of the buffer you pass, an Err(Error::TooManyHeaders) is returned.
+  /// The `headers` of the returned `Response` only hold the parsed headers, not the rest of the buffer
   #[inline]
   pub fn parse(slice: &'a [u8], header_buf: &'headers mut [Header<'a>]) -> Result<Response<'a, 'headers>> {
-    parse_http_version(slice)?;
-    let mut offset: usize = 9;
-    let (status, reason, read) = parse_status(&slice[offset..])?;
-    offset += read;
-    let read = parse_headers(&slice[offset..], header_buf)?;
+    Response::parse_with_options(slice, header_buf, &ParseOptions::default())
+  }
+  /// Parses the bytes of an HTTP response like `Response::parse` but returns the position of each part instead of borrowing them
+  /// The position of each header is written to `header_indices`, if there is more headers than its length an Err(Error::TooManyHeaders) is returned.
+  /// Turn the result back into a `Response` with `ResponseIndices::response`. Returns an Err(Error::Malformed) if the slice is longer than `u32::MAX` bytes
+  #[inline]
+  pub fn parse_indices(slice: &[u8], header_indices: &mut [HeaderIndices]) -> Result<ResponseIndices> {
+    let (_, mut offset) = parse_http_version(slice).map_err(|err| head_incomplete(err, slice))?;
+    let (status, reason, read) = parse_status(slice.get(offset..).ok_or(Error::Malformed)?, &ParseOptions::default())
+      .map_err(|err| head_incomplete(err, slice))?;
+    let reason = indices(slice, reason.as_bytes())?;
+    let (offset, headers) = parse_header_indices(slice, offset + read, header_indices).map_err(|err| head_incomplete(err, slice))?;
+    let body = indices(slice, &slice[offset..])?;
+    Ok(ResponseIndices { status, reason, headers, body })
+  }
+  /// Parses the bytes of an HTTP response into a `Response` like `Response::parse` but with the given `ParseOptions`
+  #[inline]
+  pub fn parse_with_options(slice: &'a [u8], header_buf: &'headers mut [Header<'a>], options: &ParseOptions) -> Result<Response<'a, 'headers>> {
+    Response::parse_with_stats(slice, header_buf, options, &mut ())
+  }
+  /// Parses the bytes of an HTTP response like `Response::parse_with_options`, reporting each header, the length of the head,
+  /// and the use of a lenient option to `stats`
+  #[inline]
+  pub fn parse_with_stats(slice: &'a [u8], header_buf: &'headers mut [Header<'a>], options: &ParseOptions, stats: &mut impl StatSink) -> Result<Response<'a, 'headers>> {
+    let (_, mut offset) = parse_http_version(slice).map_err(|err| head_incomplete(err, slice))?;
+    let (status, reason, read) = parse_status(slice.get(offset..).ok_or(Error::Malformed)?, options).map_err(|err| head_incomplete(err, slice))?;
+    if reason.is_empty() && options.require_reason {return Err(Error::Malformed);}
+    if options.require_canonical_reason && !reason.is_empty() {
+      if let Some(canonical) = Response::canonical_reason(status) {
+        if !reason.eq_ignore_ascii_case(canonical) {return Err(Error::Malformed);}
+      }
+    }
     offset += read;
-    Ok(Response::new(status, reason, header_buf, &slice[offset..]))
+    if slice.get(offset - 2) != Some(&CR) {stats.relaxed();}
+    let mut cursor = Cursor::default();
+    parse_headers_until(slice.get(offset..).ok_or(Error::Malformed)?, header_buf, &mut cursor, usize::MAX, options, stats).map_err(|err| head_incomplete(err, slice))?;
+    offset += cursor.offset;
+    stats.head(offset);
+    Ok(Response::new(status, reason, &header_buf[..cursor.parsed], &slice[offset..]))
   }
 }
+#[cfg(feature = "fmt")]
 impl<'a, 'headers> fmt::Display for Response<'a, 'headers> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
       let mut headers = String::new();
@@ -74,92 +377,111 @@ impl<'a, 'headers> fmt::Display for Response<'a, 'headers> {
         if header.to_string().is_empty() {continue;} 
         headers.push_str(&format!("{}\r\n", header));
       }
-      let body = match std::str::from_utf8(self.body) {
-        Ok(v) => {
-          v.to_string()
-        },
-        Err(_) => {
-          format!("{:?}", self.body)
-        },
-      };
-      if self.reason.is_empty() {
-        f.write_str(format!("HTTP/1.1 {}\r\n{}\r\n{}", self.status, headers, body).as_str())
-      } else {
-        f.write_str(format!("HTTP/1.1 {} {}\r\n{}\r\n{}", self.status, self.reason, headers, body).as_str())
-      }
+      let body = lossy_escape(self.body, DEFAULT_LIMIT);
+      f.write_str(format!("HTTP/1.1 {} {}\r\n{}\r\n{}", self.status, self.reason, headers, body).as_str())
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+/// The parameters of a `Keep-Alive` header, returned by `Response::keep_alive_params`
+pub struct KeepAlive {
+    /// The number of seconds the server keeps an idle connection open
+    pub timeout: Option<u64>,
+    /// The number of requests the server answers on the connection before closing it
+    pub max: Option<u64>,
+}
+
+
+#[inline]
+// writes the decimal digits of the status to the end of the buffer and returns them
+fn status_digits(mut status: u16, buf: &mut [u8; 5]) -> &[u8] {
+  let mut start = buf.len();
+  loop {
+    start -= 1;
+    buf[start] = b'0' + (status % 10) as u8;
+    status /= 10;
+    if status == 0 {return &buf[start..];}
+  }
+}
+
+#[cfg(any(test, feature = "encoding"))]
+#[inline]
+// the unquoted value of the `charset` parameter of a media type such as `text/html; charset="utf-8"`
+fn charset(content_type: &[u8]) -> Option<&[u8]> {
+  content_type.split(|character| *character == b';').skip(1).find_map(|param| {
+    let (name, val) = param.split_at(param.iter().position(|character| *character == b'=')?);
+    if !name.trim_ascii().eq_ignore_ascii_case(b"charset") {return None;}
+    let val = val[1..].trim_ascii();
+    Some(val.strip_prefix(b"\"").and_then(|val| val.strip_suffix(b"\"")).unwrap_or(val))
+  })
+}
 
 #[inline]
-fn parse_http_version(slice: &[u8]) -> Result<HttpVer> {
-  match slice.get(0..9) {
-    Some(b"HTTP/1.1 ") => Ok(HttpVer::One),
-    Some(b"HTTP/2.0 ") => Ok(HttpVer::Two),
-    _ => Err(Error::Malformed)
+// parses the version and the space after it, returns the version and the bytes read
+fn parse_http_version(slice: &[u8]) -> Result<(HttpVer, usize)> {
+  let (version, read) = HttpVer::parse(slice)?;
+  // a `Response` has no version and is written back as HTTP/1.1, so HTTP/1.0 responses aren't parsed
+  if version == HttpVer::OneZero {return Err(Error::Malformed);}
+  match slice.get(read) {
+    Some(&SPACE) => Ok((version, read + 1)),
+    None => Err(Error::Incomplete { needed: None }),
+    Some(_) => Err(Error::Malformed),
   }
 }
 
 #[inline]
-//parses the method and removes white space after it
+//parses the status and the reason phrase after it
 //Returns the status, reason phrase, and bytes read
-fn parse_status(slice: &[u8]) -> Result<(u16, &str, usize)> {
+fn parse_status<'a>(slice: &'a [u8], options: &ParseOptions) -> Result<(u16, &'a str, usize)> {
   for (counter, character) in slice.iter().enumerate() {
     // a number character
     if (48..=57).contains(character) {
       continue;
-    } else if *character == SPACE {
-      let status = &slice[..counter];
-      if status.len() > 3 {
-        return Err(Error::Malformed);
-      }
+    }
+    let status = &slice[..counter];
+    if status.len() != 3 {
+      return Err(Error::Malformed);
+    }
+    //SAFETY: already checked that the input is valid ascii
+    let status = str::parse::<u16>(unsafe {std::str::from_utf8_unchecked(status)}).unwrap();
+    if *character == SPACE {
       //there is a reason phrase
-      if (65..=90).contains(&slice[counter+1]) || (97..=122).contains(&slice[counter+1]) {
-        let reason = parse_reason(&slice[(counter+1)..])?;
-        //SAFETY: already checked that the input is valid ascii
-        return Ok((str::parse::<u16>(unsafe {std::str::from_utf8_unchecked(status)}).unwrap(), reason.0, counter + 1 + reason.1));
-        //there is no reason phrase
-      } else if slice[counter+1] == CR {
-        if slice[counter+2] != LF {
-          return Err(Error::Malformed);
-        }
-        //SAFETY: already checked that the input is valid ascii
-        return Ok((str::parse::<u16>(unsafe {std::str::from_utf8_unchecked(status)}).unwrap(), "", counter + 3));
-      } else {return Err(Error::Malformed);}
-    } else if *character == CR {
-      let status = &slice[..counter];
-      if status.len() > 3 {
-        return Err(Error::Malformed);
-      }
-      if slice[counter+1] != LF {
-        return Err(Error::Malformed);
+      if slice.get(counter+1).is_some_and(u8::is_ascii_alphabetic) {
+        let reason = parse_reason(&slice[(counter+1)..], options)?;
+        return Ok((status, reason.0, counter + 1 + reason.1));
       }
-      //SAFETY: already checked that the input is valid ascii
-      return Ok((str::parse::<u16>(unsafe {std::str::from_utf8_unchecked(status)}).unwrap(), "", counter + 2));
-    } else {
-      return Err(Error::Malformed);
+      //there is no reason phrase
+      return match line_terminator(&slice[(counter+1)..], options) {
+        Some(read) => Ok((status, "", counter + 1 + read)),
+        None if partial_terminator(&slice[(counter+1)..]) => Err(Error::Incomplete { needed: None }),
+        None => Err(Error::Malformed),
+      };
     }
+    return match line_terminator(&slice[counter..], options) {
+      Some(read) => Ok((status, "", counter + read)),
+      None if partial_terminator(&slice[counter..]) => Err(Error::Incomplete { needed: None }),
+      None => Err(Error::Malformed),
+    };
   }
-  Err(Error::Malformed)
+  if slice.len() <= 3 {Err(Error::Incomplete { needed: None })} else {Err(Error::Malformed)}
 }
 
 
 #[inline]
-fn parse_reason(slice: &[u8]) -> Result<(&str, usize)> {
+// the reason phrase is made of visible characters and single spaces between words
+fn parse_reason<'a>(slice: &'a [u8], options: &ParseOptions) -> Result<(&'a str, usize)> {
   for (counter, character) in slice.iter().enumerate() {
-    if HEADER_NAME_SAFE[*character as usize] {
+    if character.is_ascii_graphic() || (*character == SPACE && counter > 0 && slice[counter-1] != SPACE) {
       continue;
-    } else if *character == CR {
-      let reason = &slice[..counter];
-      if slice[counter+1] != LF {
-        return Err(Error::Malformed);
-      }
-      //SAFETY: already checked that the input is valid ascii
-      return Ok( (unsafe { std::str::from_utf8_unchecked(reason) }, counter+2));
-    } else {
-      return Err(Error::Malformed);
     }
+    let reason = &slice[..counter];
+    if reason.last() == Some(&SPACE) {return Err(Error::Malformed);}
+    return match line_terminator(&slice[counter..], options) {
+      //SAFETY: already checked that the input is valid ascii
+      Some(read) => Ok( (unsafe { std::str::from_utf8_unchecked(reason) }, counter+read)),
+      None if partial_terminator(&slice[counter..]) => Err(Error::Incomplete { needed: None }),
+      None => Err(Error::Malformed),
+    };
   }
-  Err(Error::Malformed)
+  Err(Error::Incomplete { needed: None })
 }
-
@@ -0,0 +1,250 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Building the signature base of HTTP Message Signatures (RFC 9421)
+//!
+//! Only the canonicalization is done here, signing the signature base and verifying signatures is left to the caller
+//!
+//! ```rust
+//! use htpp::{Request, EMPTY_HEADER, sig::{signature_base, Component, SigParams}};
+//!
+//! let req = b"GET /foo?a=1 HTTP/1.1\r\nHost: Example.com\r\n\r\n";
+//! let mut headers = [EMPTY_HEADER; 10];
+//! let parsed = Request::parse(req, &mut headers).unwrap();
+//! let params = SigParams { created: Some(1618884473), keyid: Some("test-key"), ..SigParams::default() };
+//! let mut base = Vec::new();
+//! signature_base(&parsed, &[Component::Method, Component::Authority, Component::Path], &params, &mut base).unwrap();
+//! assert!(base == b"\"@method\": GET\n\"@authority\": example.com\n\"@path\": /foo\n\
+//! \"@signature-params\": (\"@method\" \"@authority\" \"@path\");created=1618884473;keyid=\"test-key\"");
+//! ```
+
+use std::fmt;
+
+use crate::uri::{classify_target, is_scheme, RequestTarget};
+use crate::host::{default_port, request_authority};
+use crate::Request;
+
+
+/// A component covered by a signature
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Component<'a> {
+  /// `@method`, the method of the request
+  Method,
+  /// `@target-uri`, the full URL of the request. Reconstructed from `SigParams::scheme`, the authority, and the target for requests without an absolute-form target
+  TargetUri,
+  /// `@authority`, the lowercased host and port of the request without the default port of its scheme
+  Authority,
+  /// `@scheme`, the lowercased scheme of the request, from an absolute-form target or else from `SigParams::scheme`
+  Scheme,
+  /// `@request-target`, the target of the request exactly as sent
+  RequestTarget,
+  /// `@path`, the path of the request without its query
+  Path,
+  /// `@query`, the query of the request including the leading `?`
+  Query,
+  /// A header field, identified by its case-insensitive name
+  Field(&'a str),
+}
+impl<'a> fmt::Display for Component<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Method => "@method",
+            Self::TargetUri => "@target-uri",
+            Self::Authority => "@authority",
+            Self::Scheme => "@scheme",
+            Self::RequestTarget => "@request-target",
+            Self::Path => "@path",
+            Self::Query => "@query",
+            Self::Field(name) => {
+              for character in name.chars() {
+                fmt::Write::write_char(f, character.to_ascii_lowercase())?;
+              }
+              return Ok(());
+            },
+        };
+        f.write_str(name)
+    }
+}
+
+/// The signature parameters serialized at the end of the signature base, in the order of the fields,
+/// and the scheme the request was received over
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct SigParams<'a> {
+    /// The `created` parameter, the creation time as a UNIX timestamp
+    pub created: Option<u64>,
+    /// The `expires` parameter, the expiration time as a UNIX timestamp
+    pub expires: Option<u64>,
+    /// The `keyid` parameter
+    pub keyid: Option<&'a str>,
+    /// The `nonce` parameter
+    pub nonce: Option<&'a str>,
+    /// The `alg` parameter
+    pub alg: Option<&'a str>,
+    /// The `tag` parameter
+    pub tag: Option<&'a str>,
+    /// The scheme the request was received over, e.g. `https` on a TLS connection. It isn't serialized,
+    /// `@scheme` and `@target-uri` need it for requests whose target has no scheme
+    pub scheme: Option<&'a str>,
+}
+
+/// All errors that could result from building a signature base
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SigError {
+  /// A covered component isn't present in the request
+  MissingComponent,
+  /// A component is covered more than once
+  DuplicateComponent,
+  /// A string parameter or field name can't be serialized as a structured field string
+  InvalidParam,
+}
+impl fmt::Display for SigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let err = match self {
+            Self::MissingComponent => "covered component missing from the request",
+            Self::DuplicateComponent => "component covered more than once",
+            Self::InvalidParam => "invalid signature parameter",
+        };
+        f.write_str(err)
+    }
+}
+impl std::error::Error for SigError {}
+
+
+/// Writes the signature base of the request covering `components` to `out`
+/// Each component is written as a `"name": value` line followed by the `@signature-params` line, lines are separated by `\n`.
+/// Field values are trimmed and repeated fields are joined with `, ` in the order they appear.
+/// On error `out` may hold part of the signature base
+#[inline]
+pub fn signature_base(req: &Request, components: &[Component], params: &SigParams, out: &mut Vec<u8>) -> Result<(), SigError> {
+  let target = classify_target(req.path.as_bytes()).map_err(|_| SigError::MissingComponent)?;
+  if params.scheme.is_some_and(|scheme| !is_scheme(scheme.as_bytes())) {return Err(SigError::InvalidParam);}
+  for (index, component) in components.iter().enumerate() {
+    if components[..index].iter().any(|other| same_component(component, other)) {
+      return Err(SigError::DuplicateComponent);
+    }
+    if let Component::Field(name) = component {
+      if name.is_empty() || !name.bytes().all(|character| character.is_ascii_graphic() && character != b'"' && character != b'\\') {
+        return Err(SigError::InvalidParam);
+      }
+    }
+    out.push(b'"');
+    out.extend(component.to_string().as_bytes());
+    out.extend(b"\": ");
+    write_component_value(req, &target, params.scheme, component, out)?;
+    out.push(b'\n');
+  }
+  out.extend(b"\"@signature-params\": ");
+  write_signature_params(components, params, out)
+}
+
+/// Writes the value of the `@signature-params` component, the inner list of covered components followed by the parameters
+#[inline]
+pub fn write_signature_params(components: &[Component], params: &SigParams, out: &mut Vec<u8>) -> Result<(), SigError> {
+  out.push(b'(');
+  for (index, component) in components.iter().enumerate() {
+    if index > 0 {out.push(b' ');}
+    out.push(b'"');
+    out.extend(component.to_string().as_bytes());
+    out.push(b'"');
+  }
+  out.push(b')');
+  if let Some(created) = params.created {
+    out.extend(format!(";created={created}").as_bytes());
+  }
+  if let Some(expires) = params.expires {
+    out.extend(format!(";expires={expires}").as_bytes());
+  }
+  for (name, val) in [("keyid", params.keyid), ("nonce", params.nonce), ("alg", params.alg), ("tag", params.tag)] {
+    if let Some(val) = val {
+      // structured field strings only allow printable ASCII and escape `"` and `\`
+      if !val.bytes().all(|character| character == b' ' || character.is_ascii_graphic()) {
+        return Err(SigError::InvalidParam);
+      }
+      out.extend(format!(";{name}=\"").as_bytes());
+      for character in val.bytes() {
+        if character == b'"' || character == b'\\' {out.push(b'\\');}
+        out.push(character);
+      }
+      out.push(b'"');
+    }
+  }
+  Ok(())
+}
+
+#[inline]
+fn same_component(a: &Component, b: &Component) -> bool {
+  match (a, b) {
+    (Component::Field(a), Component::Field(b)) => a.eq_ignore_ascii_case(b),
+    _ => a == b,
+  }
+}
+
+#[inline]
+fn write_component_value(req: &Request, target: &RequestTarget, scheme: Option<&str>, component: &Component, out: &mut Vec<u8>) -> Result<(), SigError> {
+  match component {
+    Component::Method => out.extend(req.method.as_str().as_bytes()),
+    Component::RequestTarget => out.extend(req.path.as_bytes()),
+    Component::Path => {
+      let path = path_and_query(target).ok_or(SigError::MissingComponent)?.0;
+      out.extend(if path.is_empty() {"/"} else {path}.as_bytes());
+    },
+    Component::Query => {
+      out.push(b'?');
+      out.extend(path_and_query(target).ok_or(SigError::MissingComponent)?.1.unwrap_or_default().as_bytes());
+    },
+    Component::Authority => write_authority(req, scheme, out)?,
+    Component::Scheme => match target {
+      RequestTarget::Absolute { scheme, .. } => out.extend(scheme.to_ascii_lowercase().as_bytes()),
+      _ => out.extend(scheme.ok_or(SigError::MissingComponent)?.to_ascii_lowercase().as_bytes()),
+    },
+    Component::TargetUri => match target {
+      RequestTarget::Absolute { .. } => out.extend(req.path.as_bytes()),
+      _ => {
+        // RFC 9110 section 7.1, the target URI of the other forms is rebuilt from the scheme, the authority, and the path and query
+        out.extend(scheme.ok_or(SigError::MissingComponent)?.to_ascii_lowercase().as_bytes());
+        out.extend(b"://");
+        write_authority(req, scheme, out)?;
+        if let RequestTarget::Origin(path) = target {out.extend(path.as_bytes());}
+      },
+    },
+    Component::Field(name) => {
+      let mut lines = req.headers.iter().filter(|header| header.name.eq_ignore_ascii_case(name)).peekable();
+      if lines.peek().is_none() {return Err(SigError::MissingComponent);}
+      for (index, header) in lines.enumerate() {
+        if index > 0 {out.extend(b", ");}
+        out.extend(header.val.trim_ascii());
+      }
+    },
+  }
+  Ok(())
+}
+
+#[inline]
+// the path and the query of an origin-form or absolute-form target
+fn path_and_query<'a>(target: &RequestTarget<'a>) -> Option<(&'a str, Option<&'a str>)> {
+  let target = match target {
+    RequestTarget::Origin(path) => path,
+    RequestTarget::Absolute { path, .. } => path,
+    _ => return None,
+  };
+  match target.split_once('?') {
+    Some((path, query)) => Some((path, Some(query))),
+    None => Some((target, None)),
+  }
+}
+
+#[inline]
+// the lowercased authority without the default port of the scheme of the target, or of `received_scheme` if the target has none
+fn write_authority(req: &Request, received_scheme: Option<&str>, out: &mut Vec<u8>) -> Result<(), SigError> {
+  let (scheme, host, port) = request_authority(req).map_err(|_| SigError::MissingComponent)?;
+  let scheme = scheme.or(received_scheme);
+  out.extend(host.to_ascii_lowercase().as_bytes());
+  if let Some(port) = port.filter(|port| *port != scheme.and_then(default_port).unwrap_or(0)) {
+    out.extend(format!(":{port}").as_bytes());
+  }
+  Ok(())
+}
@@ -0,0 +1,78 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Counters filled in while parsing, to tune buffer sizes and limits from production traffic
+//!
+//! `Request::parse_with_stats` and `Response::parse_with_stats` report to a `StatSink` as they parse.
+//! `ParseStats` keeps plain counters, and `()` ignores everything so the `parse_with_options` entry points that use it pay nothing
+//!
+//! ```rust
+//! use htpp::{ParseOptions, Request, EMPTY_HEADER, stats::ParseStats};
+//!
+//! let mut headers = [EMPTY_HEADER; 10];
+//! let mut stats = ParseStats::default();
+//! Request::parse_with_stats(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n", &mut headers, &ParseOptions::default(), &mut stats).unwrap();
+//! assert!(stats.headers == 1);
+//! assert!(stats.largest_value == 11);
+//! ```
+
+use std::fmt;
+
+
+/// Receives what the parser finds, every method does nothing by default
+pub trait StatSink {
+  /// Called for every parsed header with the length of its value
+  #[inline]
+  fn header(&mut self, val_len: usize) {}
+
+  /// Called once the start line and headers are parsed with the number of bytes they take
+  #[inline]
+  fn head(&mut self, len: usize) {}
+
+  /// Called when the message was only accepted thanks to a lenient `ParseOptions`, such as a line ending with a bare `\n`
+  #[inline]
+  fn relaxed(&mut self) {}
+}
+impl StatSink for () {}
+
+/// A `StatSink` counting what it is told
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct ParseStats {
+    /// The bytes of the start lines and headers scanned
+    pub bytes_scanned: usize,
+    /// The headers parsed
+    pub headers: usize,
+    /// The length of the largest header value seen
+    pub largest_value: usize,
+    /// Whether any message was only accepted thanks to a lenient option
+    pub relaxed: bool,
+}
+impl StatSink for ParseStats {
+  #[inline]
+  fn header(&mut self, val_len: usize) {
+    self.headers += 1;
+    self.largest_value = self.largest_value.max(val_len);
+  }
+
+  #[inline]
+  fn head(&mut self, len: usize) {
+    self.bytes_scanned += len;
+  }
+
+  #[inline]
+  fn relaxed(&mut self) {
+    self.relaxed = true;
+  }
+}
+#[cfg(feature = "fmt")]
+impl fmt::Display for ParseStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      write!(f, "{} bytes scanned, {} headers, largest value {} bytes", self.bytes_scanned, self.headers, self.largest_value)?;
+      if self.relaxed {f.write_str(", relaxed")?;}
+      Ok(())
+    }
+}
@@ -61,6 +61,42 @@ req! {
     }
 }
 
+#[cfg(all(feature = "fmt", feature = "serialize"))]
+req! {
+    test_request_delete,
+    b"DELETE /item/5 HTTP/1.1\r\n\r\n",
+    |req| {
+        assert_eq!(req.method, Method::Delete);
+        assert_eq!(req.path, "/item/5");
+        assert_eq!(req.method.to_string(), "DELETE");
+        assert_eq!(req.as_bytes(), b"DELETE /item/5 HTTP/1.1\r\n\r\n");
+    }
+}
+
+#[test]
+fn test_request_empty_header_value_at_boundaries() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    // an empty value right before the empty line ending the header section
+    for buf in [&b"GET / HTTP/1.1\r\nCookie:\r\n\r\n"[..], b"GET / HTTP/1.1\r\nCookie: \r\n\r\n", b"GET / HTTP/1.1\r\nCookie:\t\r\n\r\n"] {
+        let req = crate::Request::parse(buf, &mut headers).unwrap();
+        assert_eq!(req.headers, &[crate::Header::new("Cookie", b"")]);
+        assert_eq!(req.body, b"");
+    }
+    // an empty value at the exact end of the buffer
+    for (buf, needed) in [(&b"GET / HTTP/1.1\r\nCookie:"[..], 4), (b"GET / HTTP/1.1\r\nCookie: ", 4), (b"GET / HTTP/1.1\r\nCookie:\r", 3), (b"GET / HTTP/1.1\r\nCookie:\r\n", 2)] {
+        assert_eq!(crate::Request::parse(buf, &mut headers), Err(Error::Incomplete { needed: Some(needed) }));
+    }
+    assert_eq!(crate::fields::parse_header_value(b"\r\n"), Ok((&b""[..], 2)));
+    assert_eq!(crate::fields::parse_header_value(b"\r"), Err(Error::Incomplete { needed: None }));
+    assert_eq!(crate::fields::parse_header_value(b""), Err(Error::Incomplete { needed: None }));
+    assert_eq!(crate::fields::parse_header_value(b"\rX"), Err(Error::Malformed));
+    crate::conformance::assert_never_panics_on_prefixes(b"GET / HTTP/1.1\r\nCookie:\r\nX-Empty: \r\n\r\n", |buf| {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let _ = crate::Request::parse(buf, &mut headers);
+        let _ = crate::fields::count_headers(buf);
+    });
+}
+
 req! {
     // test the scalar parsing
     test_request_header_value_htab_short,
@@ -96,6 +132,17 @@ req! {
     }
 }
 
+#[test]
+fn test_request_headers_len() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(b"GET / HTTP/1.1\r\nHost: foo.com\r\nAccept: */*\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.headers.len(), 2);
+    let req = crate::Request::parse(b"GET / HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert!(req.headers.is_empty());
+    let res = crate::Response::parse(b"HTTP/1.1 200 OK\r\nServer: foo\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(res.headers.len(), 1);
+}
+
 req! {
     test_request_with_string_body,
     b"GET / HTTP/1.1\r\nUser-Agent: foo.com\r\n\r\na string body",
@@ -133,6 +180,274 @@ req! {
     }
 }
 
+req! {
+    test_request_origin,
+    b"GET / HTTP/1.1\r\norigin: https://example.com:8443\r\n\r\n",
+    |req| {
+        assert_eq!(req.origin(), Some(crate::Origin::Tuple { scheme: "https", host: "example.com", port: Some(8443) }));
+    }
+}
+
+req! {
+    test_request_origin_null,
+    b"GET / HTTP/1.1\r\nOrigin: null\r\n\r\n",
+    |req| {
+        assert_eq!(req.origin(), Some(crate::Origin::Null));
+    }
+}
+
+req! {
+    test_request_origin_with_path,
+    b"GET / HTTP/1.1\r\nOrigin: https://example.com/index.html\r\n\r\n",
+    |req| {
+        assert_eq!(req.origin(), None);
+    }
+}
+
+#[test]
+fn test_method_eq_bytes() {
+    assert!(Method::Post.eq_bytes(b"POST"));
+    assert!(Method::Post == &b"POST"[..]);
+    assert!(Method::Post != &b"post"[..]);
+    assert!(Method::Get != &b"GET "[..]);
+}
+
+#[test]
+fn test_request_parse_partial() {
+    let buf = b"GET /index.html HTTP/1.1\r\nHost: foo.com\r\nAccept: */*\r\nBroken Header: x\r\nCookie: a=b\r\n\r\n";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let (partial, err) = crate::Request::parse_partial(buf, &mut headers);
+    assert_eq!(err, Some(Error::Malformed));
+    assert_eq!(partial.method, Some(Method::Get));
+    assert_eq!(partial.path, Some("/index.html"));
+    assert_eq!(partial.headers.len(), 2);
+    assert_eq!(partial.headers[0].name, "Host");
+    assert_eq!(partial.headers[1].name, "Accept");
+}
+
+#[test]
+fn test_request_parse_partial_valid() {
+    let buf = b"GET / HTTP/1.1\r\nHost: foo.com\r\n\r\n";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let (partial, err) = crate::Request::parse_partial(buf, &mut headers);
+    assert_eq!(err, None);
+    assert_eq!(partial.headers, &[crate::Header::new("Host", b"foo.com")]);
+}
+
+#[test]
+fn test_request_parse_bounded() {
+    let mut buf = b"GET /index.html HTTP/1.1\r\n".to_vec();
+    for i in 0..100 {
+        buf.extend(format!("X-Header-{i:03}: value-{i:03}\r\n").as_bytes());
+    }
+    buf.extend(b"\r\nbody");
+    let mut headers = [crate::EMPTY_HEADER; 100];
+    let mut expected_headers = [crate::EMPTY_HEADER; 100];
+    let expected = crate::Request::parse(&buf, &mut expected_headers).unwrap();
+
+    let mut yields = 0;
+    let mut outcome = crate::Request::parse_bounded(&buf, &mut headers, 1024).unwrap();
+    let req = loop {
+        match outcome {
+            crate::ParseOutcome::Complete(req) => break req,
+            crate::ParseOutcome::Yielded { state } => {
+                yields += 1;
+                outcome = crate::Request::resume_bounded(&buf, &mut headers, state, 1024).unwrap();
+            },
+        }
+    };
+    assert_eq!(yields, 2);
+    assert_eq!(req, expected);
+
+    let Ok(crate::ParseOutcome::Yielded { state }) = crate::Request::parse_bounded(&buf, &mut headers, 1024) else {panic!("expected a yield")};
+    assert_eq!(crate::Request::resume_bounded(&buf[..20], &mut headers, state.clone(), 1024), Err(Error::Malformed));
+    assert_eq!(crate::Request::resume_bounded(&buf[..100], &mut headers, state.clone(), 1024), Err(Error::Malformed));
+    assert_eq!(crate::Request::resume_bounded(&buf, &mut [crate::EMPTY_HEADER; 5], state, 1024), Err(Error::Malformed));
+}
+
+#[test]
+fn test_request_parse_exact() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse_exact(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello", &mut headers).unwrap();
+    assert_eq!(req.body, b"hello");
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse_exact(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.body, b"5\r\nhello\r\n0\r\n\r\n");
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert!(crate::Request::parse_exact(b"GET / HTTP/1.1\r\n\r\n", &mut headers).is_ok());
+}
+
+#[test]
+fn test_request_parse_exact_trailing_bytes() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Request::parse_exact(b"GET / HTTP/1.1\r\n\r\njunk", &mut headers), Err(Error::Malformed));
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Request::parse_exact(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello\r\nGET /admin HTTP/1.1\r\n\r\n", &mut headers), Err(Error::Malformed));
+    assert_eq!(crate::Request::parse_exact(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\njunk", &mut headers), Err(Error::Malformed));
+    assert_eq!(crate::Request::parse_exact(b"POST / HTTP/1.1\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n", &mut headers), Err(Error::Malformed));
+}
+
+#[test]
+fn test_request_exact_truncated_chunks() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let full = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\nhello\r\n0\r\nX-Trailer: 1\r\n\r\n";
+    let head = full.windows(4).position(|window| window == b"\r\n\r\n").unwrap() + 4;
+    for len in head..full.len() {
+        assert!(matches!(crate::Request::parse_exact(&full[..len], &mut headers), Err(Error::Incomplete { .. })), "prefix of {len} bytes");
+    }
+    assert!(crate::Request::parse_exact(full, &mut headers).is_ok());
+    let cut = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhel";
+    assert_eq!(crate::Request::parse_exact(cut, &mut headers), Err(Error::Incomplete { needed: Some(4) }));
+    let mut out = Vec::new();
+    assert_eq!(crate::framing::decode_chunked(b"5\r\nhello\r", &mut out), Err(Error::Incomplete { needed: Some(1) }));
+    assert_eq!(crate::framing::decode_chunked(b"5\r\nhelloXY", &mut out), Err(Error::Malformed));
+    assert_eq!(crate::framing::decode_chunked(b"5x\r\nhello\r\n", &mut out), Err(Error::Malformed));
+    assert_eq!(crate::framing::decode_chunked(b"5\rx", &mut out), Err(Error::Malformed));
+}
+
+#[test]
+fn test_request_incomplete_needed() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let err = crate::Request::parse_exact(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhel", &mut headers).unwrap_err();
+    assert_eq!(err, Error::Incomplete { needed: Some(2) });
+    assert_eq!(err.needed(), Some(2));
+    assert_eq!(crate::Request::parse_exact(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\n", &mut headers), Err(Error::Incomplete { needed: Some(5) }));
+    assert_eq!(crate::Request::parse(b"GET / HTTP/1.1\r\nHost: a.com\r\n", &mut headers), Err(Error::Incomplete { needed: Some(2) }));
+    assert_eq!(crate::Request::parse(b"GET / HTTP/1.1\r\nHost: a.com\r\n\r", &mut headers), Err(Error::Incomplete { needed: Some(1) }));
+    assert_eq!(crate::fields::parse_headers(b"Host: a.com\r\n", &mut headers), Err(Error::Incomplete { needed: None }));
+    assert_eq!(Error::Malformed.needed(), None);
+    let options = crate::ParseOptions::default();
+    let truncated = b"GET / HTTP/1.1\r\nHost: a.com\r\n";
+    assert_eq!(crate::Request::parse_with_options(truncated, &mut headers, &options), Err(Error::Incomplete { needed: Some(2) }));
+    assert_eq!(crate::Request::parse_with_stats(truncated, &mut headers, &options, &mut ()), Err(Error::Incomplete { needed: Some(2) }));
+    assert_eq!(crate::Request::parse_exact_with_options(b"GET / HTTP/1.1\r", &mut headers, &options), Err(Error::Incomplete { needed: Some(3) }));
+    assert_eq!(crate::Request::parse_bounded(truncated, &mut headers, usize::MAX), Err(Error::Incomplete { needed: Some(2) }));
+    assert_eq!(crate::Request::parse_salvage(truncated, &mut headers).1, Some(Error::Incomplete { needed: Some(2) }));
+    assert_eq!(crate::Request::parse_partial(b"GET / HTTP/1", &mut headers).1, Some(Error::Incomplete { needed: Some(4) }));
+    let truncated = b"HTTP/1.1 200 OK\r\nServer: a\r\n\r";
+    assert_eq!(crate::Response::parse_with_options(truncated, &mut headers, &options), Err(Error::Incomplete { needed: Some(1) }));
+    assert_eq!(crate::Response::parse_with_stats(truncated, &mut headers, &options, &mut ()), Err(Error::Incomplete { needed: Some(1) }));
+}
+
+#[test]
+fn test_request_parse_allowed() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let buf = b"PUT /upload HTTP/1.1\r\nContent-Length: 2\r\n\r\nhi";
+    let err = crate::Request::parse_allowed(buf, &mut headers, &[Method::Get]).unwrap_err();
+    assert_eq!(err, Error::MethodNotAllowed("PUT".into()));
+    assert_eq!((err.code(), crate::Response::from_error(&err).status), (402, 405));
+    // the method is rejected before the rest of the request is looked at
+    assert_eq!(crate::Request::parse_allowed(b"PUT /a b", &mut headers, &[Method::Get]), Err(Error::MethodNotAllowed("PUT".into())));
+    let req = crate::Request::parse_allowed(b"GET / HTTP/1.1\r\n\r\n", &mut headers, &[Method::Get, Method::Post]).unwrap();
+    assert_eq!(req.method, Method::Get);
+    assert_eq!(crate::Request::parse_allowed(b"GE", &mut headers, &[Method::Get]), Err(Error::Incomplete { needed: Some(4) }));
+    // extension methods are copied into the error
+    let err = crate::Request::parse_allowed(b"get / HTTP/1.1\r\n\r\n", &mut headers, &[Method::Get]).unwrap_err();
+    assert_eq!(err, Error::MethodNotAllowed("get".into()));
+    let Error::MethodNotAllowed(token) = err else {unreachable!()};
+    assert_eq!(Method::from_bytes(token.as_bytes()), Ok(Method::Other("get")));
+    assert_eq!(crate::Request::parse_allowed(b"PURGE / HTTP/1.1\r\n\r\n", &mut headers, &[Method::Other("PURGE")]).unwrap().method, Method::Other("PURGE"));
+}
+
+req! {
+    test_request_accept_language,
+    b"GET / HTTP/1.1\r\nAccept-Language: ja,en-us;q=0.7,en;q=0.3\r\n\r\n",
+    |req| {
+        assert_eq!(req.accept_language(), vec![("ja", 1.0), ("en-us", 0.7), ("en", 0.3)]);
+    }
+}
+
+req! {
+    test_request_accept_language_ordering,
+    b"GET / HTTP/1.1\r\nAccept-Language: fr;q=0.5, de, *;q=0.1, en;q=0.8, it;q=2\r\n\r\n",
+    |req| {
+        assert_eq!(req.accept_language(), vec![("de", 1.0), ("en", 0.8), ("fr", 0.5), ("*", 0.1)]);
+    }
+}
+
+req! {
+    test_request_accept_language_params,
+    b"GET / HTTP/1.1\r\nAccept-Language: fr;Q=0.9, en;foo=bar;q=0.5, de;q = 0.7, it;level=1\r\n\r\n",
+    |req| {
+        assert_eq!(req.accept_language(), vec![("it", 1.0), ("fr", 0.9), ("de", 0.7), ("en", 0.5)]);
+    }
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_as_bytes_round_trip() {
+    use crate::conformance::assert_roundtrip_parse_serialize;
+    let buf = b"POST /index.html HTTP/1.1\r\nHost: foo.com\r\nContent-Length: 5\r\n\r\nhello";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_roundtrip_parse_serialize(buf, |buf| crate::Request::parse(buf, &mut headers), |req| req.as_bytes());
+    let buf = b"HTTP/1.1 404 NotFound\r\nContent-Length: 5\r\n\r\nhello";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_roundtrip_parse_serialize(buf, |buf| crate::Response::parse(buf, &mut headers), |res| res.as_bytes());
+    assert_eq!(crate::Response::new(65535, "", &[], b"").as_bytes(), b"HTTP/1.1 65535 \r\n\r\n");
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_as_bytes_sorted() {
+    use crate::Header;
+    let headers = [Header::new("X-B", b"1"), Header::new("accept", b"*/*"), Header::new("x-b", b"2"), Header::new("Host", b"a.com")];
+    let expected = b"GET / HTTP/1.1\r\naccept: */*\r\nHost: a.com\r\nX-B: 1\r\nx-b: 2\r\n\r\n";
+    assert_eq!(crate::Request::new(Method::Get, "/", &headers, b"").as_bytes_sorted(), expected);
+    let reordered = [headers[3], headers[0], headers[2], headers[1]];
+    let sorted = crate::Request::new(Method::Get, "/", &reordered, b"").as_bytes_sorted();
+    assert_eq!(sorted, b"GET / HTTP/1.1\r\naccept: */*\r\nHost: a.com\r\nX-B: 1\r\nx-b: 2\r\n\r\n");
+    assert_eq!(crate::Request::new(Method::Get, "/", &reordered, b"").as_bytes(), b"GET / HTTP/1.1\r\nHost: a.com\r\nX-B: 1\r\nx-b: 2\r\naccept: */*\r\n\r\n");
+}
+
+#[test]
+fn test_request_parse_salvage_header_corruption() {
+    let buf = b"GET / HTTP/1.1\r\nHost: foo.com\r\nAccept: */*\r\nBad\x00Header: x\r\nCookie: a=b\r\n\r\n";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let (partial, err) = crate::Request::parse_salvage(buf, &mut headers);
+    assert_eq!(err, Some(Error::Malformed));
+    assert!(!partial.complete);
+    assert_eq!(partial.method, Some(Method::Get));
+    assert_eq!(partial.headers, &[crate::Header::new("Host", b"foo.com"), crate::Header::new("Accept", b"*/*")]);
+    assert!(buf[partial.offset..].starts_with(b"Bad\x00Header"));
+}
+
+#[test]
+fn test_request_parse_salvage_request_line_corruption() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let (partial, err) = crate::Request::parse_salvage(b"GET /\x7f HTTP/1.1\r\nHost: foo.com\r\n\r\n", &mut headers);
+    assert_eq!(err, Some(Error::Malformed));
+    assert!(!partial.complete);
+    assert_eq!(partial.method, None);
+    assert_eq!(partial.path, None);
+    assert!(partial.headers.is_empty());
+    assert_eq!(partial.offset, 0);
+}
+
+#[test]
+fn test_request_parse_salvage_valid() {
+    let buf = b"GET /index.html HTTP/1.1\r\nHost: foo.com\r\nAccept: */*\r\n\r\nbody";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let (partial, err) = crate::Request::parse_salvage(buf, &mut headers);
+    let mut strict_headers = [crate::EMPTY_HEADER; 10];
+    let strict = crate::Request::parse(buf, &mut strict_headers).unwrap();
+    assert_eq!(err, None);
+    assert!(partial.complete);
+    assert_eq!(partial.method, Some(strict.method));
+    assert_eq!(partial.path, Some(strict.path));
+    assert_eq!(partial.headers, strict.headers);
+    assert_eq!(&buf[partial.offset..], strict.body);
+}
+
+#[cfg(feature = "serialize")]
+req! {
+    test_request_line_len,
+    b"POST /index.html?q=1 HTTP/1.1\r\nHost: foo.com\r\n\r\n",
+    |req| {
+        assert_eq!(req.request_line_len(), b"POST /index.html?q=1 HTTP/1.1\r\n".len());
+        assert_eq!(&req.as_bytes()[..req.request_line_len()], b"POST /index.html?q=1 HTTP/1.1\r\n");
+    }
+}
+
 req! {
     test_request_newlines,
     b"GET / HTTP/1.1\nHost: foo.bar\n\n",
@@ -145,6 +460,31 @@ req! {
     should_panic
 }
 
+#[test]
+fn test_request_leading_crlf_option() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let strict = crate::ParseOptions::default();
+    assert_eq!(crate::Request::parse_with_options(b"\r\nGET / HTTP/1.1\r\n\r\n", &mut headers, &strict), Err(Error::Malformed));
+    let options = crate::ParseOptions { allow_leading_crlf: true, ..Default::default() };
+    let req = crate::Request::parse_with_options(b"\r\n\r\nGET / HTTP/1.1\r\nHost: foo.com\r\n\r\nbody", &mut headers, &options).unwrap();
+    assert_eq!(req, crate::Request::new(Method::Get, "/", &[crate::Header::new("Host", b"foo.com")], b"body"));
+    assert_eq!(crate::Request::parse_with_options(b"\r\n\r", &mut headers, &options), Err(Error::Incomplete { needed: Some(1) }));
+    assert_eq!(crate::Request::parse_with_options(b"\nGET / HTTP/1.1\r\n\r\n", &mut headers, &options), Err(Error::Malformed));
+    let options = crate::ParseOptions { allow_leading_crlf: true, allow_bare_lf: true, ..Default::default() };
+    assert!(crate::Request::parse_with_options(b"\nGET / HTTP/1.1\r\n\r\n", &mut headers, &options).is_ok());
+}
+
+#[test]
+fn test_request_bare_lf_with_option() {
+    let buf = b"GET / HTTP/1.1\nHost: a\n\n";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let options = crate::ParseOptions { allow_bare_lf: true, ..Default::default() };
+    let req = crate::Request::parse_with_options(buf, &mut headers, &options).unwrap();
+    assert_eq!(req, crate::Request::new(Method::Get, "/", &[crate::Header::new("Host", b"a")], b""));
+    assert_eq!(crate::Request::parse_with_options(buf, &mut headers, &crate::ParseOptions::default()), Err(Error::Malformed));
+    assert_eq!(crate::Request::parse(buf, &mut headers), Err(Error::Malformed));
+}
+
 req! {
     test_request_path_with_invalid_chars,
     b"GET /\\?wayne\\=5 HTTP/1.1\r\n",
@@ -175,6 +515,448 @@ req! {
     should_panic
 }
 
+#[cfg(feature = "serialize")]
+req! {
+    test_request_expects_continue,
+    b"PUT /upload HTTP/1.1\r\nContent-Length: 5\r\nExpect: 100-Continue\r\n\r\n",
+    |req| {
+        assert!(req.expects_continue());
+        assert_eq!(req.expect(), Some("100-Continue"));
+        assert_eq!(crate::Response::continue_interim().as_bytes(), b"HTTP/1.1 100 Continue\r\n\r\n");
+        assert_eq!(crate::Response::for_expectation(&req), Some(crate::Response::continue_interim()));
+    }
+}
+
+#[cfg(feature = "serialize")]
+req! {
+    test_request_unknown_expectation,
+    b"PUT /upload HTTP/1.1\r\nContent-Length: 5\r\nExpect:  200-ok \r\n\r\n",
+    |req| {
+        assert!(!req.expects_continue());
+        assert_eq!(req.expect(), Some("200-ok"));
+        assert_eq!(crate::Response::for_expectation(&req).unwrap().as_bytes(), b"HTTP/1.1 417 Expectation Failed\r\n\r\n");
+    }
+}
+
+req! {
+    test_request_does_not_expect_continue,
+    b"PUT /upload HTTP/1.1\r\nContent-Length: 5\r\n\r\n",
+    |req| {
+        assert!(!req.expects_continue());
+        assert_eq!(req.expect(), None);
+        assert_eq!(crate::Response::for_expectation(&req), None);
+    }
+}
+
+req! {
+    test_request_cookies,
+    b"GET / HTTP/1.1\r\nCookie: session=60; token=a=b==; session=61\r\nCookie: theme=dark;flag\r\n\r\n",
+    |req| {
+        assert_eq!(req.cookies().collect::<Vec<_>>(), vec![("session", "60"), ("token", "a=b=="), ("session", "61"), ("theme", "dark")]);
+        assert_eq!(req.cookie("session"), Some("60"));
+        assert_eq!(req.cookie("token"), Some("a=b=="));
+        assert_eq!(req.cookie("Session"), None);
+        assert_eq!(req.cookie("flag"), None);
+    }
+}
+
+#[test]
+fn test_request_header_pairs() {
+    let headers = [crate::Header::new("Host", b"a.com"), crate::EMPTY_HEADER, crate::Header::new("Accept", b"*/*")];
+    let req = crate::Request::new(Method::Get, "/", &headers, b"");
+    assert_eq!(req.header_pairs().collect::<Vec<_>>(), vec![("Host", &b"a.com"[..]), ("Accept", b"*/*")]);
+}
+
+req! {
+    test_request_headers_matching,
+    b"GET / HTTP/1.1\r\nX-Trace: 1\r\nHost: a.com\r\nx-span: 2\r\nAccept: */*\r\n\r\n",
+    |req| {
+        let custom = req.headers_matching(|header| header.name.len() >= 2 && header.name[..2].eq_ignore_ascii_case("X-"));
+        assert_eq!(custom.map(|header| header.name).collect::<Vec<_>>(), vec!["X-Trace", "x-span"]);
+        assert_eq!(req.headers_matching(|_| false).count(), 0);
+    }
+}
+
+req! {
+    test_request_header_entry_keeps_casing,
+    b"GET / HTTP/1.1\r\nX-Request-ID: 42\r\n\r\n",
+    |req| {
+        assert_eq!(req.header_entry("x-request-id"), Some(&crate::Header::new("X-Request-ID", b"42")));
+        assert_eq!(req.header_entry("X-Missing"), None);
+    }
+}
+
+#[test]
+fn test_request_url_and_query_str() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let mut queries = [crate::EMPTY_QUERY; 10];
+    let req = crate::Request::parse(b"GET /search?q=rust&page=2 HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.query_str(), Some("q=rust&page=2"));
+    let url = req.url(&mut queries).unwrap();
+    assert_eq!(url.path, "/search");
+    assert_eq!(url.query_params.unwrap()[1], crate::QueryParam::new("page", "2"));
+
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let mut queries = [crate::EMPTY_QUERY; 10];
+    let req = crate::Request::parse(b"GET /index.html HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.query_str(), None);
+    assert_eq!(req.url(&mut queries), Ok(crate::Url::new("/index.html", None)));
+
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let mut queries = [crate::EMPTY_QUERY; 10];
+    let req = crate::Request::parse(b"GET http://example.com/a?b=c HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.query_str(), Some("b=c"));
+    assert_eq!(req.url(&mut queries).unwrap().path, "/a");
+
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(b"GET /a?x=1#frag HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.query_str(), Some("x=1"));
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(b"GET /a#frag?x=1 HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.query_str(), None);
+
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let mut queries = [crate::EMPTY_QUERY; 10];
+    let req = crate::Request::parse(b"OPTIONS * HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.query_str(), None);
+    assert_eq!(req.url(&mut queries), Err(crate::UrlError::Path));
+}
+
+#[cfg(all(feature = "fmt", feature = "serialize"))]
+#[test]
+fn test_request_head() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(b"HEAD / HTTP/1.1\r\nHost: a.com\r\n\r\n", &mut headers).unwrap();
+    assert_eq!((req.method.clone(), req.path, req.body), (Method::Head, "/", &b""[..]));
+    assert_eq!(req.as_bytes(), b"HEAD / HTTP/1.1\r\nHost: a.com\r\n\r\n");
+    assert_eq!(Method::Head.to_string(), "HEAD");
+    // bytes after the header section of a HEAD request are not a body
+    let req = crate::Request::parse_exact(b"HEAD /index.html HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.body, b"");
+    assert_eq!(crate::Request::parse_exact(b"HEAD / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n", &mut headers), Err(Error::Malformed));
+}
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_method_expects_response_body() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(b"HEAD / HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert!(!req.method.expects_response_body());
+    for method in [Method::Get, Method::Post, Method::Put, Method::Delete, Method::Patch, Method::Options] {
+        assert!(method.expects_response_body(), "{method}");
+    }
+}
+
+#[cfg(all(feature = "fmt", feature = "serialize"))]
+#[test]
+fn test_request_patch() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    // the shortest PATCH request is accepted, the parser has no minimum length
+    let req = crate::Request::parse(b"PATCH / HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert_eq!((req.method, req.path), (Method::Patch, "/"));
+    let buf = b"PATCH /users/7 HTTP/1.1\r\nContent-Type: application/merge-patch+json\r\nContent-Length: 2\r\n\r\n{}";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    crate::conformance::assert_roundtrip_parse_serialize(buf, |buf| crate::Request::parse(buf, &mut headers), |req| req.as_bytes());
+    assert_eq!(Method::Patch.to_string(), "PATCH");
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_request_patch_json_round_trip() {
+    let buf = b"PATCH /users/7 HTTP/1.1\r\nHost: api.example.com\r\nContent-Type: application/json\r\nContent-Length: 16\r\n\r\n{\"name\":\"Alice\"}";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(buf, &mut headers).unwrap();
+    assert_eq!(req.method, Method::Patch);
+    assert_eq!(req.body, br#"{"name":"Alice"}"#);
+    assert_eq!(req.as_bytes(), buf);
+}
+
+#[cfg(all(feature = "fmt", feature = "serialize"))]
+#[test]
+fn test_request_extension_method() {
+    let buf = b"PURGE /cache/item HTTP/1.1\r\nHost: cdn.example.com\r\n\r\n";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(buf, &mut headers).unwrap();
+    assert_eq!((req.method.clone(), req.path), (Method::Other("PURGE"), "/cache/item"));
+    assert_eq!(req.method.to_string(), "PURGE");
+    assert_eq!(req.as_bytes(), buf);
+    let mut indices = [crate::indices::EMPTY_HEADER_INDICES; 10];
+    let parsed = crate::Request::parse_indices(buf, &mut indices).unwrap();
+    assert_eq!(parsed.method, 0..5);
+    assert_eq!(parsed.request(buf, &indices, &mut headers).unwrap().method, Method::Other("PURGE"));
+    // any tchar is allowed in a method token, separators and control bytes aren't
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Request::parse(b"M-SEARCH!~ * HTTP/1.1\r\n\r\n", &mut headers), Err(Error::Malformed));
+    assert_eq!(crate::Request::parse(b"M-SEARCH!~ / HTTP/1.1\r\n\r\n", &mut headers).unwrap().method, Method::Other("M-SEARCH!~"));
+    for malformed in [&b"LI(NK / HTTP/1.1\r\n\r\n"[..], b"LI\x00NK / HTTP/1.1\r\n\r\n", b"LINK\t/ HTTP/1.1\r\n\r\n", b" / HTTP/1.1\r\n\r\n", b"L\xc3\xa9 / HTTP/1.1\r\n\r\n"] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert_eq!(crate::Request::parse(malformed, &mut headers), Err(Error::Malformed), "{:?}", String::from_utf8_lossy(malformed));
+    }
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_request_webdav_methods() {
+    for (buf, token) in [
+        (&b"PROPFIND /files/ HTTP/1.1\r\nDepth: 1\r\n\r\n"[..], "PROPFIND"),
+        (b"MKCOL /files/new/ HTTP/1.1\r\n\r\n", "MKCOL"),
+    ] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let req = crate::Request::parse(buf, &mut headers).unwrap();
+        assert_eq!(req.method, Method::Other(token));
+        assert!(req.method.eq_bytes(token.as_bytes()) && req.method == token.as_bytes());
+        assert_ne!(req.method, Method::Other(&token.to_lowercase()));
+        assert_eq!(req.as_bytes(), buf);
+    }
+    // the fast path for the common methods never yields an extension method
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Request::parse(b"GET / HTTP/1.1\r\n\r\n", &mut headers).unwrap().method, Method::Get);
+    for malformed in [&b"PROP\"FIND / HTTP/1.1\r\n\r\n"[..], b"MK,COL / HTTP/1.1\r\n\r\n", b"MKCOL\x7f / HTTP/1.1\r\n\r\n", b"PROP/FIND / HTTP/1.1\r\n\r\n"] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert_eq!(crate::Request::parse(malformed, &mut headers), Err(Error::Malformed), "{:?}", String::from_utf8_lossy(malformed));
+    }
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_request_delete_round_trip() {
+    let buf = b"DELETE /resource/5 HTTP/1.1\r\nHost: api.example.com\r\n\r\n";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    crate::conformance::assert_roundtrip_parse_serialize(buf, |buf| crate::Request::parse(buf, &mut headers), |req| req.as_bytes());
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(buf, &mut headers).unwrap();
+    assert_eq!((req.method, req.path), (Method::Delete, "/resource/5"));
+    // every prefix of the longer method token is still incomplete rather than malformed
+    for len in 0..7 {
+        assert!(matches!(crate::Request::parse(&buf[..len], &mut headers), Err(Error::Incomplete { .. })), "prefix of {len} bytes");
+    }
+    // tokens that only look like DELETE are extension methods
+    assert_eq!(crate::Request::parse(b"DELETEX / HTTP/1.1\r\n\r\n", &mut headers).unwrap().method, Method::Other("DELETEX"));
+    assert_eq!(crate::Request::parse(b"DELET / HTTP/1.1\r\n\r\n", &mut headers).unwrap().method, Method::Other("DELET"));
+}
+
+#[test]
+fn test_request_truncated_prefixes() {
+    let full = b"GET /a HTTP/1.1\r\nHost: x\r\n\r\n";
+    crate::conformance::assert_never_panics_on_prefixes(b"POST /a?b=%20 HTTP/1.1\r\nHost: x\r\nContent-Length: 2\r\n\r\nhi", |buf| {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let _ = crate::Request::parse(buf, &mut headers);
+        let _ = crate::Request::parse_exact(buf, &mut headers);
+    });
+    for len in 0..20 {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert!(matches!(crate::Request::parse(&full[..len], &mut headers), Err(Error::Incomplete { needed: Some(_) })), "prefix of {len} bytes");
+    }
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert!(crate::Request::parse(full, &mut headers).is_ok());
+    for malformed in [&b"GE("[..], b"GET /a\r", b"GET /a HTTP/1.0", b"GET /a%2x", b"GET /a HTTP/1.1\r\nHo st"] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert_eq!(crate::Request::parse(malformed, &mut headers), Err(Error::Malformed));
+    }
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Request::parse(b"GET /a%2", &mut headers), Err(Error::Incomplete { needed: Some(4) }));
+}
+
+#[test]
+fn test_request_method_prefixes_never_panic() {
+    let requests: &[&[u8]] = &[
+        b"GET / HTTP/1.1\r\n\r\n", b"POST /a HTTP/1.1\r\nContent-Length: 0\r\n\r\n", b"PUT /a HTTP/1.1\r\n\r\n", b"DELETE /a HTTP/1.1\r\n\r\n",
+        b"PATCH /a HTTP/1.1\r\n\r\n", b"HEAD / HTTP/1.1\r\n\r\n", b"OPTIONS * HTTP/1.1\r\n\r\n", b"TRACE / HTTP/1.1\r\n\r\n",
+        b"CONNECT a.com:443 HTTP/1.1\r\n\r\n", b"PROPFIND / HTTP/1.1\r\n\r\n",
+    ];
+    for full in requests {
+        crate::conformance::assert_never_panics_on_prefixes(full, |buf| {
+            let mut headers = [crate::EMPTY_HEADER; 10];
+            let _ = crate::Request::parse(buf, &mut headers);
+            let _ = crate::Request::parse_partial(buf, &mut headers);
+            let mut indices = [crate::indices::EMPTY_HEADER_INDICES; 10];
+            let _ = crate::Request::parse_indices(buf, &mut indices);
+        });
+        // every strict prefix of a valid request is incomplete rather than malformed
+        for len in 0..full.len() {
+            let mut headers = [crate::EMPTY_HEADER; 10];
+            assert!(matches!(crate::Request::parse(&full[..len], &mut headers), Err(Error::Incomplete { .. })), "{:?}", String::from_utf8_lossy(&full[..len]));
+        }
+    }
+    // short garbage is malformed
+    for garbage in [&b"\x00"[..], b"G\xff", b"GE\r", b"GET\n", b"P\x7f", b"(", b" "] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert_eq!(crate::Request::parse(garbage, &mut headers), Err(Error::Malformed), "{:?}", String::from_utf8_lossy(garbage));
+    }
+}
+
+#[test]
+fn test_request_target_form() {
+    use crate::{Request, TargetForm};
+    for (buf, form) in [
+        (&b"GET /index.html?q=1 HTTP/1.1\r\n\r\n"[..], TargetForm::Origin),
+        (b"GET http://example.com/index.html HTTP/1.1\r\n\r\n", TargetForm::Absolute),
+        (b"GET example.com:443 HTTP/1.1\r\n\r\n", TargetForm::Authority),
+        (b"OPTIONS * HTTP/1.1\r\n\r\n", TargetForm::Asterisk),
+    ] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let req = Request::parse(buf, &mut headers).unwrap();
+        assert_eq!(req.target_form(), form);
+        assert_eq!(crate::uri::parse_request_target(req.path.as_bytes()).unwrap().form(), form);
+    }
+}
+
+#[cfg(all(feature = "fmt", feature = "serialize"))]
+#[test]
+fn test_method_as_str() {
+    for (method, token) in [
+        (Method::Get, "GET"), (Method::Post, "POST"), (Method::Put, "PUT"), (Method::Delete, "DELETE"), (Method::Patch, "PATCH"),
+        (Method::Head, "HEAD"), (Method::Options, "OPTIONS"), (Method::Trace, "TRACE"), (Method::Connect, "CONNECT"), (Method::Other("PURGE"), "PURGE"),
+    ] {
+        assert_eq!(method.as_str(), token);
+        assert_eq!(method.to_string(), token);
+        assert_eq!(crate::Request::new(method, "/", &[], b"").as_bytes(), format!("{token} / HTTP/1.1\r\n\r\n").as_bytes());
+    }
+    let method: &'static str = Method::Get.as_str();
+    assert_eq!(method, "GET");
+}
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_method_safe_and_idempotent() {
+    for (method, safe, idempotent) in [
+        (Method::Get, true, true), (Method::Head, true, true), (Method::Options, true, true), (Method::Trace, true, true),
+        (Method::Put, false, true), (Method::Delete, false, true),
+        (Method::Post, false, false), (Method::Patch, false, false), (Method::Connect, false, false), (Method::Other("PROPFIND"), false, false),
+    ] {
+        assert_eq!((method.is_safe(), method.is_idempotent()), (safe, idempotent), "{method}");
+    }
+}
+
+#[test]
+fn test_method_from_bytes_and_str() {
+    assert_eq!("GET".parse::<Method>(), Ok(Method::Get));
+    assert_eq!("CONNECT".parse::<Method>(), Ok(Method::Connect));
+    assert_eq!("get ".parse::<Method>(), Err(Error::Malformed));
+    assert_eq!("PROPFIND".parse::<Method>(), Err(Error::Malformed));
+    assert_eq!(Method::from_bytes(b"DELETE"), Ok(Method::Delete));
+    assert_eq!(Method::from_bytes(b"get"), Ok(Method::Other("get")));
+    for invalid in [&b"get "[..], b"", b"GET\r\n", b"P(ST"] {
+        assert_eq!(Method::from_bytes(invalid), Err(Error::Malformed));
+    }
+}
+
+#[cfg(all(feature = "fmt", feature = "serialize"))]
+#[test]
+fn test_method_from_routing_table() {
+    let routes = [("GET", "/"), ("POST", "/login"), ("PATCH", "/users/7"), ("OPTIONS", "*")];
+    for (method, path) in routes {
+        let method: Method = method.parse().unwrap();
+        let req = crate::Request::new(method.clone(), path, &[], b"");
+        assert!(req.as_bytes().starts_with(format!("{method} {path} ").as_bytes()));
+        assert_eq!(Method::from_bytes(method.as_str().as_bytes()), Ok(method));
+    }
+    // methods are case-sensitive, lowercase tokens are never the standard methods
+    for lowercase in ["get", "Post", "delete"] {
+        assert_eq!(lowercase.parse::<Method>(), Err(Error::Malformed));
+        assert_eq!(Method::from_bytes(lowercase.as_bytes()), Ok(Method::Other(lowercase)));
+    }
+    for unknown in ["BREW", "PROPFIND", ""] {
+        assert_eq!(unknown.parse::<Method>(), Err(Error::Malformed));
+    }
+}
+
+#[cfg(all(feature = "fmt", feature = "serialize"))]
+#[test]
+fn test_request_trace() {
+    let buf = b"TRACE /debug HTTP/1.1\r\nHost: example.com\r\nMax-Forwards: 0\r\n\r\n";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(buf, &mut headers).unwrap();
+    assert_eq!((req.method.clone(), req.path), (Method::Trace, "/debug"));
+    assert_eq!(req.as_bytes(), buf);
+    assert_eq!(Method::Trace.to_string(), "TRACE");
+}
+
+#[cfg(all(feature = "fmt", feature = "serialize"))]
+#[test]
+fn test_request_connect() {
+    use crate::TargetForm;
+    for (buf, authority) in [
+        (&b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n"[..], "example.com:443"),
+        (b"CONNECT [::1]:8443 HTTP/1.1\r\n\r\n", "[::1]:8443"),
+    ] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let req = crate::Request::parse(buf, &mut headers).unwrap();
+        assert_eq!((req.method.clone(), req.path), (Method::Connect, authority));
+        assert_eq!(req.target_form(), TargetForm::Authority);
+        assert_eq!(req.as_bytes(), buf);
+    }
+    assert_eq!(Method::Connect.to_string(), "CONNECT");
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Request::parse(b"CONNECT * HTTP/1.1\r\n\r\n", &mut headers), Err(Error::Malformed));
+    assert_eq!(crate::Request::parse(b"CONNECT example.com:99999 HTTP/1.1\r\n\r\n", &mut headers), Err(Error::Malformed));
+    assert!(matches!(crate::Request::parse(b"CONNEC", &mut headers), Err(Error::Incomplete { .. })));
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_request_options() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(b"OPTIONS /path HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.method, crate::Method::Options);
+    assert_eq!(req.path, "/path");
+
+    let buf = b"OPTIONS * HTTP/1.1\r\nHost: example.com\r\n\r\n";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(buf, &mut headers).unwrap();
+    assert_eq!(req.path, "*");
+    assert_eq!(req.target_form(), crate::TargetForm::Asterisk);
+    assert_eq!(req.as_bytes(), buf);
+
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Request::parse(b"GET * HTTP/1.1\r\n\r\n", &mut headers), Err(crate::Error::Malformed));
+}
+
+req! {
+    test_request_options_asterisk,
+    b"OPTIONS * HTTP/1.1\r\n\r\n",
+    |req| {
+        assert_eq!((req.method, req.path), (Method::Options, "*"));
+    }
+}
+
+req! {
+    test_request_options_origin_form,
+    b"OPTIONS /api HTTP/1.1\r\n\r\n",
+    |req| {
+        assert_eq!((req.method, req.path), (Method::Options, "/api"));
+    }
+}
+
+#[test]
+fn test_request_url_encoded_equals() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let mut queries = [crate::EMPTY_QUERY; 10];
+    let req = crate::Request::parse(b"GET /search?q=a%3Db&r=c=d HTTP/1.1\r\n\r\n", &mut headers).unwrap();
+    assert_eq!(req.path, "/search?q=a%3Db&r=c=d");
+    let url = req.url(&mut queries).unwrap();
+    let params = url.query_params.unwrap();
+    assert_eq!(params[0], crate::QueryParam::new("q", "a%3Db"));
+    assert_eq!(params[0].decoded_val(), "a=b");
+    assert_eq!(params[1], crate::QueryParam::new("r", "c=d"));
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_request_try_new_rejects_injection() {
+    use crate::{Header, Request};
+    assert_eq!(Request::try_new(Method::Get, "/a\r\nHost: evil.com\r\n\r\n", &[], b""), Err(Error::Malformed));
+    assert_eq!(Request::try_new(Method::Get, "/a HTTP/1.0", &[], b""), Err(Error::Malformed));
+    assert_eq!(Request::try_new(Method::Get, "/a", &[Header::new("X-Id", b"1\r\nX-Admin: 1")], b""), Err(Error::Malformed));
+    assert_eq!(Request::try_new(Method::Get, "/a", &[Header::new("X Id", b"1")], b""), Err(Error::Malformed));
+    assert_eq!(Request::try_new(Method::Other("GET /x HTTP/1.1\r\nHost: a\r\n\r\nGET"), "/", &[], b""), Err(Error::Malformed));
+    assert_eq!(Request::try_new(Method::Other(""), "/", &[], b""), Err(Error::Malformed));
+    assert_eq!(Request::new(Method::Other("X\r\nEvil: 1\r\n"), "/", &[], b"").try_as_bytes(), Err(crate::EncodeError::IllegalStartLine));
+    assert!(Request::try_new(Method::Other("PURGE"), "/", &[], b"").is_ok());
+    let headers = [Header::new("Accept", b"text/html,\t*/*")];
+    assert_eq!(Request::try_new(Method::Get, "/a?b=c", &headers, b""), Ok(Request::new(Method::Get, "/a?b=c", &headers, b"")));
+}
+
 
 
 
@@ -259,6 +1041,223 @@ res! {
     }
 }
 
+res! {
+    test_response_reason_missing_double_space,
+    b"HTTP/1.1 200  \r\n\r\n",
+    should_panic
+}
+
+res! {
+    test_response_header_entry_keeps_casing,
+    b"HTTP/1.1 200 OK\r\nETag: \"abc\"\r\n\r\n",
+    |res| {
+        assert_eq!(res.header_entry("etag").map(|header| header.name), Some("ETag"));
+    }
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_response_truncated() {
+    let full = b"HTTP/1.1 200 OK\r\nFoo: bar\r\n\r\n";
+    crate::conformance::assert_never_panics_on_prefixes(full, |buf| {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let _ = crate::Response::parse(buf, &mut headers);
+    });
+    for len in [0, 5, 9, 10] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert_eq!(crate::Response::parse(&full[..len], &mut headers), Err(Error::Incomplete { needed: Some(4) }));
+    }
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let res = crate::Response::parse(full, &mut headers).unwrap();
+    assert_eq!((res.status, res.reason, res.headers), (200, "OK", &[crate::Header::new("Foo", b"bar")][..]));
+    assert_eq!(res.as_bytes(), full);
+}
+
+res! {
+    test_response_status_text_parsed,
+    b"HTTP/1.1 404 Not Found\r\n\r\n",
+    |res| {
+        assert_eq!(res.reason, "Not Found");
+        assert_eq!(res.status_text(), "Not Found");
+        assert!(!res.has_custom_reason());
+    }
+}
+
+res! {
+    test_response_status_text_custom,
+    b"HTTP/1.1 404 Nothing Here\r\n\r\n",
+    |res| {
+        assert_eq!(res.status_text(), "Nothing Here");
+        assert!(res.has_custom_reason());
+    }
+}
+
+res! {
+    test_response_status_text_canonical,
+    b"HTTP/1.1 404\r\n\r\n",
+    |res| {
+        assert_eq!(res.status_text(), "Not Found");
+        assert!(!res.has_custom_reason());
+    }
+}
+
+res! {
+    test_response_status_text_unregistered,
+    b"HTTP/1.1 599 \r\n\r\n",
+    |res| {
+        assert_eq!(res.status_text(), "");
+    }
+}
+
+res! {
+    test_response_reason_double_space_between_words,
+    b"HTTP/1.1 404 Not  Found\r\n\r\n",
+    should_panic
+}
+
+#[test]
+fn test_response_require_reason() {
+    let options = crate::ParseOptions { require_reason: true, ..Default::default() };
+    for buf in [&b"HTTP/1.1 200\r\n\r\n"[..], b"HTTP/1.1 200 \r\n\r\n"] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert!(crate::Response::parse(buf, &mut headers).is_ok());
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert_eq!(crate::Response::parse_with_options(buf, &mut headers, &options), Err(Error::Malformed));
+    }
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Response::parse_with_options(b"HTTP/1.1 200 OK\r\n\r\n", &mut headers, &options).unwrap().reason, "OK");
+}
+
+#[test]
+fn test_response_is_tunnel_established() {
+    let buf = b"HTTP/1.1 200 Connection established\r\n\r\n\x16\x03\x01\x02\x00";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let res = crate::Response::parse(buf, &mut headers).unwrap();
+    assert!(res.is_tunnel_established());
+    // the tunnel starts right after the header section
+    assert_eq!(res.body, b"\x16\x03\x01\x02\x00");
+    for buf in [&b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n"[..], b"HTTP/1.1 502 Bad Gateway\r\n\r\n", b"HTTP/1.1 101 Switching Protocols\r\n\r\n"] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert!(!crate::Response::parse(buf, &mut headers).unwrap().is_tunnel_established());
+    }
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_response_redirect() {
+    let mut headers = [crate::EMPTY_HEADER; 2];
+    let res = crate::Response::redirect(302, "https://example.com/login?next=%2F", &mut headers).unwrap();
+    assert_eq!((res.status, res.reason, res.body), (302, "Found", &b""[..]));
+    assert_eq!(res.header_entry("location").unwrap().val, b"https://example.com/login?next=%2F");
+    assert_eq!(res.header_entry("Content-Length").unwrap().val, b"0");
+    assert_eq!(res.try_as_bytes(), Ok(res.as_bytes()));
+
+    for status in [301, 303, 307, 308] {
+        let mut headers = [crate::EMPTY_HEADER; 2];
+        assert_eq!(crate::Response::redirect(status, "/", &mut headers).unwrap().status, status);
+    }
+    for status in [200, 299, 300, 304, 305, 306, 400, 0] {
+        let mut headers = [crate::EMPTY_HEADER; 2];
+        assert_eq!(crate::Response::redirect(status, "/", &mut headers), Err(Error::Malformed));
+    }
+    let mut headers = [crate::EMPTY_HEADER; 2];
+    assert_eq!(crate::Response::redirect(301, "/a\r\nSet-Cookie: x=y", &mut headers), Err(Error::Malformed));
+}
+
+#[test]
+fn test_response_require_canonical_reason() {
+    let options = crate::ParseOptions { require_canonical_reason: true, ..Default::default() };
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert!(crate::Response::parse(b"HTTP/1.1 200 Not Found\r\n\r\n", &mut headers).is_ok());
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Response::parse_with_options(b"HTTP/1.1 200 Not Found\r\n\r\n", &mut headers, &options), Err(Error::Malformed));
+    // matching reasons in any case, empty reasons, and unregistered codes are accepted
+    for buf in [&b"HTTP/1.1 404 Not Found\r\n\r\n"[..], b"HTTP/1.1 404 NOT FOUND\r\n\r\n", b"HTTP/1.1 404\r\n\r\n", b"HTTP/1.1 599 Whatever\r\n\r\n"] {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert!(crate::Response::parse_with_options(buf, &mut headers, &options).is_ok(), "{:?}", String::from_utf8_lossy(buf));
+    }
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_response_try_as_bytes_rejects_smuggling() {
+    use crate::{EncodeError, Header, Response};
+    let headers = [Header::new("Server", b"htpp"), Header::new("X-Data", b"a\r\n\r\nGET /smuggled HTTP/1.1")];
+    let res = Response::new(200, "OK", &headers, b"");
+    assert_eq!(res.try_as_bytes(), Err(EncodeError::IllegalByte { header_index: 1 }));
+    assert_eq!(res.as_bytes(), b"HTTP/1.1 200 OK\r\nServer: htpp\r\nX-Data: a\r\n\r\nGET /smuggled HTTP/1.1\r\n\r\n");
+    assert_eq!(Response::new(200, "OK\r\nX: y", &[], b"").try_as_bytes(), Err(EncodeError::IllegalStartLine));
+    assert_eq!(Response::new(200, "OK", &headers[..1], b"").try_as_bytes(), Ok(b"HTTP/1.1 200 OK\r\nServer: htpp\r\n\r\n".to_vec()));
+    let req = crate::Request::new(Method::Get, "/a b", &[], b"");
+    assert_eq!(req.try_as_bytes(), Err(EncodeError::IllegalStartLine));
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_try_as_bytes_length_mismatch() {
+    use crate::{EncodeError, Header, Response};
+    let headers = [Header::new("Content-Length", b"100")];
+    assert_eq!(Response::new(200, "OK", &headers, b"hello").try_as_bytes(), Err(EncodeError::LengthMismatch { declared: 100, actual: 5 }));
+    let req = crate::Request::new(Method::Post, "/", &headers, b"hello");
+    assert_eq!(req.try_as_bytes(), Err(EncodeError::LengthMismatch { declared: 100, actual: 5 }));
+    let headers = [Header::new("Server", b"htpp"), Header::new("Content-Length", b"5x")];
+    assert_eq!(Response::new(200, "OK", &headers, b"hello").try_as_bytes(), Err(EncodeError::IllegalByte { header_index: 1 }));
+    let headers = [Header::new("Content-Length", b"5")];
+    assert_eq!(Response::new(200, "OK", &headers, b"hello").try_as_bytes(), Ok(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello".to_vec()));
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_try_as_bytes_auto_content_length() {
+    use crate::{EncodeOptions, Header, Response};
+    let options = EncodeOptions { auto_content_length: true, ..Default::default() };
+    let headers = [Header::new("content-length", b"100"), Header::new("Server", b"htpp"), Header::new("Content-Length", b"100")];
+    let res = Response::new(200, "OK", &headers, b"hello");
+    assert_eq!(res.try_as_bytes_with_options(&options), Ok(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nServer: htpp\r\n\r\nhello".to_vec()));
+    let headers = [Header::new("Server", b"htpp")];
+    let req = crate::Request::new(Method::Post, "/", &headers, b"hello");
+    assert_eq!(req.try_as_bytes_with_options(&options), Ok(b"POST / HTTP/1.1\r\nServer: htpp\r\nContent-Length: 5\r\n\r\nhello".to_vec()));
+    let headers = [Header::new("Transfer-Encoding", b"chunked")];
+    let res = Response::new(200, "OK", &headers, b"0\r\n\r\n");
+    assert_eq!(res.try_as_bytes_with_options(&options), Ok(res.as_bytes()));
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_try_as_bytes_bodiless() {
+    use crate::{EncodeError, EncodeOptions, Header, Response};
+    let options = EncodeOptions { bodiless: true, ..Default::default() };
+    let headers = [Header::new("Content-Length", b"100")];
+    let res = Response::new(200, "OK", &headers, b"");
+    assert_eq!(res.try_as_bytes(), Err(EncodeError::LengthMismatch { declared: 100, actual: 0 }));
+    assert_eq!(res.try_as_bytes_with_options(&options), Ok(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n".to_vec()));
+    let options = EncodeOptions { bodiless: true, auto_content_length: true };
+    assert_eq!(Response::new(304, "Not Modified", &headers, b"").try_as_bytes_with_options(&options).unwrap(), b"HTTP/1.1 304 Not Modified\r\nContent-Length: 100\r\n\r\n");
+    assert_eq!(
+        Response::new(200, "OK", &headers, b"hello").try_as_bytes_with_options(&EncodeOptions { bodiless: true, ..Default::default() }),
+        Err(EncodeError::LengthMismatch { declared: 100, actual: 5 })
+    );
+}
+
+#[test]
+fn test_response_from_error() {
+    use crate::Response;
+    assert_eq!(Response::from_error(&Error::Malformed), Response::new(400, "Bad Request", &[], b""));
+    assert_eq!(Response::from_error(&Error::Incomplete { needed: None }), Response::new(400, "Bad Request", &[], b""));
+    assert_eq!(Response::from_error(&Error::TooManyHeaders), Response::new(431, "Request Header Fields Too Large", &[], b""));
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_response_empty_reason_round_trip() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let bytes = crate::Response::new(204, "", &[crate::Header::new("Foo", b"bar")], b"").as_bytes();
+    assert_eq!(bytes, b"HTTP/1.1 204 \r\nFoo: bar\r\n\r\n");
+    crate::conformance::assert_roundtrip_parse_serialize(&bytes, |buf| crate::Response::parse(buf, &mut headers), |res| res.as_bytes());
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Response::parse(b"HTTP/1.1 204\r\nFoo: bar\r\n\r\n", &mut headers).unwrap().as_bytes(), bytes);
+}
+
 res! {
     test_response_reason_with_space_and_tab,
     b"HTTP/1.1 101 Switching Protocols\t\r\n\r\n",
@@ -295,6 +1294,23 @@ res! {
     should_panic
 }
 
+#[test]
+fn test_response_bare_lf_with_option() {
+    let buf = b"HTTP/1.1 403 Forbidden\nServer: foo.bar\nConnection: close\r\n\nbody";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let options = crate::ParseOptions { allow_bare_lf: true, ..Default::default() };
+    let res = crate::Response::parse_with_options(buf, &mut headers, &options).unwrap();
+    assert_eq!(res.status, 403);
+    assert_eq!(res.reason, "Forbidden");
+    assert_eq!(res.headers[0], crate::Header::new("Server", b"foo.bar"));
+    assert_eq!(res.headers[1], crate::Header::new("Connection", b"close"));
+    assert_eq!(res.body, b"body");
+    let res = crate::Response::parse_with_options(b"HTTP/1.1 200\n\n", &mut headers, &options).unwrap();
+    assert_eq!(res.status, 200);
+    assert_eq!(res.reason, "");
+    assert_eq!(crate::Response::parse(buf, &mut headers), Err(Error::Malformed));
+}
+
 res! {
     test_response_no_cr,
     b"HTTP/1.0 200\nContent-type: text/html\n\n",
@@ -304,11 +1320,56 @@ res! {
 
 
 
-// --------------------------
-//  TESTING URL PARSING
-// --------------------------
-
-
+res! {
+    test_response_text_utf8,
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\n\xe3\x81\xb2 ok",
+    |res| {
+        assert_eq!(res.text(), Some("\u{3072} ok".to_string()));
+    }
+}
+
+res! {
+    test_response_text_latin1,
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=\"ISO-8859-1\"\r\n\r\nna\xefve caf\xe9",
+    |res| {
+        assert_eq!(res.text(), Some("na\u{ef}ve caf\u{e9}".to_string()));
+    }
+}
+
+#[test]
+fn test_response_text_invalid() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let res = crate::Response::parse(b"HTTP/1.1 200 OK\r\n\r\ncaf\xe9", &mut headers).unwrap();
+    assert_eq!(res.text(), None);
+    let res = crate::Response::parse(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=x-unknown\r\n\r\nabc", &mut headers).unwrap();
+    assert_eq!(res.text(), None);
+    let res = crate::Response::parse(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=Shift_JIS\r\n\r\n\x82\xa0", &mut headers).unwrap();
+    assert_eq!(res.text(), Some("\u{3042}".to_string()));
+    let res = crate::Response::parse(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain;charset=us-ascii\r\n\r\nabc", &mut headers).unwrap();
+    assert_eq!(res.text(), Some("abc".to_string()));
+}
+
+#[test]
+fn test_response_keep_alive_params() {
+    use crate::{Header, KeepAlive, Response};
+    let params = |val: &[u8]| Response::new(200, "OK", &[Header::new("Keep-Alive", val)], b"").keep_alive_params();
+    assert_eq!(params(b"timeout=5, max=1000"), KeepAlive { timeout: Some(5), max: Some(1000) });
+    assert_eq!(params(b"MAX = 3 ,Timeout=10"), KeepAlive { timeout: Some(10), max: Some(3) });
+    assert_eq!(params(b"115"), KeepAlive { timeout: Some(115), max: None });
+    assert_eq!(params(b" 115 "), KeepAlive { timeout: Some(115), max: None });
+    assert_eq!(params(b"timeout=abc, max=2, foo=1"), KeepAlive { timeout: None, max: Some(2) });
+    assert_eq!(params(b"-1"), KeepAlive::default());
+    assert_eq!(Response::new(200, "OK", &[], b"").keep_alive_params(), KeepAlive::default());
+}
+
+
+
+
+// --------------------------
+//  TESTING URL PARSING
+// --------------------------
+
+
 
 
 macro_rules! url {
@@ -327,6 +1388,47 @@ macro_rules! url {
 }
 
 
+#[test]
+fn test_request_target_forms() {
+    use crate::uri::{parse_request_target, RequestTarget};
+    assert_eq!(parse_request_target(b"/index.html?q=1"), Ok(RequestTarget::Origin("/index.html?q=1")));
+    assert_eq!(parse_request_target(b"*"), Ok(RequestTarget::Asterisk));
+    assert_eq!(parse_request_target(b"example.com:443"), Ok(RequestTarget::Authority { host: "example.com", port: 443 }));
+    assert_eq!(parse_request_target(b"[::1]:8080"), Ok(RequestTarget::Authority { host: "[::1]", port: 8080 }));
+    assert_eq!(
+        parse_request_target(b"http://example.com/a?b=c"),
+        Ok(RequestTarget::Absolute { scheme: "http", host: "example.com", port: None, path: "/a?b=c" })
+    );
+    assert_eq!(
+        parse_request_target(b"https://example.com:8443"),
+        Ok(RequestTarget::Absolute { scheme: "https", host: "example.com", port: Some(8443), path: "" })
+    );
+    assert_eq!(parse_request_target(b""), Err(crate::UrlError::Path));
+    assert_eq!(parse_request_target(b"/a%2"), Err(crate::UrlError::Path));
+    assert_eq!(parse_request_target(b"/a b"), Err(crate::UrlError::Path));
+    assert_eq!(parse_request_target(b"example.com"), Err(crate::UrlError::Authority));
+    assert_eq!(parse_request_target(b"http://exa%6dple.com/"), Err(crate::UrlError::Authority));
+}
+
+#[test]
+fn test_request_target_agrees_with_request_parse() {
+    let corpus: &[&[u8]] = &[
+        b"/", b"/index.html", b"/thing?data=a", b"/a%20b", b"/a%2", b"/a%zz", b"*", b"**", b"example.com:443",
+        b"example.com", b"example.com:", b"example.com:99999", b"[::1]:443", b"[::1", b"http://example.com",
+        b"http://example.com/path?q", b"http://:80/", b"1http://example.com/", b"http://ex ample.com/", b"%41",
+        b"/\xe3\x81\xb2", b"/<script>",
+    ];
+    for target in corpus {
+        let mut buf = b"OPTIONS ".to_vec();
+        buf.extend_from_slice(target);
+        buf.extend_from_slice(b" HTTP/1.1\r\n\r\n");
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let req = crate::Request::parse(&buf, &mut headers);
+        let parsed = crate::uri::parse_request_target(target);
+        assert_eq!(req.is_ok(), parsed.is_ok(), "disagreement on {:?}", String::from_utf8_lossy(target));
+    }
+}
+
 url! {
     test_url,
     b"/path/path.html/user?query1=value&query2=value&query3=value",
@@ -338,3 +1440,1516 @@ url! {
 }
 
 
+
+
+url! {
+    test_url_matches,
+    b"/users/42/posts/7?sort=asc",
+    |url| {
+        assert_eq!(url.segments().collect::<Vec<_>>(), vec!["users", "42", "posts", "7"]);
+        assert_eq!(url.matches("/users/:id/posts/:pid"), Some(vec![("id", "42"), ("pid", "7")]));
+        assert_eq!(url.matches("/users/:id/posts/:pid/comments"), None);
+        assert_eq!(url.matches("/users/:id/comments/:pid"), None);
+        assert_eq!(url.matches("/users/:id"), None);
+    }
+}
+
+url! {
+    test_url_matches_static,
+    b"/",
+    |url| {
+        assert_eq!(url.matches("/"), Some(vec![]));
+        assert_eq!(url.matches("/:id"), None);
+        assert_eq!(url.matches("/index.html"), None);
+    }
+}
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_url_empty_path() {
+    use crate::{QueryParam, Url, EMPTY_QUERY};
+    let mut queries = [EMPTY_QUERY; 10];
+    let url = Url::parse(b"?a=1", &mut queries).unwrap();
+    assert_eq!(url.path, "");
+    assert_eq!(url.query_params.unwrap()[0], QueryParam::new("a", "1"));
+    assert_eq!(url.to_string(), "?a=1");
+    let mut queries = [EMPTY_QUERY; 10];
+    assert_eq!(Url::parse(b"#frag", &mut queries), Ok(Url::new("", None)));
+    let mut queries = [EMPTY_QUERY; 10];
+    assert_eq!(Url::parse(b"", &mut queries), Ok(Url::new("", None)));
+    assert_eq!(Url::new("", None).to_string(), "");
+    let mut queries = [EMPTY_QUERY; 10];
+    assert_eq!(Url::parse(b"/a?b=c#frag", &mut queries).unwrap().to_string(), "/a?b=c");
+    let mut queries = [EMPTY_QUERY; 10];
+    assert_eq!(Url::parse(b"a?b=c", &mut queries), Err(crate::UrlError::Path));
+}
+
+#[test]
+fn test_url_params_without_padding() {
+    use crate::{QueryParam, Url, EMPTY_QUERY};
+    let mut queries = [EMPTY_QUERY; 10];
+    let url = Url::parse(b"/search?q=rust&page=2", &mut queries).unwrap();
+    assert_eq!(url.query_params.unwrap().len(), 10);
+    assert_eq!(url.params(), &[QueryParam::new("q", "rust"), QueryParam::new("page", "2")]);
+    let mut queries = [EMPTY_QUERY; 10];
+    assert_eq!(Url::parse(b"/search", &mut queries).unwrap().params(), &[]);
+}
+
+#[test]
+fn test_query_param_parse_query() {
+    use crate::{QueryParam, UrlError, EMPTY_QUERY};
+    let mut queries = [EMPTY_QUERY; 10];
+    let params = QueryParam::parse_query(b"name=ferris&lang=rust%20lang", &mut queries).unwrap();
+    assert_eq!(params, [QueryParam::new("name", "ferris"), QueryParam::new("lang", "rust%20lang")]);
+    assert_eq!(params[1].decoded_val(), "rust lang");
+    // the query agrees with the query of a url
+    let mut url_queries = [EMPTY_QUERY; 10];
+    assert_eq!(crate::Url::parse(b"/?name=ferris&lang=rust%20lang", &mut url_queries).unwrap().params(), params);
+
+    let mut queries = [EMPTY_QUERY; 10];
+    assert_eq!(QueryParam::parse_query(b"", &mut queries), Ok(&[][..]));
+    for malformed in [&b"a"[..], b"a=&b=1", b"=1", b"a=\xff"] {
+        let mut queries = [EMPTY_QUERY; 10];
+        assert_eq!(QueryParam::parse_query(malformed, &mut queries), Err(UrlError::Query), "{:?}", String::from_utf8_lossy(malformed));
+    }
+    let mut queries = [EMPTY_QUERY; 1];
+    assert_eq!(QueryParam::parse_query(b"a=1&b=2", &mut queries), Err(UrlError::TooManyQueryParams));
+}
+
+#[test]
+fn test_url_to_owned_decoded() {
+    use crate::{OwnedUrl, Url, EMPTY_QUERY};
+    let owned = {
+        let buf = b"/caf%C3%A9/a%20b?q=a%20b&x%26y=1%262&bad=%ff".to_vec();
+        let mut queries = [EMPTY_QUERY; 10];
+        let url = Url::parse(&buf, &mut queries).unwrap();
+        url.to_owned_decoded()
+    };
+    assert_eq!(owned.path, "/café/a b");
+    assert_eq!(owned.params, vec![
+        ("q".to_string(), "a b".to_string()),
+        ("x&y".to_string(), "1&2".to_string()),
+        ("bad".to_string(), "\u{fffd}".to_string()),
+    ]);
+    let mut queries = [EMPTY_QUERY; 10];
+    assert_eq!(Url::parse(b"/", &mut queries).unwrap().to_owned_decoded(), OwnedUrl { path: "/".to_string(), params: vec![] });
+}
+
+#[test]
+fn test_url_classify_uri_reference() {
+    use crate::uri::{classify_uri_reference, UriRefKind};
+    for absolute in ["https://example.com/a", "HTTP://example.com", "mailto:a@example.com", "urn:isbn:0451450523", "git+ssh://host/repo"] {
+        assert_eq!(classify_uri_reference(absolute), UriRefKind::Absolute, "{absolute}");
+    }
+    for scheme_relative in ["//cdn.example.com/x", "//cdn.example.com", "///a"] {
+        assert_eq!(classify_uri_reference(scheme_relative), UriRefKind::SchemeRelative, "{scheme_relative}");
+    }
+    for absolute_path in ["/", "/login?next=/a", "/a:b"] {
+        assert_eq!(classify_uri_reference(absolute_path), UriRefKind::AbsolutePath, "{absolute_path}");
+    }
+    for relative in ["", "b/c", "../d", "./e:f", "?q=1", "#top", "1http://a", "a b:c", ":x"] {
+        assert_eq!(classify_uri_reference(relative), UriRefKind::Relative, "{relative}");
+    }
+}
+
+url! {
+    test_url_encoded_ampersand,
+    b"/search?a=1%262&b=%zz",
+    |url| {
+        let params = url.query_params.unwrap();
+        assert_eq!(params[0].name, "a");
+        assert_eq!(params[0].val, "1%262");
+        assert_eq!(params[0].decoded_val(), "1&2");
+        assert_eq!(params[1].name, "b");
+        assert_eq!(params[1].decoded_val(), "%zz");
+    }
+}
+
+
+
+
+// --------------------------
+//  TESTING TEXT RENDERING
+// --------------------------
+
+
+
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_lossy_escape_multibyte() {
+    let buf = b"GET / HTTP/1.1\r\nHost: foo.com\r\nUser-Agent: \xe3\x81\xb2\xe3/1.0\r\n\r\n";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(buf, &mut headers).unwrap();
+    assert_eq!(req.headers[1].to_string(), "User-Agent: \u{3072}\\xe3/1.0");
+}
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_lossy_escape_bounded() {
+    let body = vec![0xff; 1024 * 1024];
+    let rendered = crate::text::lossy_escape(&body, 1000).to_string();
+    assert!(rendered.len() <= 1000 + "...(1048576 more bytes)".len());
+    assert!(rendered.starts_with("\\xff\\xff"));
+    assert!(rendered.ends_with("...(1048326 more bytes)"));
+
+    let res = crate::Response::new(200, "OK", &[], &body);
+    assert!(res.to_string().len() <= "HTTP/1.1 200 OK\r\n\r\n".len() + crate::text::DEFAULT_LIMIT + 32);
+}
+
+#[test]
+fn test_lossy_escape_truncates_on_char_boundary() {
+    assert_eq!(crate::text::lossy_escape("a\u{3072}".as_bytes(), 2).to_string(), "a...(3 more bytes)");
+    assert_eq!(crate::text::lossy_escape(b"abc", 3).to_string(), "abc");
+}
+
+
+
+
+// --------------------------
+//  TESTING HEADER FIELDS
+// --------------------------
+
+
+
+
+#[test]
+fn test_fields_parse_rtsp_headers() {
+    let block = b"CSeq: 3\r\nTransport:RTP/AVP;unicast\r\nSession: 12345678\r\n\r\nv=0";
+    let mut headers = [crate::EMPTY_HEADER; 3];
+    let (read, count) = crate::fields::parse_headers(block, &mut headers).unwrap();
+    assert_eq!(&block[read..], b"v=0");
+    assert_eq!(count, 3);
+    assert_eq!(headers[0], crate::Header::new("CSeq", b"3"));
+    assert_eq!(headers[1], crate::Header::new("Transport", b"RTP/AVP;unicast"));
+    assert_eq!(headers[2], crate::Header::new("Session", b"12345678"));
+}
+
+#[test]
+fn test_fields_value_tokens_lower() {
+    let header = crate::Header::new("Connection", b"Keep-Alive,\tUPGRADE ,, close,");
+    assert_eq!(header.value_tokens_lower().collect::<Vec<_>>(), ["keep-alive", "upgrade", "close"]);
+    assert!(crate::Header::new("Connection", b"keep-alive").value_tokens_lower().eq(header.value_tokens_lower().take(1)));
+    assert_eq!(crate::Header::new("TE", b"").value_tokens_lower().count(), 0);
+    assert_eq!(crate::Header::new("Upgrade", b"WebSocket, H\xffC").value_tokens_lower().collect::<Vec<_>>(), ["websocket", "h\u{fffd}c"]);
+}
+
+#[test]
+fn test_fields_too_many_headers() {
+    let mut headers = [crate::EMPTY_HEADER; 1];
+    assert_eq!(crate::fields::parse_headers(b"CSeq: 3\r\nSession: 1\r\n\r\n", &mut headers), Err(Error::TooManyHeaders));
+}
+
+#[test]
+fn test_fields_truncated() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::fields::parse_headers(b"CSeq: 3\r\n", &mut headers), Err(Error::Incomplete { needed: None }));
+    assert_eq!(crate::fields::parse_headers(b"CSeq: 3\r\n\r", &mut headers), Err(Error::Incomplete { needed: None }));
+    assert_eq!(crate::fields::parse_header_name(b"CSeq"), Err(Error::Incomplete { needed: None }));
+    assert_eq!(crate::fields::parse_header_name(b"CSeq:"), Ok(("CSeq", 5)));
+    assert_eq!(crate::fields::parse_header_value(b"3\r"), Err(Error::Incomplete { needed: None }));
+    assert_eq!(crate::fields::parse_header_value(b"3\rx"), Err(Error::Malformed));
+}
+
+#[test]
+fn test_fields_invalid_name() {
+    assert_eq!(crate::fields::parse_header_name(b"C Seq: 3\r\n"), Err(Error::Malformed));
+    assert_eq!(crate::fields::parse_header_name(b": 3\r\n"), Err(Error::Malformed));
+}
+
+#[test]
+fn test_fields_obs_fold_rejected() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::fields::parse_headers(b"X-Long: a\r\n  b\r\n\r\n", &mut headers), Err(Error::ObsFold));
+    assert_eq!(crate::fields::parse_headers(b"X-Long: a\r\n\tb\r\n\r\n", &mut headers), Err(Error::ObsFold));
+    assert_eq!(crate::Request::parse(b"GET / HTTP/1.1\r\nX-Long: a\r\n b\r\n\r\n", &mut headers), Err(Error::ObsFold));
+    assert_eq!(crate::Response::parse(b"HTTP/1.1 200 OK\r\nX-Long: a\r\n b\r\n\r\n", &mut headers), Err(Error::ObsFold));
+    assert_eq!(crate::Response::from_error(&Error::ObsFold).status, 400);
+}
+
+#[test]
+fn test_fields_parse_single_line() {
+    use crate::Header;
+    assert_eq!(Header::parse_line(b"Session: 12345678\r\nCSeq: 3\r\n"), Ok((Header::new("Session", b"12345678"), 19)));
+    assert_eq!(Header::parse_line(b"Session:\r\n"), Ok((Header::new("Session", b""), 10)));
+    assert_eq!(Header::parse_line(b"Session: 12345678"), Err(Error::Incomplete { needed: None }));
+    assert_eq!(Header::parse_line(b"Session: 12345678\n"), Err(Error::Malformed));
+}
+
+#[test]
+fn test_fields_vectored_straddling_header() {
+    use std::borrow::Cow;
+    use crate::fields::{parse_headers_vectored, EMPTY_VECTORED_HEADER};
+    let bufs: [&[u8]; 4] = [b"CSeq: 3\r\nTrans", b"port: RTP/AVP\r", b"\nSession: 1\r\n", b"\r\nv=0"];
+    let mut headers = [EMPTY_VECTORED_HEADER; 10];
+    let (read, count) = parse_headers_vectored(&bufs, &mut headers).unwrap();
+    assert_eq!(read, 9 + 20 + 12 + 2);
+    assert_eq!(count, 3);
+    assert!(matches!(headers[0].name, Cow::Borrowed("CSeq")));
+    assert!(matches!(headers[1].name, Cow::Owned(_)));
+    assert_eq!(headers[1].as_header(), crate::Header::new("Transport", b"RTP/AVP"));
+    assert_eq!(headers[2].as_header(), crate::Header::new("Session", b"1"));
+    let mut headers = [EMPTY_VECTORED_HEADER; 1];
+    assert_eq!(parse_headers_vectored(&bufs, &mut headers), Err(Error::TooManyHeaders));
+    assert_eq!(parse_headers_vectored(&[b"CSeq: 3\r\n", b"\r"], &mut headers), Err(Error::Incomplete { needed: None }));
+}
+
+#[test]
+fn test_fields_semantic_eq() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let mut other_headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(b"GET / HTTP/1.1\r\nHost: a.com\r\nAccept: */*\r\nAccept: text/html\r\n\r\n", &mut headers).unwrap();
+    let reordered = crate::Request::parse(b"GET / HTTP/1.1\r\naccept: text/html\r\nHOST:a.com \r\nAccept: */*\r\n\r\n", &mut other_headers).unwrap();
+    assert!(req.semantic_eq(&reordered));
+    assert_ne!(req, reordered);
+    let mut other_headers = [crate::EMPTY_HEADER; 10];
+    let missing = crate::Request::parse(b"GET / HTTP/1.1\r\nHost: a.com\r\nAccept: */*\r\n\r\n", &mut other_headers).unwrap();
+    assert!(!req.semantic_eq(&missing));
+
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let mut other_headers = [crate::EMPTY_HEADER; 10];
+    let res = crate::Response::parse(b"HTTP/1.1 200 OK\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\nDate: Mon\r\n\r\nbody", &mut headers).unwrap();
+    let reason = crate::Response::parse(b"HTTP/1.1 200 Fine\r\nDate: Mon\r\nset-cookie: a=1\r\nSet-Cookie: b=2\r\n\r\nbody", &mut other_headers).unwrap();
+    assert!(res.semantic_eq(&reason));
+    let mut other_headers = [crate::EMPTY_HEADER; 10];
+    let cookies = crate::Response::parse(b"HTTP/1.1 200 OK\r\nSet-Cookie: b=2\r\nSet-Cookie: a=1\r\nDate: Mon\r\n\r\nbody", &mut other_headers).unwrap();
+    assert!(!res.semantic_eq(&cookies));
+    let mut other_headers = [crate::EMPTY_HEADER; 10];
+    let date = crate::Response::parse(b"HTTP/1.1 200 OK\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\nDate: Tue\r\n\r\nbody", &mut other_headers).unwrap();
+    assert!(!res.semantic_eq(&date));
+    assert!(crate::fields::headers_semantic_eq(res.headers, date.headers, &["date"]));
+}
+
+#[test]
+fn test_fields_count_headers() {
+    use crate::fields::count_headers;
+    let block = b"Host: foo.com\r\nAccept: */*\r\nCookie: a=b\r\nX-Empty:\r\n\r\nbody";
+    assert_eq!(count_headers(block), Ok(4));
+    let mut headers = vec![crate::EMPTY_HEADER; 4];
+    assert_eq!(crate::fields::parse_headers(block, &mut headers), Ok((block.len() - 4, 4)));
+    assert_eq!(count_headers(b"\r\n"), Ok(0));
+    assert_eq!(count_headers(b"Host: foo.com\r\nAccept: */*\r"), Err(Error::Incomplete { needed: None }));
+    assert_eq!(count_headers(b"Host: foo.com\r\nAcc"), Err(Error::Incomplete { needed: None }));
+    assert_eq!(count_headers(b"Host: foo.com\r\nBad Header: x\r\n\r\n"), Err(Error::Malformed));
+}
+
+#[test]
+fn test_fields_to_map() {
+    use crate::{Header, fields::{to_btreemap, to_map}};
+    let headers = [
+        Header::new("Set-Cookie", b"a=1; Path=/"),
+        Header::new("Accept", b"text/html"),
+        Header::new("SET-COOKIE", b"b=2, c=3"),
+        Header::new("accept", b"*/*"),
+        crate::EMPTY_HEADER,
+        Header::new("Ignored", b"after the padding"),
+    ];
+    let map = to_map(&headers);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["set-cookie"], vec![b"a=1; Path=/".to_vec(), b"b=2, c=3".to_vec()]);
+    assert_eq!(map["accept"], vec![b"text/html".to_vec(), b"*/*".to_vec()]);
+    let btree = to_btreemap(&headers);
+    assert_eq!(btree.keys().collect::<Vec<_>>(), ["accept", "set-cookie"]);
+    assert!(btree.iter().all(|(name, values)| map[name] == *values));
+    let many = vec![Header::new("X-Repeated", b"v"); 500];
+    assert_eq!(to_map(&many)["x-repeated"].len(), 500);
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_fields_headers_from_map() {
+    use crate::{Header, fields::{headers_from_map, headers_semantic_eq, to_btreemap, to_map}};
+    let headers = [
+        Header::new("Host", b"a.com"),
+        Header::new("Set-Cookie", b"a=1"),
+        Header::new("Content-Type", b"text/plain"),
+        Header::new("set-cookie", b"b=2"),
+    ];
+    let map = to_map(&headers);
+    let rebuilt = headers_from_map(&map, &["HOST", "Set-Cookie"]);
+    assert_eq!(rebuilt, [
+        Header::new("host", b"a.com"),
+        Header::new("set-cookie", b"a=1"),
+        Header::new("set-cookie", b"b=2"),
+        Header::new("content-type", b"text/plain"),
+    ]);
+    assert!(headers_semantic_eq(&headers, &rebuilt, &[]));
+    let btree = to_btreemap(&headers);
+    assert_eq!(headers_from_map(&btree, &[]), headers_from_map(&map, &[]));
+    let res = crate::Response::new(200, "OK", &rebuilt, b"");
+    let bytes = res.as_bytes();
+    let mut parsed = [crate::EMPTY_HEADER; 10];
+    assert_eq!(to_map(crate::Response::parse(&bytes, &mut parsed).unwrap().headers), map);
+}
+
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_fields_combined() {
+    use crate::fields::combined;
+    let mut split = [crate::EMPTY_HEADER; 10];
+    let split = crate::Request::parse(b"GET / HTTP/1.1\r\nConnection: keep-alive,\r\nHost: a.com\r\nconnection:  Upgrade \r\n\r\n", &mut split).unwrap();
+    let mut single = [crate::EMPTY_HEADER; 10];
+    let single = crate::Request::parse(b"GET / HTTP/1.1\r\nHost: a.com\r\nConnection: keep-alive,, Upgrade\r\n\r\n", &mut single).unwrap();
+    let val = combined(split.headers, "Connection").unwrap();
+    assert!(val.contains_token("upgrade"));
+    assert!(val.contains_token("Keep-Alive"));
+    assert!(!val.contains_token("keep-alive, upgrade"));
+    assert_eq!(val.clone().collect::<Vec<_>>(), [&b"keep-alive,"[..], &b" Upgrade "[..]]);
+    assert_eq!(val, combined(single.headers, "connection").unwrap());
+    assert!(val.eq_ignore_ascii_case("KEEP-ALIVE,, upgrade"));
+    assert_eq!(val.to_string(), "keep-alive,, Upgrade");
+    let mut rest = val.clone();
+    rest.next();
+    assert_eq!(rest.to_string(), "Upgrade");
+    assert_eq!(combined(split.headers, "Accept").unwrap().count(), 0);
+    let cookies = [crate::Header::new("Set-Cookie", b"a=1"), crate::Header::new("Set-Cookie", b"b=2")];
+    assert!(combined(&cookies, "set-cookie").is_none());
+}
+
+
+
+// --------------------------
+//  TESTING FETCH METADATA
+// --------------------------
+
+
+
+
+macro_rules! fetch_metadata {
+  ($name:ident, $buf:expr, |$arg:ident| $body:expr) => (
+    #[test]
+    fn $name() {
+      let buf = $buf;
+      let mut headers = [crate::EMPTY_HEADER; 10];
+      let req = crate::request::Request::parse(buf, &mut headers).unwrap();
+      closure(crate::fetch_metadata::parse(&req));
+      fn closure($arg: crate::fetch_metadata::FetchMetadata) {
+          $body
+      }
+      }
+  );
+}
+
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_fetch_metadata_tokens() {
+    use crate::fetch_metadata::{Site, Mode, Dest};
+    let sites = [("same-origin", Site::SameOrigin), ("same-site", Site::SameSite), ("cross-site", Site::CrossSite), ("none", Site::None)];
+    for (token, site) in sites {
+        let buf = format!("GET / HTTP/1.1\r\nSec-Fetch-Site: {token}\r\n\r\n");
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let req = crate::request::Request::parse(buf.as_bytes(), &mut headers).unwrap();
+        assert_eq!(crate::fetch_metadata::parse(&req).site, site);
+        assert_eq!(site.to_string(), token);
+    }
+    let modes = [("cors", Mode::Cors), ("navigate", Mode::Navigate), ("no-cors", Mode::NoCors), ("same-origin", Mode::SameOrigin), ("websocket", Mode::Websocket)];
+    for (token, mode) in modes {
+        let buf = format!("GET / HTTP/1.1\r\nSec-Fetch-Mode: {token}\r\n\r\n");
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let req = crate::request::Request::parse(buf.as_bytes(), &mut headers).unwrap();
+        assert_eq!(crate::fetch_metadata::parse(&req).mode, mode);
+        assert_eq!(mode.to_string(), token);
+    }
+    let dests = [
+        ("audio", Dest::Audio), ("audioworklet", Dest::AudioWorklet), ("document", Dest::Document), ("embed", Dest::Embed),
+        ("empty", Dest::Empty), ("font", Dest::Font), ("frame", Dest::Frame), ("iframe", Dest::Iframe), ("image", Dest::Image),
+        ("manifest", Dest::Manifest), ("object", Dest::Object), ("paintworklet", Dest::PaintWorklet), ("report", Dest::Report),
+        ("script", Dest::Script), ("serviceworker", Dest::ServiceWorker), ("sharedworker", Dest::SharedWorker), ("style", Dest::Style),
+        ("track", Dest::Track), ("video", Dest::Video), ("worker", Dest::Worker), ("xslt", Dest::Xslt),
+    ];
+    for (token, dest) in dests {
+        let buf = format!("GET / HTTP/1.1\r\nSec-Fetch-Dest: {token}\r\n\r\n");
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let req = crate::request::Request::parse(buf.as_bytes(), &mut headers).unwrap();
+        assert_eq!(crate::fetch_metadata::parse(&req).dest, dest);
+        assert_eq!(dest.to_string(), token);
+    }
+}
+
+fetch_metadata! {
+    test_fetch_metadata_missing_and_unknown,
+    b"GET / HTTP/1.1\r\nSec-Fetch-Mode: teleport\r\n\r\n",
+    |metadata| {
+        use crate::fetch_metadata::{Site, Mode, Dest, Policy};
+        assert_eq!(metadata.site, Site::Unknown);
+        assert_eq!(metadata.mode, Mode::Other("teleport"));
+        assert_eq!(metadata.dest, Dest::Unknown);
+        assert!(!metadata.user);
+        assert!(!metadata.early_data);
+        assert!(metadata.is_allowed(&Policy::default()));
+    }
+}
+
+fetch_metadata! {
+    test_fetch_metadata_cross_site_navigation,
+    b"GET /article HTTP/1.1\r\nSec-Fetch-Site: cross-site\r\nSec-Fetch-Mode: navigate\r\nSec-Fetch-Dest: document\r\nSec-Fetch-User: ?1\r\n\r\n",
+    |metadata| {
+        use crate::fetch_metadata::Policy;
+        assert!(metadata.user);
+        assert!(metadata.is_allowed(&Policy::default()));
+        assert!(!metadata.is_allowed(&Policy { allow_navigations: false, ..Policy::default() }));
+    }
+}
+
+fetch_metadata! {
+    test_fetch_metadata_cross_site_embed,
+    b"GET /widget HTTP/1.1\r\nSec-Fetch-Site: cross-site\r\nSec-Fetch-Mode: navigate\r\nSec-Fetch-Dest: embed\r\n\r\n",
+    |metadata| {
+        assert!(!metadata.is_allowed(&crate::fetch_metadata::Policy::default()));
+    }
+}
+
+fetch_metadata! {
+    test_fetch_metadata_early_data_replay,
+    b"POST /transfer HTTP/1.1\r\nSec-Fetch-Site: same-origin\r\nEarly-Data: 1\r\n\r\n",
+    |metadata| {
+        use crate::fetch_metadata::Policy;
+        assert!(metadata.early_data);
+        assert!(!metadata.is_allowed(&Policy::default()));
+        assert!(metadata.is_allowed(&Policy { reject_early_data: false, ..Policy::default() }));
+    }
+}
+
+fetch_metadata! {
+    test_fetch_metadata_early_data_head,
+    b"HEAD /transfer HTTP/1.1\r\nSec-Fetch-Site: cross-site\r\nSec-Fetch-Mode: navigate\r\nSec-Fetch-Dest: document\r\nEarly-Data: 1\r\n\r\n",
+    |metadata| {
+        use crate::fetch_metadata::Policy;
+        assert!(metadata.early_data);
+        assert!(metadata.is_allowed(&Policy::default()));
+        assert!(!metadata.is_allowed(&Policy { allow_navigations: false, ..Policy::default() }));
+    }
+}
+
+
+
+// --------------------------
+//  TESTING SIGNATURE BASES
+// --------------------------
+
+macro_rules! sig {
+  ($name:ident, $buf:expr, $components:expr, $params:expr, $expected:expr) => (
+    #[test]
+    fn $name() {
+      let buf = $buf;
+      let mut headers = [crate::EMPTY_HEADER; 10];
+      let req = crate::request::Request::parse(buf, &mut headers).unwrap();
+      let mut base = Vec::new();
+      crate::sig::signature_base(&req, $components, $params, &mut base).unwrap();
+      assert_eq!(std::str::from_utf8(&base).unwrap(), $expected);
+      }
+  );
+}
+
+// the test request of RFC 9421 appendix B.2
+const SIG_TEST_REQUEST: &[u8] = b"POST /foo?param=Value&Pet=dog HTTP/1.1\r\nHost: example.com\r\nDate: Tue, 20 Apr 2021 02:07:55 GMT\r\nContent-Type: application/json\r\nContent-Digest: sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:\r\nContent-Length: 18\r\n\r\n{\"hello\": \"world\"}";
+
+sig! {
+    test_sig_minimal_coverage,
+    SIG_TEST_REQUEST,
+    &[],
+    &crate::sig::SigParams { created: Some(1618884473), keyid: Some("test-key-rsa-pss"), nonce: Some("b3k2pp5k7z-50gnwp.yemd"), ..Default::default() },
+    "\"@signature-params\": ();created=1618884473;keyid=\"test-key-rsa-pss\";nonce=\"b3k2pp5k7z-50gnwp.yemd\""
+}
+
+sig! {
+    test_sig_full_coverage,
+    SIG_TEST_REQUEST,
+    {
+        use crate::sig::Component::*;
+        &[Field("date"), Method, Path, Query, Authority, Field("content-type"), Field("content-digest"), Field("content-length")]
+    },
+    &crate::sig::SigParams { created: Some(1618884473), keyid: Some("test-key-rsa-pss"), ..Default::default() },
+    "\"date\": Tue, 20 Apr 2021 02:07:55 GMT\n\
+\"@method\": POST\n\
+\"@path\": /foo\n\
+\"@query\": ?param=Value&Pet=dog\n\
+\"@authority\": example.com\n\
+\"content-type\": application/json\n\
+\"content-digest\": sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:\n\
+\"content-length\": 18\n\
+\"@signature-params\": (\"date\" \"@method\" \"@path\" \"@query\" \"@authority\" \"content-type\" \"content-digest\" \"content-length\");created=1618884473;keyid=\"test-key-rsa-pss\""
+}
+
+// the field values of RFC 9421 section 2.1, repeated fields are joined and values are trimmed
+sig! {
+    test_sig_repeated_and_empty_fields,
+    b"GET / HTTP/1.1\r\nHost: www.example.com\r\nX-OWS-Header:   Leading and trailing whitespace.   \r\nCache-Control: max-age=60\r\nCache-Control:    must-revalidate\r\nX-Empty-Header:\r\n\r\n",
+    {
+        use crate::sig::Component::*;
+        &[Field("Host"), Field("X-OWS-Header"), Field("cache-control"), Field("x-empty-header")]
+    },
+    &crate::sig::SigParams::default(),
+    "\"host\": www.example.com\n\
+\"x-ows-header\": Leading and trailing whitespace.\n\
+\"cache-control\": max-age=60, must-revalidate\n\
+\"x-empty-header\": \n\
+\"@signature-params\": (\"host\" \"x-ows-header\" \"cache-control\" \"x-empty-header\")"
+}
+
+sig! {
+    test_sig_absolute_target,
+    b"GET HTTP://WWW.Example.com:80/path HTTP/1.1\r\n\r\n",
+    {
+        use crate::sig::Component::*;
+        &[Scheme, Authority, Path, Query, TargetUri]
+    },
+    &crate::sig::SigParams::default(),
+    "\"@scheme\": http\n\
+\"@authority\": www.example.com\n\
+\"@path\": /path\n\
+\"@query\": ?\n\
+\"@target-uri\": HTTP://WWW.Example.com:80/path\n\
+\"@signature-params\": (\"@scheme\" \"@authority\" \"@path\" \"@query\" \"@target-uri\")"
+}
+
+// the derived components of RFC 9421 sections 2.2.2 and 2.2.4, the target URI is rebuilt from the scheme the request was received over
+sig! {
+    test_sig_origin_target_uri,
+    b"POST /path?param=value HTTP/1.1\r\nHost: www.example.com\r\n\r\n",
+    {
+        use crate::sig::Component::*;
+        &[TargetUri, Scheme]
+    },
+    &crate::sig::SigParams { scheme: Some("https"), ..Default::default() },
+    "\"@target-uri\": https://www.example.com/path?param=value\n\
+\"@scheme\": https\n\
+\"@signature-params\": (\"@target-uri\" \"@scheme\")"
+}
+
+sig! {
+    test_sig_received_scheme_default_port,
+    b"OPTIONS * HTTP/1.1\r\nHost: WWW.Example.com:443\r\n\r\n",
+    {
+        use crate::sig::Component::*;
+        &[Authority, TargetUri]
+    },
+    &crate::sig::SigParams { scheme: Some("HTTPS"), ..Default::default() },
+    "\"@authority\": www.example.com\n\
+\"@target-uri\": https://www.example.com\n\
+\"@signature-params\": (\"@authority\" \"@target-uri\")"
+}
+
+#[test]
+fn test_sig_errors() {
+  use crate::sig::{signature_base, Component, SigError, SigParams};
+  let mut headers = [crate::EMPTY_HEADER; 10];
+  let req = crate::request::Request::parse(b"GET /foo HTTP/1.1\r\nHost: example.com\r\n\r\n", &mut headers).unwrap();
+  let params = SigParams::default();
+  let mut base = Vec::new();
+  assert_eq!(signature_base(&req, &[Component::Field("date")], &params, &mut base), Err(SigError::MissingComponent));
+  assert_eq!(signature_base(&req, &[Component::Scheme], &params, &mut base), Err(SigError::MissingComponent));
+  assert_eq!(signature_base(&req, &[Component::TargetUri], &params, &mut base), Err(SigError::MissingComponent));
+  assert_eq!(signature_base(&req, &[Component::TargetUri], &SigParams { scheme: Some("ht tp"), ..params }, &mut base), Err(SigError::InvalidParam));
+  assert_eq!(signature_base(&req, &[Component::Field("host"), Component::Field("Host")], &params, &mut base), Err(SigError::DuplicateComponent));
+  assert_eq!(signature_base(&req, &[Component::Field("bad\"name")], &params, &mut base), Err(SigError::InvalidParam));
+  assert_eq!(signature_base(&req, &[], &SigParams { keyid: Some("bad\nkey"), ..params }, &mut base), Err(SigError::InvalidParam));
+}
+
+
+
+// --------------------------
+//  TESTING HTTP VERSIONS
+// --------------------------
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_http_version_parse() {
+    for version in [HttpVer::OneZero, HttpVer::One, HttpVer::Two] {
+        assert_eq!(HttpVer::parse(version.as_bytes()), Ok((version, 8)));
+        assert_eq!(version.as_str(), version.to_string());
+        let mut line = version.as_bytes().to_vec();
+        line.extend(b"\r\n");
+        assert_eq!(HttpVer::parse(&line), Ok((version, 8)));
+        for end in 0..8 {
+            assert_eq!(HttpVer::parse(&version.as_bytes()[..end]), Err(Error::Incomplete { needed: None }));
+        }
+    }
+    for unknown in [&b"HTTP/0.9"[..], b"HTTP/1.2", b"HTTP/2.1", b"HTTP/3.0", b"http/1.1", b"HTTP/11"] {
+        assert_eq!(HttpVer::parse(unknown), Err(Error::Malformed));
+    }
+}
+
+#[test]
+fn test_http_version_delimiters() {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    for (truncated, needed) in [(&b"GET / HTTP/1.1"[..], 4), (b"GET / HTTP/1.1\r", 3), (b"GET / HTTP/2.", 4)] {
+        assert_eq!(crate::Request::parse(truncated, &mut headers), Err(Error::Incomplete { needed: Some(needed) }));
+    }
+    assert_eq!(crate::Request::parse(b"GET / HTTP/1.1 \r\n\r\n", &mut headers), Err(Error::Malformed));
+    assert_eq!(crate::Request::parse(b"GET / HTTP/1.1\rX\r\n", &mut headers), Err(Error::Malformed));
+    assert_eq!(crate::Response::parse(b"HTTP/1.1", &mut headers), Err(Error::Incomplete { needed: Some(4) }));
+    assert_eq!(crate::Response::parse(b"HTTP/1.1\r\n\r\n", &mut headers), Err(Error::Malformed));
+    assert!(crate::Response::parse(b"HTTP/2.0 200 OK\r\n\r\n", &mut headers).is_ok());
+    assert_eq!(crate::Request::parse(b"GET / HTTP/1.0\r\n\r\n", &mut headers), Err(Error::Malformed));
+    assert_eq!(crate::Response::parse(b"HTTP/1.0 200 OK\r\n\r\n", &mut headers), Err(Error::Malformed));
+}
+
+
+
+
+// --------------------------
+//  TESTING ERROR CODES
+// --------------------------
+
+#[test]
+fn test_error_codes_are_stable() {
+  use crate::{ErrorCategory, UrlError};
+  assert_eq!((Error::Malformed.code(), Error::Malformed.category()), (100, ErrorCategory::Structural));
+  assert_eq!((Error::Incomplete { needed: None }.code(), Error::Incomplete { needed: None }.category()), (101, ErrorCategory::Structural));
+  assert_eq!((Error::ObsFold.code(), Error::ObsFold.category()), (200, ErrorCategory::Header));
+  assert_eq!((Error::TooManyHeaders.code(), Error::TooManyHeaders.category()), (400, ErrorCategory::Limit));
+  assert_eq!((Error::BodyTooLarge { declared: None, limit: 0 }.code(), Error::BodyTooLarge { declared: None, limit: 0 }.category()), (403, ErrorCategory::Limit));
+  assert_eq!((UrlError::Path.code(), UrlError::Path.category()), (300, ErrorCategory::Target));
+  assert_eq!((UrlError::Query.code(), UrlError::Query.category()), (301, ErrorCategory::Target));
+  assert_eq!((UrlError::Authority.code(), UrlError::Authority.category()), (302, ErrorCategory::Target));
+  assert_eq!((UrlError::TooManyQueryParams.code(), UrlError::TooManyQueryParams.category()), (401, ErrorCategory::Limit));
+}
+
+#[test]
+fn test_max_body_size() {
+  use crate::{ParseOptions, Request, framing::decode_chunked_with_options};
+  let options = ParseOptions { max_body_size: Some(5), ..ParseOptions::default() };
+  let mut headers = [crate::EMPTY_HEADER; 10];
+  // rejected from the head alone, before any of the body arrives
+  let head = b"POST / HTTP/1.1\r\nContent-Length: 6\r\n\r\n";
+  assert_eq!(Request::parse_exact_with_options(head, &mut headers, &options), Err(Error::BodyTooLarge { declared: Some(6), limit: 5 }));
+  assert!(Request::parse_with_options(head, &mut headers, &options).is_ok());
+  let exact = b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello";
+  assert_eq!(Request::parse_exact_with_options(exact, &mut headers, &options).unwrap().body, b"hello");
+  // the second chunk crosses the limit and is rejected before its data is read
+  let chunked = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n3\r\nd";
+  assert_eq!(Request::parse_exact_with_options(chunked, &mut headers, &options), Err(Error::BodyTooLarge { declared: None, limit: 5 }));
+  let chunked = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n";
+  assert!(Request::parse_exact_with_options(chunked, &mut headers, &options).is_ok());
+  let mut out = Vec::new();
+  assert_eq!(decode_chunked_with_options(b"3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n", &mut out, &options), Ok(20));
+  assert_eq!(out, b"abcde");
+  out.clear();
+  assert_eq!(decode_chunked_with_options(b"3\r\nabc\r\n3\r\ndef\r\n0\r\n\r\n", &mut out, &options), Err(Error::BodyTooLarge { declared: None, limit: 5 }));
+  assert_eq!(out, b"abc");
+}
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_parse_stats() {
+  use crate::{ParseOptions, Request, Response, stats::ParseStats};
+  let buf = b"GET / HTTP/1.1\r\nHost: example.com\r\nAccept: */*\r\n\r\nbody";
+  let (mut headers, mut expected) = ([crate::EMPTY_HEADER; 10], [crate::EMPTY_HEADER; 10]);
+  let mut stats = ParseStats::default();
+  let req = Request::parse_with_stats(buf, &mut headers, &ParseOptions::default(), &mut stats).unwrap();
+  assert_eq!(req, Request::parse_with_options(buf, &mut expected, &ParseOptions::default()).unwrap());
+  assert_eq!(stats, ParseStats { bytes_scanned: buf.len() - 4, headers: 2, largest_value: 11, relaxed: false });
+  assert_eq!(stats.to_string(), "50 bytes scanned, 2 headers, largest value 11 bytes");
+  let lenient = ParseOptions { allow_bare_lf: true, allow_leading_crlf: true, ..ParseOptions::default() };
+  let mut stats = ParseStats::default();
+  Request::parse_with_stats(b"\r\nGET / HTTP/1.1\r\nHost: a\r\n\r\n", &mut headers, &lenient, &mut stats).unwrap();
+  assert!(stats.relaxed);
+  let mut stats = ParseStats::default();
+  Request::parse_with_stats(b"GET / HTTP/1.1\nHost: a\r\n\r\n", &mut headers, &lenient, &mut stats).unwrap();
+  assert!(stats.relaxed);
+  let mut stats = ParseStats::default();
+  let res = Response::parse_with_stats(b"HTTP/1.1 200 OK\nX: 1\r\n\r\n", &mut headers, &lenient, &mut stats).unwrap();
+  assert_eq!(res, Response::parse_with_options(b"HTTP/1.1 200 OK\nX: 1\r\n\r\n", &mut expected, &lenient).unwrap());
+  assert_eq!(stats, ParseStats { bytes_scanned: 24, headers: 1, largest_value: 1, relaxed: true });
+  let mut stats = ParseStats::default();
+  Response::parse_with_stats(b"HTTP/1.1 200 OK\r\nX: 1\n\n", &mut headers, &lenient, &mut stats).unwrap();
+  assert!(stats.relaxed);
+  let mut stats = ParseStats::default();
+  Response::parse_with_stats(b"HTTP/1.1 200 OK\r\nX: 1\r\n\r\n", &mut headers, &lenient, &mut stats).unwrap();
+  assert!(!stats.relaxed);
+}
+
+
+
+// --------------------------
+//  TESTING BODY FRAMING
+// --------------------------
+
+#[test]
+fn test_framing_chunked_last() {
+  use crate::{Header, framing::{is_chunked, transfer_codings, try_is_chunked}};
+  let headers = [Header::new("Transfer-Encoding", b"gzip, chunked")];
+  assert_eq!(transfer_codings(&headers).collect::<Vec<_>>(), vec![&b"gzip"[..], b"chunked"]);
+  assert!(is_chunked(&headers));
+  assert_eq!(try_is_chunked(&headers), Ok(true));
+  assert!(is_chunked(&[Header::new("transfer-encoding", b"CHUNKED")]));
+  assert!(!is_chunked(&[Header::new("Content-Length", b"5")]));
+}
+
+#[test]
+fn test_framing_chunked_not_last() {
+  use crate::{Header, framing::{is_chunked, try_is_chunked}};
+  let headers = [Header::new("Transfer-Encoding", b"chunked, gzip")];
+  assert!(!is_chunked(&headers));
+  assert_eq!(try_is_chunked(&headers), Err(Error::Malformed));
+  assert_eq!(try_is_chunked(&[Header::new("Transfer-Encoding", b"gzip")]), Ok(false));
+}
+
+#[test]
+fn test_framing_request_codings() {
+  use crate::{Header, framing::validate_request_codings};
+  assert_eq!(validate_request_codings(&[Header::new("Transfer-Encoding", b"gzip, chunked")]), Ok(()));
+  assert_eq!(validate_request_codings(&[Header::new("Host", b"a.com")]), Ok(()));
+  assert_eq!(validate_request_codings(&[Header::new("Transfer-Encoding", b"chunked, gzip")]), Err(Error::Malformed));
+  assert_eq!(validate_request_codings(&[Header::new("Transfer-Encoding", b"chunked, chunked")]), Err(Error::Malformed));
+  assert_eq!(validate_request_codings(&[Header::new("Transfer-Encoding", b"gzip")]), Err(Error::Malformed));
+  let mut headers = [crate::EMPTY_HEADER; 10];
+  let buf = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\n\r\n0\r\n\r\n";
+  assert_eq!(crate::Request::parse_exact(buf, &mut headers), Err(Error::Malformed));
+}
+
+#[test]
+fn test_framing_split_lines() {
+  use crate::{Header, framing::{is_chunked, transfer_codings, try_is_chunked}};
+  let headers = [Header::new("Transfer-Encoding", b"gzip,"), Header::new("Host", b"a"), Header::new("Transfer-Encoding", b" Chunked")];
+  assert_eq!(transfer_codings(&headers).count(), 2);
+  assert!(is_chunked(&headers));
+  let headers = [Header::new("Transfer-Encoding", b"chunked"), Header::new("Transfer-Encoding", b"gzip")];
+  assert_eq!(try_is_chunked(&headers), Err(Error::Malformed));
+}
+
+
+
+// --------------------------
+//  TESTING CONTENT DISPOSITION
+// --------------------------
+
+res! {
+    test_content_disposition_quoted_filename,
+    b"HTTP/1.1 200 OK\r\nContent-Disposition: Attachment; filename=\"annual \\\"2024\\\" report.pdf\"\r\n\r\n",
+    |res| {
+        let disposition = res.content_disposition().unwrap();
+        assert!(disposition.is_attachment());
+        assert_eq!(disposition.filename().unwrap(), "annual \"2024\" report.pdf");
+        assert_eq!(disposition.param("name"), None);
+    }
+}
+
+res! {
+    test_content_disposition_extended_filename,
+    b"HTTP/1.1 200 OK\r\nContent-Disposition: attachment; filename=\"EURO rates\"; filename*=utf-8''%e2%82%ac%20rates\r\n\r\n",
+    |res| {
+        let disposition = res.content_disposition().unwrap();
+        assert_eq!(disposition.filename().unwrap(), "\u{20ac} rates");
+        assert_eq!(disposition.param("filename").unwrap(), "EURO rates");
+    }
+}
+
+#[test]
+fn test_content_disposition_params() {
+  use crate::content_disposition::ContentDisposition;
+  let disposition = ContentDisposition::parse(b"form-data; name=field1; filename*=iso-8859-1'en'%A3%20rates").unwrap();
+  assert!(!disposition.is_attachment() && !disposition.is_inline());
+  assert_eq!(disposition.param("NAME").unwrap(), "field1");
+  assert_eq!(disposition.filename().unwrap(), "\u{a3} rates");
+  assert_eq!(ContentDisposition::parse(b"inline").unwrap().filename(), None);
+  assert_eq!(ContentDisposition::parse(b"attachment; filename"), Err(Error::Malformed));
+  assert_eq!(ContentDisposition::parse(b"attachment; filename=\"x.txt"), Err(Error::Malformed));
+  assert_eq!(ContentDisposition::parse(b"attachment filename=x.txt"), Err(Error::Malformed));
+  assert_eq!(ContentDisposition::parse(b""), Err(Error::Malformed));
+}
+
+
+
+// --------------------------
+//  TESTING INCREMENTAL PARSING
+// --------------------------
+
+#[test]
+fn test_request_parser_one_byte_at_a_time() {
+  use crate::{ParseProgress, RequestParser};
+  let buf = b"POST /upload?x=1 HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\n\r\nhello";
+  let head_len = buf.len() - 5;
+  let mut parser = RequestParser::new();
+  for (counter, byte) in buf[..head_len].iter().enumerate() {
+    let progress = parser.feed(&[*byte]).unwrap();
+    let expected = match counter + 1 {
+      read if read < 27 => ParseProgress::NeedMore,
+      read if read < 46 => ParseProgress::Headers(0),
+      read if read < 65 => ParseProgress::Headers(1),
+      read if read < head_len => ParseProgress::Headers(2),
+      _ => ParseProgress::Complete(head_len),
+    };
+    assert_eq!(progress, expected, "after {} bytes", counter + 1);
+  }
+  for byte in &buf[head_len..] {
+    assert_eq!(parser.feed(&[*byte]), Ok(ParseProgress::Complete(head_len)));
+  }
+  let mut headers = [crate::EMPTY_HEADER; 10];
+  let req = parser.request(&mut headers).unwrap();
+  let mut expected_headers = [crate::EMPTY_HEADER; 10];
+  assert_eq!(req, crate::Request::parse(buf, &mut expected_headers).unwrap());
+  assert_eq!(req.body, b"hello");
+}
+
+#[test]
+fn test_request_parser_errors() {
+  use crate::RequestParser;
+  let mut parser = RequestParser::new();
+  assert_eq!(parser.request(&mut [crate::EMPTY_HEADER; 1]).map(|_| ()), Err(Error::Incomplete { needed: Some(4) }));
+  assert!(parser.feed(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n\r\n").is_ok());
+  assert_eq!(parser.request(&mut [crate::EMPTY_HEADER; 1]).map(|_| ()), Err(Error::TooManyHeaders));
+  let mut parser = RequestParser::new();
+  assert_eq!(parser.feed(b"GET / HTTP/1.1\r\nBad Name: 1\r\n"), Err(Error::Malformed));
+  let mut parser = RequestParser::new();
+  assert_eq!(parser.feed(b"GET / HTTP/1.1\n"), Err(Error::Malformed));
+}
+
+#[test]
+fn test_request_parser_limits() {
+  use crate::{ParseProgress, RequestParser};
+  let head = b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n\r\n";
+  let mut parser = RequestParser::with_limits(head.len(), 2);
+  assert_eq!(parser.feed(head), Ok(ParseProgress::Complete(head.len())));
+  assert_eq!(parser.feed(b"body past the limit"), Ok(ParseProgress::Complete(head.len())));
+  let mut parser = RequestParser::with_limits(head.len() - 1, 2);
+  assert_eq!(parser.feed(head), Err(Error::Malformed));
+  let mut parser = RequestParser::with_limits(64, 10);
+  assert_eq!(parser.feed(b"GET / HTTP/1.1\r\nX: "), Ok(ParseProgress::Headers(0)));
+  assert_eq!(parser.feed(&[b'a'; 64]), Err(Error::Malformed));
+  let mut parser = RequestParser::with_limits(1024, 1);
+  assert_eq!(parser.feed(head), Err(Error::TooManyHeaders));
+  let mut parser = RequestParser::new();
+  assert!(parser.feed(b"GET / HTTP/1.1\r\n").is_ok());
+  let line = b"X: 1\r\n";
+  let result = (0..RequestParser::DEFAULT_MAX_HEADERS + 1).map(|_| parser.feed(line)).last().unwrap();
+  assert_eq!(result, Err(Error::TooManyHeaders));
+  let mut parser = RequestParser::new();
+  assert_eq!(parser.feed(&vec![b'G'; RequestParser::DEFAULT_MAX_HEAD_SIZE + 1]), Err(Error::Malformed));
+}
+
+
+
+// --------------------------
+//  TESTING FORWARDED AUTHORITIES
+// --------------------------
+
+macro_rules! authority {
+  ($name:ident, $buf:expr, $sni:expr, $expected:expr) => (
+    #[test]
+    fn $name() {
+      let buf = $buf;
+      let mut headers = [crate::EMPTY_HEADER; 10];
+      let req = crate::request::Request::parse(buf, &mut headers).unwrap();
+      let decision = crate::host::authority_for_forwarding(&req, $sni);
+      assert_eq!(decision.map(|decision| (decision.authority.into_owned(), decision.sni_verified)), $expected);
+      }
+  );
+}
+
+authority! {
+    test_authority_matching_sni,
+    b"GET / HTTP/1.1\r\nHost: API.example.com:443\r\n\r\n",
+    Some("api.EXAMPLE.com"),
+    Ok(("api.example.com".to_string(), true))
+}
+
+authority! {
+    test_authority_mismatching_sni,
+    b"GET / HTTP/1.1\r\nHost: admin.example.com\r\n\r\n",
+    Some("api.example.com"),
+    Err(crate::host::HostError::Misdirected)
+}
+
+authority! {
+    test_authority_ipv6_literal,
+    b"GET / HTTP/1.1\r\nHost: [::1]:8443\r\n\r\n",
+    Some("::1"),
+    Err(crate::host::HostError::Misdirected)
+}
+
+authority! {
+    test_authority_ipv6_literal_without_sni,
+    b"GET / HTTP/1.1\r\nHost: [::1]:8443\r\n\r\n",
+    None,
+    Ok(("[::1]:8443".to_string(), false))
+}
+
+authority! {
+    test_authority_missing_host,
+    b"GET /index.html HTTP/1.1\r\nAccept: */*\r\n\r\n",
+    None,
+    Err(crate::host::HostError::Missing)
+}
+
+authority! {
+    test_authority_absolute_form_wins,
+    b"GET http://origin.example.com:80/a HTTP/1.1\r\nHost: other.example.com\r\n\r\n",
+    None,
+    Ok(("origin.example.com".to_string(), false))
+}
+
+authority! {
+    test_authority_duplicate_host,
+    b"GET / HTTP/1.1\r\nHost: a.example.com\r\nhost: b.example.com\r\n\r\n",
+    None,
+    Err(crate::host::HostError::Duplicate)
+}
+
+
+
+
+// --------------------------
+//  TESTING PARSING INTO INDICES
+// --------------------------
+
+#[test]
+fn test_indices_request_survives_copy() {
+    use crate::indices::EMPTY_HEADER_INDICES;
+    let buf = b"POST /upload?id=1 HTTP/1.1\r\nHost: foo.com\r\nContent-Length: 5\r\n\r\nhello".to_vec();
+    let mut header_indices = [EMPTY_HEADER_INDICES; 10];
+    let indices = crate::Request::parse_indices(&buf, &mut header_indices).unwrap();
+    assert_eq!(indices.method, 0..4);
+    assert_eq!(indices.headers, 2);
+    assert_eq!(indices.body, (buf.len() as u32 - 5)..(buf.len() as u32));
+    let mut copy = vec![0; buf.len()];
+    copy.copy_from_slice(&buf);
+    drop(buf);
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = indices.request(&copy, &header_indices, &mut headers).unwrap();
+    let mut direct_headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(req, crate::Request::parse(&copy, &mut direct_headers).unwrap());
+}
+
+#[test]
+fn test_indices_response() {
+    use crate::indices::EMPTY_HEADER_INDICES;
+    for buf in [&b"HTTP/1.1 404 Not Found\r\nServer: foo\r\n\r\nbody"[..], b"HTTP/1.1 204\r\n\r\n"] {
+        let mut header_indices = [EMPTY_HEADER_INDICES; 10];
+        let indices = crate::Response::parse_indices(buf, &mut header_indices).unwrap();
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        let res = indices.response(buf, &header_indices, &mut headers).unwrap();
+        let mut direct_headers = [crate::EMPTY_HEADER; 10];
+        assert_eq!(res, crate::Response::parse(buf, &mut direct_headers).unwrap());
+    }
+}
+
+#[test]
+fn test_indices_errors() {
+    use crate::indices::EMPTY_HEADER_INDICES;
+    let buf = b"GET / HTTP/1.1\r\nHost: foo.com\r\nAccept: */*\r\n\r\n";
+    let mut header_indices = [EMPTY_HEADER_INDICES; 1];
+    assert_eq!(crate::Request::parse_indices(buf, &mut header_indices), Err(Error::TooManyHeaders));
+    let mut header_indices = [EMPTY_HEADER_INDICES; 10];
+    assert_eq!(crate::Request::parse_indices(&buf[..buf.len() - 1], &mut header_indices), Err(Error::Incomplete { needed: Some(1) }));
+    let indices = crate::Request::parse_indices(buf, &mut header_indices).unwrap();
+    let mut headers = [crate::EMPTY_HEADER; 1];
+    assert_eq!(indices.request(buf, &header_indices, &mut headers), Err(Error::TooManyHeaders));
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(indices.request(&buf[..20], &header_indices, &mut headers), Err(Error::Malformed));
+}
+
+#[test]
+fn test_indices_match_request_parser() {
+    use crate::indices::EMPTY_HEADER_INDICES;
+    let buf = b"GET /a HTTP/1.1\r\nHost: foo.com\r\nAccept: */*\r\n\r\nbody";
+    let mut parser = crate::RequestParser::new();
+    parser.feed(&buf[..20]).unwrap();
+    parser.feed(&buf[20..]).unwrap();
+    let mut header_indices = [EMPTY_HEADER_INDICES; 10];
+    let indices = crate::Request::parse_indices(buf, &mut header_indices).unwrap();
+    assert_eq!(parser.indices(), Ok(indices.clone()));
+    assert_eq!(parser.header_indices(), &header_indices[..indices.headers]);
+    assert_eq!(parser.buf(), buf);
+}
+
+
+
+
+// --------------------------
+//  TESTING CANONICAL FORMS
+// --------------------------
+
+// canonical query string of the query of `url` after sorting
+fn canonical_query(url: &[u8]) -> String {
+    let mut queries = [crate::EMPTY_QUERY; 10];
+    crate::Url::parse(url, &mut queries).unwrap();
+    crate::canonical::sort_query(&mut queries);
+    let mut out = Vec::new();
+    crate::canonical::canonical_query_string(&queries, &mut out);
+    String::from_utf8(out).unwrap()
+}
+
+// canonical headers of the request
+fn canonical_headers(buf: &[u8]) -> String {
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let count = crate::Request::parse(buf, &mut headers).unwrap().headers.len();
+    let mut out = Vec::new();
+    crate::canonical::canonical_headers(&mut headers[..count], &mut out);
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_canonical_query_aws_vectors() {
+    // get-vanilla-query-order-key-case, get-vanilla-query-order-value, and get-vanilla-query-unreserved of the SigV4 test suite
+    assert_eq!(canonical_query(b"/?Param2=value2&Param1=value1"), "Param1=value1&Param2=value2");
+    assert_eq!(canonical_query(b"/?Param1=value2&Param1=Value1"), "Param1=Value1&Param1=value2");
+    let unreserved = "-._~0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    assert_eq!(canonical_query(format!("/?{unreserved}={unreserved}").as_bytes()), format!("{unreserved}={unreserved}"));
+    // get-vanilla-utf8-query
+    assert_eq!(canonical_query("/?ሴ=bar".as_bytes()), "%E1%88%B4=bar");
+    // the IAM ListUsers example of the signing documentation
+    assert_eq!(canonical_query(b"/?Version=2010-05-08&Action=ListUsers"), "Action=ListUsers&Version=2010-05-08");
+}
+
+#[test]
+fn test_canonical_query_encoding() {
+    assert_eq!(canonical_query(b"/?a=x%20y&b=*&c=+&d=%7e&e=%zz&f="), "a=x%20y&b=%2A&c=%2B&d=~&e=%25zz&f=");
+    assert_eq!(canonical_query(b"/?b=1&a=2&a=1"), "a=1&a=2&b=1");
+    assert_eq!(canonical_query(b"/"), "");
+}
+
+#[test]
+fn test_canonical_headers_aws_vectors() {
+    // get-header-value-trim and get-header-key-duplicate of the SigV4 test suite
+    assert_eq!(
+        canonical_headers(b"GET / HTTP/1.1\r\nHost:example.amazonaws.com\r\nMy-Header1: value1\r\nMy-Header2: \"a   b   c\"\r\nX-Amz-Date:20150830T123600Z\r\n\r\n"),
+        "host:example.amazonaws.com\nmy-header1:value1\nmy-header2:\"a b c\"\nx-amz-date:20150830T123600Z\n"
+    );
+    assert_eq!(
+        canonical_headers(b"GET / HTTP/1.1\r\nHost:example.amazonaws.com\r\nMy-Header1:value2\r\nMy-Header1:value2\r\nMy-Header1:value1\r\nX-Amz-Date:20150830T123600Z\r\n\r\n"),
+        "host:example.amazonaws.com\nmy-header1:value2,value2,value1\nx-amz-date:20150830T123600Z\n"
+    );
+    assert_eq!(canonical_headers(b"GET / HTTP/1.1\r\nB: \r\na: 1\r\n\r\n"), "a:1\nb:\n");
+}
+
+
+
+
+// --------------------------
+//  TESTING HTTP/2 PSEUDO-HEADERS
+// --------------------------
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_h2_request_from_pseudo_headers() {
+    use crate::{Header, h2::{request_from_pseudo_headers, pseudo_header}};
+    let fields = [
+        Header::new(":method", b"POST"),
+        Header::new(":scheme", b"https"),
+        Header::new(":authority", b"example.com"),
+        Header::new(":path", b"/upload?id=1"),
+        Header::new("content-length", b"5"),
+    ];
+    let req = request_from_pseudo_headers(&fields, b"hello").unwrap();
+    assert_eq!(req, crate::Request::new(Method::Post, "/upload?id=1", &fields[4..], b"hello"));
+    assert_eq!(req.as_bytes(), b"POST /upload?id=1 HTTP/1.1\r\ncontent-length: 5\r\n\r\nhello");
+    assert_eq!(pseudo_header(&fields, ":authority"), Some(&b"example.com"[..]));
+    assert_eq!(pseudo_header(&fields, "content-length"), None);
+    let fields = [Header::new(":method", b"GET"), Header::new(":scheme", b"http"), Header::new(":path", b"/"), Header::new("host", b"a.com")];
+    assert_eq!(request_from_pseudo_headers(&fields, b"").unwrap().as_bytes(), b"GET / HTTP/1.1\r\nhost: a.com\r\n\r\n");
+    let fields = [Header::new(":method", b"CONNECT"), Header::new(":authority", b"a.com:443")];
+    assert_eq!(request_from_pseudo_headers(&fields, b"").unwrap().as_bytes(), b"CONNECT a.com:443 HTTP/1.1\r\n\r\n");
+}
+
+#[test]
+fn test_h2_invalid_pseudo_headers() {
+    use crate::{Header, h2::request_from_pseudo_headers};
+    let invalid: [&[Header]; 14] = [
+        &[Header::new(":path", b"/")],
+        &[Header::new(":method", b"GET"), Header::new(":scheme", b"https"), Header::new(":authority", b"a.com")],
+        &[Header::new(":method", b"GET"), Header::new(":scheme", b"https"), Header::new(":authority", b"a.com"), Header::new(":path", b"/"), Header::new(":path", b"/a")],
+        &[Header::new(":method", b"GET"), Header::new(":scheme", b"https"), Header::new(":authority", b"a.com"), Header::new("accept", b"*/*"), Header::new(":path", b"/")],
+        &[Header::new(":method", b"GET"), Header::new(":scheme", b"https"), Header::new(":authority", b"a.com"), Header::new(":path", b"/"), Header::new(":status", b"200")],
+        &[Header::new(":method", b"GET"), Header::new(":scheme", b"https"), Header::new(":authority", b"a.com"), Header::new(":path", b"/a b")],
+        &[Header::new(":method", b"GET"), Header::new(":authority", b"a.com"), Header::new(":path", b"/")],
+        &[Header::new(":method", b"GET"), Header::new(":scheme", b"1http"), Header::new(":authority", b"a.com"), Header::new(":path", b"/")],
+        &[Header::new(":method", b"GET"), Header::new(":scheme", b"https"), Header::new(":authority", b"user@a.com"), Header::new(":path", b"/")],
+        &[Header::new(":method", b"GET"), Header::new(":scheme", b"https"), Header::new(":authority", b"a.com:99999"), Header::new(":path", b"/")],
+        &[Header::new(":method", b"GET"), Header::new(":scheme", b"https"), Header::new(":path", b"/")],
+        &[Header::new(":method", b"GET"), Header::new(":scheme", b"https"), Header::new(":authority", b"a.com"), Header::new(":path", b"/"), Header::new("host", b"b.com")],
+        &[Header::new(":method", b"CONNECT"), Header::new(":scheme", b"https"), Header::new(":authority", b"a.com:443")],
+        &[Header::new(":method", b"CONNECT"), Header::new(":path", b"/")],
+    ];
+    for fields in invalid {
+        assert_eq!(request_from_pseudo_headers(fields, b""), Err(Error::Malformed));
+    }
+}
+
+#[test]
+fn test_h2_connection_preface() {
+    use crate::h2::{is_connection_preface, PrefaceStatus, CONNECTION_PREFACE};
+    assert_eq!(is_connection_preface(CONNECTION_PREFACE), PrefaceStatus::Complete);
+    assert_eq!(is_connection_preface(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\x00\x00\x00\x04\x00\x00\x00\x00\x00"), PrefaceStatus::Complete);
+    for len in 0..CONNECTION_PREFACE.len() {
+        assert_eq!(is_connection_preface(&CONNECTION_PREFACE[..len]), PrefaceStatus::Partial, "prefix of {len} bytes");
+    }
+    for garbage in [&b"PRI * HTTP/2.0\r\n\r\nSM\r\n\rX"[..], b"PRI * HTTP/1.1\r\n\r\n", b"pri * HTTP/2.0", b"GET / HTTP/1.1\r\n\r\n"] {
+        assert_eq!(is_connection_preface(garbage), PrefaceStatus::Absent);
+    }
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    assert_eq!(crate::Request::parse(CONNECTION_PREFACE, &mut headers), Err(Error::Malformed));
+}
+
+
+
+
+// --------------------------
+//  TESTING PRETTY PRINTING
+// --------------------------
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_pretty_binary_response() {
+    use crate::pretty::{dump_response, DumpOptions};
+    let buf = b"HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nSet-Cookie: id=1\r\n\r\n\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\x01";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let res = crate::Response::parse(buf, &mut headers).unwrap();
+    let mut out = String::new();
+    dump_response(&res, &DumpOptions::default(), &mut out).unwrap();
+    assert_eq!(out, "HTTP/1.1 200 OK\n\
+Content-Type : application/octet-stream\n\
+Set-Cookie   : <redacted>\n\
+\n\
+00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|\n\
+00000010  00 01                                             |..|\n");
+}
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_pretty_truncation() {
+    use crate::pretty::{dump_request, dump_request_io, DumpOptions};
+    let buf = b"POST / HTTP/1.1\r\nX-Long: 0123456789abcdef\r\n\r\n0123456789";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(buf, &mut headers).unwrap();
+    let opts = DumpOptions { width: 19, max_body_bytes: 4, ..DumpOptions::default() };
+    let mut out = String::new();
+    dump_request(&req, &opts, &mut out).unwrap();
+    assert_eq!(out, "POST / HTTP/1.1\nX-Long : 0123456789...(6 more bytes)\n\n0123\n-- 6 more bytes not shown --\n");
+    let mut bytes = Vec::new();
+    dump_request_io(&req, &opts, &mut bytes).unwrap();
+    assert_eq!(bytes, out.as_bytes());
+}
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_pretty_chunked() {
+    use crate::pretty::{dump_request, DumpOptions};
+    let buf = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n3;ext=1\r\n\x00\x01\x02\r\n0\r\n\r\n";
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(buf, &mut headers).unwrap();
+    let opts = DumpOptions { color: true, ..DumpOptions::default() };
+    let mut out = String::new();
+    dump_request(&req, &opts, &mut out).unwrap();
+    assert_eq!(out, "\x1b[1mPOST / HTTP/1.1\x1b[0m\n\
+\x1b[36mTransfer-Encoding\x1b[0m : chunked\n\
+\n\
+\x1b[2m-- chunk 0: 5 bytes --\x1b[0m\n\
+hello\n\
+\x1b[2m-- chunk 1: 3 bytes --\x1b[0m\n\
+00000000  00 01 02                                          |...|\n\
+\x1b[2m-- last chunk --\x1b[0m\n");
+}
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_pretty_chunked_hostile_size() {
+    use crate::pretty::{dump_response, DumpOptions};
+    let headers = [crate::Header::new("Transfer-Encoding", b"chunked")];
+    let mut out = String::new();
+    let res = crate::Response::new(200, "OK", &headers, b"ffffffffffffffff\r\nab");
+    dump_response(&res, &DumpOptions::default(), &mut out).unwrap();
+    assert_eq!(out, "HTTP/1.1 200 OK\nTransfer-Encoding : chunked\n\n-- 20 bytes after the chunks --\nffffffffffffffff\r\nab\n");
+    out.clear();
+    let res = crate::Response::new(200, "OK", &headers, b"2\r\nhi\r\nfffffffffffffff\r\nab");
+    dump_response(&res, &DumpOptions::default(), &mut out).unwrap();
+    assert_eq!(out, "HTTP/1.1 200 OK\nTransfer-Encoding : chunked\n\n-- chunk 0: 2 bytes --\nhi\n-- 19 bytes after the chunks --\nfffffffffffffff\r\nab\n");
+}
+
+
+
+
+// --------------------------
+//  TESTING NORMALIZATION
+// --------------------------
+
+#[test]
+fn test_normalize_golden() {
+    use crate::normalize::{message, NameCase, NormalizeOptions, NormalizeReport};
+    let input = b"POST  /upload HTTP/1.1\r\nHost:example.com\r\nX-Folded: first\r\n   second\nTransfer-Encoding: chunked\r\nAccept:\t*/* \r\n\r\n5;ext=1\r\nhello\r\n6\r\n world\r\n0\r\nTrailer: x\r\n\r\n";
+    let opts = NormalizeOptions { name_case: NameCase::Canonical, sort_headers: true, ..NormalizeOptions::default() };
+    let mut out = Vec::new();
+    let report = message(input, &opts, &mut out).unwrap();
+    assert_eq!(out, b"POST /upload HTTP/1.1\r\n\
+Accept: */*\r\n\
+Content-Length: 11\r\n\
+Host: example.com\r\n\
+X-Folded: first second\r\n\
+\r\n\
+hello world");
+    assert_eq!(report, NormalizeReport {
+        line_endings: true,
+        separators: true,
+        unfolded: true,
+        whitespace_trimmed: true,
+        names_recased: false,
+        dechunked: true,
+        headers_reordered: true,
+    });
+    let mut again = Vec::new();
+    assert!(message(&out, &opts, &mut again).unwrap().is_unchanged());
+    crate::conformance::assert_idempotent_normalization(input, |input| {
+        let mut out = Vec::new();
+        message(input, &opts, &mut out).map(|_| out)
+    });
+}
+
+#[test]
+fn test_normalize_names_and_codings() {
+    use crate::normalize::{message, NormalizeOptions};
+    let input = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nhi\r\n0\r\n\r\n";
+    let mut out = Vec::new();
+    let report = message(input, &NormalizeOptions::default(), &mut out).unwrap();
+    assert_eq!(out, b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 2\r\n\r\nhi");
+    assert!(report.names_recased && report.dechunked && !report.headers_reordered);
+    let mut again = Vec::new();
+    assert!(message(&out, &NormalizeOptions::default(), &mut again).unwrap().is_unchanged());
+    // a body with another coding keeps its chunked framing
+    let input = b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n2\r\nhi\r\n0\r\n\r\n";
+    let mut out = Vec::new();
+    let report = message(input, &NormalizeOptions::default(), &mut out).unwrap();
+    assert_eq!(out, b"POST / HTTP/1.1\r\ntransfer-encoding: gzip, chunked\r\n\r\n2\r\nhi\r\n0\r\n\r\n");
+    assert!(!report.dechunked);
+    // only the separators of the start line are collapsed, the reason phrase is kept as sent
+    let mut out = Vec::new();
+    let report = message(b"HTTP/1.1  404\tNot Found\r\n\r\n", &NormalizeOptions::default(), &mut out).unwrap();
+    assert_eq!(out, b"HTTP/1.1 404 Not Found\r\n\r\n");
+    assert!(report.separators);
+    let mut out = Vec::new();
+    assert_eq!(message(b"HTTP/1.1 404 Not  Found\r\n\r\n", &NormalizeOptions::default(), &mut out), Err(Error::Malformed));
+    let mut out = Vec::new();
+    assert_eq!(message(b"GET / HTTP/1.1\r\nHost: a", &NormalizeOptions::default(), &mut out), Err(Error::Incomplete { needed: None }));
+    assert_eq!(message(b"GET / HTTP/1.1\r\n folded\r\n\r\n", &NormalizeOptions::default(), &mut out), Err(Error::Malformed));
+    assert_eq!(message(b"GET / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\nextra", &NormalizeOptions::default(), &mut out), Err(Error::Malformed));
+    assert!(out.is_empty());
+}
+
+
+
+
+// --------------------------
+//  TESTING INSPECTION
+// --------------------------
+
+// the rule, part, and offset of each finding of the built-in rules
+fn inspect_request(buf: &[u8]) -> Vec<(&'static str, crate::inspect::Part, usize)> {
+    use crate::inspect::{inspect, RuleSet};
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(buf, &mut headers).unwrap();
+    inspect(&req, &RuleSet::new()).iter().map(|finding| (finding.rule, finding.part, finding.offset)).collect()
+}
+
+#[test]
+fn test_inspect_builtin_rules() {
+    use crate::inspect::{Part, *};
+    assert_eq!(inspect_request(b"GET /index.html?a=1 HTTP/1.1\r\nHost: a.com\r\nContent-Length: 0\r\n\r\n"), vec![]);
+    assert_eq!(inspect_request(b"GET /a%0D%0ab HTTP/1.1\r\n\r\n"), vec![(ENCODED_CRLF, Part::Target, 2), (ENCODED_CRLF, Part::Target, 5)]);
+    assert_eq!(inspect_request(b"GET /a/..%c0%af/b HTTP/1.1\r\n\r\n"), vec![(OVERLONG_ENCODING, Part::Target, 5)]);
+    assert_eq!(inspect_request(b"GET /%e0%80%af HTTP/1.1\r\n\r\n"), vec![(OVERLONG_ENCODING, Part::Target, 1)]);
+    assert_eq!(inspect_request(b"GET /%e0%a4%85 HTTP/1.1\r\n\r\n"), vec![]);
+    assert_eq!(
+        inspect_request(b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, Chunked\r\n\r\n"),
+        vec![(CHUNKED_CASE, Part::HeaderValue(0), 6)]
+    );
+    let query = format!("GET /?{} HTTP/1.1\r\n\r\n", vec!["a=1"; 66].join("&"));
+    assert_eq!(inspect_request(query.as_bytes()), vec![(QUERY_PARAM_COUNT, Part::Target, 2 + 64 * 4)]);
+    assert_eq!(
+        inspect_request(b"GET / HTTP/1.1\r\nHost: a.com\r\nAccept: */*\r\nhost: b.com\r\nHost: c.com\r\n\r\n"),
+        vec![(CASE_DUPLICATE_HEADER, Part::HeaderName(2), 0), (CASE_DUPLICATE_HEADER, Part::HeaderName(3), 0)]
+    );
+    assert_eq!(inspect_request(b"GET / HTTP/1.1\r\nAccept: a\r\nAccept: b\r\n\r\n"), vec![]);
+    assert_eq!(inspect_request(b"POST / HTTP/1.1\r\nContent-Length: 005\r\n\r\n"), vec![(CONTENT_LENGTH_FORM, Part::HeaderValue(0), 0)]);
+    assert_eq!(inspect_request(b"POST / HTTP/1.1\r\nContent-Length: 5, 5\r\n\r\n"), vec![(CONTENT_LENGTH_FORM, Part::HeaderValue(0), 1)]);
+}
+
+#[test]
+fn test_inspect_custom_rules() {
+    use crate::inspect::{inspect, Finding, Part, RuleSet, Severity, ENCODED_CRLF};
+    let mut headers = [crate::EMPTY_HEADER; 10];
+    let req = crate::Request::parse(b"GET /a%0a HTTP/1.1\r\nUser-Agent: sqlmap/1.0\r\n\r\n", &mut headers).unwrap();
+    let rules = RuleSet::new().disable(ENCODED_CRLF).with_rule(|req, findings| {
+        for (index, header) in req.headers.iter().enumerate() {
+            if header.name.eq_ignore_ascii_case("User-Agent") && header.val.starts_with(b"sqlmap") {
+                findings.push(Finding { rule: "scanner", severity: Severity::High, part: Part::HeaderValue(index), offset: 0 });
+            }
+        }
+    });
+    let findings = inspect(&req, &rules);
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings.iter().next().unwrap().rule, "scanner");
+    assert_eq!(findings.max_severity(), Some(Severity::High));
+    assert_eq!(inspect(&req, &RuleSet::new()).max_severity(), Some(Severity::High));
+}
+
+
+
+
+// --------------------------
+//  TESTING DATES
+// --------------------------
+
+#[test]
+fn test_date_range() {
+    use crate::date::HttpDate;
+    assert_eq!(HttpDate::from_unix_secs(-2_208_988_800), Some(HttpDate::MIN));
+    assert_eq!(HttpDate::from_unix_secs(-2_208_988_801), None);
+    assert_eq!(HttpDate::from_unix_secs(253_402_300_799), Some(HttpDate::MAX));
+    assert_eq!(HttpDate::from_unix_secs(253_402_300_800), None);
+    assert_eq!(HttpDate::from_unix_secs(i64::MAX), None);
+    assert_eq!(HttpDate::from_unix_secs(i64::MIN), None);
+    assert_eq!(HttpDate::MIN.unix_secs(), -2_208_988_800);
+    assert_eq!(HttpDate::MAX.unix_secs(), 253_402_300_799);
+}
+
+#[test]
+fn test_date_arithmetic_extremes() {
+    use crate::date::HttpDate;
+    let near_max = HttpDate::MAX.checked_sub(10).unwrap();
+    assert_eq!(near_max.checked_add_secs(10), Some(HttpDate::MAX));
+    assert_eq!(near_max.checked_add_secs(11), None);
+    assert_eq!(near_max.checked_add_secs(u32::MAX as u64), None);
+    assert_eq!(near_max.saturating_add_secs(u32::MAX as u64), HttpDate::MAX);
+    for secs in [0, 1, u32::MAX as u64, u64::MAX / 2, u64::MAX - 1, u64::MAX] {
+        assert!(HttpDate::MAX.checked_add_secs(secs).is_none_or(|date| date == HttpDate::MAX));
+        assert!(HttpDate::MIN.checked_sub(secs).is_none_or(|date| date == HttpDate::MIN));
+        assert!(HttpDate::MIN.saturating_add_secs(secs) <= HttpDate::MAX);
+        assert!(HttpDate::MAX.saturating_sub(secs) >= HttpDate::MIN);
+    }
+    assert_eq!(HttpDate::MIN.checked_sub(1), None);
+    assert_eq!(HttpDate::MIN.saturating_sub(u64::MAX), HttpDate::MIN);
+    assert_eq!(HttpDate::MIN.checked_add_secs(u64::MAX), None);
+}
+
+#[test]
+fn test_date_duration_since() {
+    use crate::date::HttpDate;
+    let date = HttpDate::from_unix_secs(1_445_412_480).unwrap();
+    assert_eq!(date.duration_since(date), Some(0));
+    assert_eq!(date.duration_since(HttpDate::from_unix_secs(1_445_412_000).unwrap()), Some(480));
+    assert_eq!(HttpDate::MIN.duration_since(date), None);
+    assert_eq!(HttpDate::MAX.duration_since(HttpDate::MIN), Some(255_611_289_599));
+}
+
+#[test]
+fn test_date_needs_no_clock() {
+    // the date code must build on targets without a wall clock
+    assert!(!include_str!("date.rs").contains("std::time"));
+    assert!(!include_str!("date.rs").contains("SystemTime"));
+}
+
+
+
+
+
+// --------------------------
+//  TESTING CONFORMANCE
+// --------------------------
+
+#[cfg(feature = "fmt")]
+#[test]
+fn test_conformance_url_round_trip() {
+    use crate::{conformance::{assert_never_panics_on_prefixes, assert_roundtrip_parse_serialize}, Url, EMPTY_QUERY};
+    for input in [&b"/"[..], b"/a/b%20c", b"/search?q=rust&page=2", b"?a=1", b""] {
+        let mut queries = [EMPTY_QUERY; 10];
+        assert_roundtrip_parse_serialize(input, |input| Url::parse(input, &mut queries), |url| url.to_string().into_bytes());
+    }
+    assert_never_panics_on_prefixes(b"/a%2Fb?c=%20&d=e#frag", |input| {
+        let mut queries = [EMPTY_QUERY; 10];
+        let _ = Url::parse(input, &mut queries);
+    });
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_conformance_catches_serializer_bug() {
+    use crate::conformance::assert_roundtrip_parse_serialize;
+    let buf = b"GET / HTTP/1.1\r\nHost: a.com\r\nAccept: */*\r\n\r\n";
+    // a serializer that loses the last header
+    let result = std::panic::catch_unwind(|| {
+        let mut headers = [crate::EMPTY_HEADER; 10];
+        assert_roundtrip_parse_serialize(buf, |buf| crate::Request::parse(buf, &mut headers), |req| {
+            crate::Request::new(req.method.clone(), req.path, &req.headers[..1], req.body).as_bytes()
+        });
+    });
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.contains("first difference at byte 29, expected 44 bytes and got 31"), "{message}");
+    assert!(message.contains("00000010  48 6f 73 74 3a 20 61 2e  63 6f 6d 0d 0a 41 63 63  |Host: a.com..Acc|"), "{message}");
+}
+
+#[test]
+fn test_conformance_catches_panics_and_unstable_normalization() {
+    use crate::conformance::{assert_idempotent_normalization, assert_never_panics_on_prefixes};
+    let result = std::panic::catch_unwind(|| assert_never_panics_on_prefixes(b"abc", |input| assert!(input.len() < 2)));
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.starts_with("parsing panicked on the prefix of 2 bytes"), "{message}");
+    let result = std::panic::catch_unwind(|| assert_idempotent_normalization(b"a", |input| Ok::<_, ()>([input, b"!"].concat())));
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.contains("first difference at byte 2"), "{message}");
+}
+
+
+
+
+
+// --------------------------
+//  TESTING FFI
+// --------------------------
+
+#[test]
+fn test_ffi_parse_request() {
+    use crate::ffi::{htpp_header, htpp_method, htpp_parse_request, htpp_request, HTPP_NULL_POINTER, HTPP_OK};
+    // reads a string written by the C API back
+    unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+        // SAFETY: the pointers written by htpp_parse_request point into the buffer or static memory
+        unsafe { std::slice::from_raw_parts(ptr, len) }
+    }
+    let buf = b"PROPFIND /files HTTP/1.1\r\nHost: a.com\r\nDepth: 1\r\n\r\nbody";
+    let empty = htpp_header { name: std::ptr::null(), name_len: 0, val: std::ptr::null(), val_len: 0 };
+    let mut headers = [empty; 4];
+    let mut req = std::mem::MaybeUninit::<htpp_request>::uninit();
+    // SAFETY: the buffer, request, and headers array are valid for their lengths
+    let status = unsafe { htpp_parse_request(buf.as_ptr(), buf.len(), req.as_mut_ptr(), headers.as_mut_ptr(), headers.len()) };
+    assert_eq!(status, HTPP_OK);
+    // SAFETY: htpp_parse_request wrote the request on success
+    let req = unsafe { req.assume_init() };
+    assert_eq!(req.method, htpp_method::HTPP_METHOD_OTHER);
+    // SAFETY: the pointers come from a successful parse of `buf`, which is still alive
+    unsafe {
+        assert_eq!(bytes(req.method_name, req.method_name_len), b"PROPFIND");
+        assert_eq!(bytes(req.path, req.path_len), b"/files");
+        assert_eq!(bytes(req.body, req.body_len), b"body");
+        assert_eq!(req.headers_len, 2);
+        assert_eq!((bytes(headers[1].name, headers[1].name_len), bytes(headers[1].val, headers[1].val_len)), (&b"Depth"[..], &b"1"[..]));
+    }
+    assert_eq!(req.path, buf[9..].as_ptr());
+
+    let mut req = std::mem::MaybeUninit::<htpp_request>::uninit();
+    // more headers than the scratch buffer holds are copied in several batches
+    let mut many = b"GET / HTTP/1.1\r\n".to_vec();
+    for index in 0..70 {
+        many.extend(format!("X-{index}: {index}\r\n").as_bytes());
+    }
+    many.extend(b"\r\n");
+    let mut headers = [empty; 70];
+    // SAFETY: the buffer, request, and headers array are valid for their lengths
+    unsafe {
+        assert_eq!(htpp_parse_request(many.as_ptr(), many.len(), req.as_mut_ptr(), headers.as_mut_ptr(), 70), HTPP_OK);
+        assert_eq!(req.assume_init().headers_len, 70);
+        assert_eq!(bytes(headers[69].name, headers[69].name_len), b"X-69");
+        assert_eq!(htpp_parse_request(many.as_ptr(), many.len(), req.as_mut_ptr(), headers.as_mut_ptr(), 69), 400);
+    }
+
+    let mut req = std::mem::MaybeUninit::<htpp_request>::uninit();
+    // SAFETY: as above, the errors don't write the request
+    unsafe {
+        assert_eq!(htpp_parse_request(b"GET / HTTP/1.1\r\n".as_ptr(), 16, req.as_mut_ptr(), headers.as_mut_ptr(), 4), 101);
+        assert_eq!(htpp_parse_request(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n\r\n".as_ptr(), 30, req.as_mut_ptr(), headers.as_mut_ptr(), 1), 400);
+        assert_eq!(htpp_parse_request(b"GET / HTTP/1.1\r\n\r\n".as_ptr(), 18, req.as_mut_ptr(), std::ptr::null_mut(), 0), HTPP_OK);
+        assert_eq!(req.assume_init().method, htpp_method::HTPP_METHOD_GET);
+        assert_eq!(htpp_parse_request(std::ptr::null(), 0, req.as_mut_ptr(), headers.as_mut_ptr(), 4), HTPP_NULL_POINTER);
+        assert_eq!(htpp_parse_request(b"GET".as_ptr(), 3, std::ptr::null_mut(), headers.as_mut_ptr(), 4), HTPP_NULL_POINTER);
+    }
+}
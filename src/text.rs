@@ -0,0 +1,98 @@
+#![allow(unused)]
+#![deny(
+    missing_docs,
+    clippy::missing_safety_doc,
+    clippy::undocumented_unsafe_blocks
+)]
+
+//! Human readable rendering of bytes that may not be valid UTF-8
+//!
+//! ```rust
+//! use htpp::text::lossy_escape;
+//!
+//! let rendered = lossy_escape(b"\xe3\x81\xb2\xe3/1.0", 100).to_string();
+//! assert!(rendered == "\u{3072}\\xe3/1.0");
+//! ```
+
+use std::{fmt, str};
+
+
+/// The output limit used by the `Display` impls of this crate when rendering header values and bodies
+pub const DEFAULT_LIMIT: usize = 64 * 1024;
+// the bytes in a row of a hex dump
+pub(crate) const HEX_ROW: usize = 16;
+
+/// Renders the bytes with valid UTF-8 runs written as is and every invalid byte escaped as `\xNN`.
+/// At most `limit` bytes are written, longer inputs are cut off and end with a marker telling how many input bytes were left out
+#[inline]
+pub fn lossy_escape(bytes: &[u8], limit: usize) -> LossyEscape<'_> {
+  LossyEscape { bytes, limit }
+}
+
+/// The `Display` adapter returned by [lossy_escape]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct LossyEscape<'a> {
+    bytes: &'a [u8],
+    limit: usize,
+}
+impl<'a> fmt::Display for LossyEscape<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      let mut rest = self.bytes;
+      let mut written = 0;
+      while !rest.is_empty() {
+        let (valid, invalid) = match str::from_utf8(rest) {
+          Ok(valid) => (valid, 0),
+          Err(err) => {
+            //SAFETY: `valid_up_to` is the length of the prefix that is valid UTF-8
+            let valid = unsafe { str::from_utf8_unchecked(&rest[..err.valid_up_to()]) };
+            (valid, err.error_len().unwrap_or(rest.len() - err.valid_up_to()))
+          },
+        };
+        if written + valid.len() > self.limit {
+          let mut end = self.limit - written;
+          while !valid.is_char_boundary(end) {end -= 1;}
+          f.write_str(&valid[..end])?;
+          return write_truncated(f, rest.len() - end);
+        }
+        f.write_str(valid)?;
+        written += valid.len();
+        rest = &rest[valid.len()..];
+        for byte in &rest[..invalid] {
+          if written + 4 > self.limit {
+            return write_truncated(f, rest.len());
+          }
+          write!(f, "\\x{byte:02x}")?;
+          written += 4;
+          rest = &rest[1..];
+        }
+      }
+      Ok(())
+    }
+}
+
+#[inline]
+fn write_truncated(f: &mut fmt::Formatter<'_>, remaining: usize) -> fmt::Result {
+  write!(f, "...({remaining} more bytes)")
+}
+
+#[inline]
+// writes rows of 16 bytes as `indent`, an offset counted from `first_offset`, the bytes in hex in two groups of 8,
+// and the bytes as ASCII with `.` for other bytes
+pub(crate) fn hex_dump<W: fmt::Write>(bytes: &[u8], first_offset: usize, indent: &str, out: &mut W) -> fmt::Result {
+  for (row, chunk) in bytes.chunks(HEX_ROW).enumerate() {
+    write!(out, "{indent}{:08x} ", first_offset + row * HEX_ROW)?;
+    for column in 0..HEX_ROW {
+      if column % 8 == 0 {out.write_char(' ')?;}
+      match chunk.get(column) {
+        Some(byte) => write!(out, "{byte:02x} ")?,
+        None => out.write_str("   ")?,
+      }
+    }
+    out.write_str(" |")?;
+    for byte in chunk {
+      out.write_char(if byte.is_ascii_graphic() || *byte == b' ' {*byte as char} else {'.'})?;
+    }
+    out.write_str("|\n")?;
+  }
+  Ok(())
+}
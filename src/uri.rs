@@ -6,10 +6,11 @@
     clippy::missing_safety_doc,
     clippy::undocumented_unsafe_blocks
 )]
+//! Parsing of URLs, request targets, and origins
 
-use std::{collections::HashMap, fmt, path::Path};
+use std::{borrow::Cow, collections::HashMap, fmt, path::Path, str};
 
-use crate::URL_SAFE;
+use crate::{ErrorCategory, URL_SAFE};
 
 
 
@@ -24,6 +25,7 @@ pub struct QueryParam<'a> {
     /// The value of the query parameter
     pub val: &'a str,
 }
+#[cfg(feature = "fmt")]
 impl<'a> fmt::Display for QueryParam<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(format!("{}={}", self.name, self.val).as_str())
@@ -37,8 +39,86 @@ impl<'a> QueryParam<'a> {
         val
     }
   }
+
+  /// The value with its percent-encodings decoded, `1%262` decodes to `1&2`.
+  /// Percent signs not followed by two hex digits are kept as is and decoded bytes that aren't valid UTF-8 are replaced with U+FFFD
+  #[inline]
+  pub fn decoded_val(&self) -> Cow<'a, str> {
+    percent_decode(self.val)
+  }
+
+  /// Parses a bare query such as `a=1&b=2`, without a path or a leading `?`, into the start of `buf` with the same rules as the query of `Url::parse`
+  /// Returns the parsed parameters, an empty slice for an empty query. Returns an Err(UrlError::Query) if the query is malformed or isn't valid UTF-8,
+  /// or an Err(UrlError::TooManyQueryParams) if there is more query parameters than the length of `buf`
+  ///
+  /// ```rust
+  /// use htpp::{QueryParam, EMPTY_QUERY};
+  ///
+  /// let mut queries = [EMPTY_QUERY; 10];
+  /// let params = QueryParam::parse_query(b"a=1&b=2", &mut queries).unwrap();
+  /// assert!(params == [QueryParam::new("a", "1"), QueryParam::new("b", "2")]);
+  /// ```
+  #[inline]
+  pub fn parse_query<'queries>(slice: &'a [u8], buf: &'queries mut [QueryParam<'a>]) -> Result<&'queries [QueryParam<'a>], UrlError> {
+    if str::from_utf8(slice).is_err() {return Err(UrlError::Query);}
+    let count = parse_query_params(slice, buf)?;
+    Ok(&buf[..count])
+  }
+}
+
+#[inline]
+// decodes the `%HH` sequences of the string, only allocating if there is one
+fn percent_decode(encoded: &str) -> Cow<'_, str> {
+  if !encoded.contains('%') {return Cow::Borrowed(encoded);}
+  let decoded: Vec<u8> = PercentDecoded::new(encoded.as_bytes()).collect();
+  Cow::Owned(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+// the bytes of a percent-encoded string with every `%HH` decoded, a `%` not followed by two hex digits is kept as is
+pub(crate) struct PercentDecoded<'a> {
+  bytes: &'a [u8],
+  offset: usize,
+}
+impl<'a> PercentDecoded<'a> {
+  #[inline]
+  pub(crate) fn new(bytes: &'a [u8]) -> Self {
+    Self { bytes, offset: 0 }
+  }
+}
+impl<'a> Iterator for PercentDecoded<'a> {
+  type Item = u8;
+  #[inline]
+  fn next(&mut self) -> Option<u8> {
+    let character = *self.bytes.get(self.offset)?;
+    match percent_byte(self.bytes, self.offset) {
+      Some(byte) => {
+        self.offset += 3;
+        Some(byte)
+      },
+      None => {
+        self.offset += 1;
+        Some(character)
+      },
+    }
+  }
+}
+
+#[inline]
+// the byte encoded by the `%HH` at the offset, `None` if there isn't a `%` followed by two hex digits there
+pub(crate) fn percent_byte(bytes: &[u8], offset: usize) -> Option<u8> {
+  if bytes.get(offset) != Some(&b'%') {return None;}
+  let hex = bytes.get((offset+1)..(offset+3)).filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+  Some(hex_value(hex[0]) << 4 | hex_value(hex[1]))
 }
 
+#[inline]
+fn hex_value(digit: u8) -> u8 {
+  match digit {
+    b'0'..=b'9' => digit - b'0',
+    b'a'..=b'f' => digit - b'a' + 10,
+    _ => digit - b'A' + 10,
+  }
+}
 
 
 
@@ -53,7 +133,27 @@ pub enum UrlError {
   /// An error while parsing the query parameters part of the URL
   Query,
   /// The URL has more query parameters than the length of the buffer passed
-  TooManyQueryParams
+  TooManyQueryParams,
+  /// An error while parsing the scheme or authority part of the URL
+  Authority,
+}
+impl UrlError {
+  /// A stable numeric code for the error, meant for telemetry. See `Error::code` for the table of codes
+  #[inline]
+  pub fn code(&self) -> u16 {
+    match self {
+      Self::Path => 300,
+      Self::Query => 301,
+      Self::Authority => 302,
+      Self::TooManyQueryParams => 401,
+    }
+  }
+
+  /// The category of the error, it matches the hundreds digit of `code`
+  #[inline]
+  pub fn category(&self) -> ErrorCategory {
+    ErrorCategory::from_code(self.code())
+  }
 }
 
 
@@ -68,13 +168,8 @@ pub struct Url<'a, 'queries> {
 
 impl<'a, 'queries> Url<'a, 'queries> {
   /// Construct a new `Url` from its parts.
+  /// An empty path is kept as is like `Url::parse` does for query-only references such as `?a=1`
   pub fn new(path: &'a str, query_params: Option<&'queries [QueryParam<'a>]>) -> Url<'a, 'queries> {
-    if path.is_empty() {
-      return Self {
-        path: "/",
-        query_params,
-      }
-    }
     Self {
       path,
       query_params,
@@ -83,12 +178,18 @@ impl<'a, 'queries> Url<'a, 'queries> {
 
 
   /// Parses the bytes of an HTTP URL into a `Url`
-  /// The URL you parse must be valid UTF-8 and must be stripped of the leading protocol and authority parts or an Err(UrlError::Path) is returned
+  /// The URL you parse must be valid UTF-8 and must be stripped of the leading protocol and authority parts or an Err(UrlError::Path) is returned.
+  /// The path is empty for references holding only a query or fragment such as `?a=1`, `#top`, or an empty slice, and a fragment is left out of the `Url`
   /// If you pass an empty `queries_buf`, it will not parse query parameters
   /// If there is more query parameters than the length of the passed `queries_buf`, an Err(UrlError::TooManyQueryParams) is returned
   #[inline]
   pub fn parse(slice: &'a [u8], queries_buf: &'queries mut [QueryParam<'a>]) -> Result<Url<'a, 'queries>, UrlError> {
     let mut offset = 0;
+    // the fragment is neither part of the path nor of the query
+    let slice = match slice.iter().position(|character| *character == b'#') {
+      Some(end) => &slice[..end],
+      None => slice,
+    };
     let path = parse_path(slice)?;
     offset += path.1;
     if offset == slice.len() || queries_buf.is_empty(){
@@ -97,9 +198,63 @@ impl<'a, 'queries> Url<'a, 'queries> {
     parse_query_params(&slice[offset..], queries_buf)?;
     Ok(Url{path: path.0, query_params: Some(queries_buf)})
   }
+
+  /// The parsed query parameters without the `EMPTY_QUERY` padding at the end of the buffer, an empty slice if there is no query
+  #[inline]
+  pub fn params(&self) -> &'queries [QueryParam<'a>] {
+    let params = self.query_params.unwrap_or_default();
+    let count = params.iter().position(|param| param.name.is_empty()).unwrap_or(params.len());
+    &params[..count]
+  }
+
+  /// An iterator over the `/` separated segments of the path, `/users/5/` yields `users`, `5`, and an empty segment
+  #[inline]
+  pub fn segments(&self) -> impl Iterator<Item = &'a str> {
+    let path = self.path.strip_prefix('/').unwrap_or(self.path);
+    path.split('/')
+  }
+
+  /// Matches the path against a route pattern such as `/users/:id/posts/:pid`
+  /// Segments starting with `:` capture the non-empty path segment at the same position, other segments must be equal.
+  /// Returns the captures as (name, value) pairs in pattern order, or `None` if the path doesn't match
+  #[inline]
+  pub fn matches<'p>(&self, pattern: &'p str) -> Option<Vec<(&'p str, &'a str)>> {
+    let mut captures = Vec::new();
+    let mut segments = self.segments();
+    for expected in pattern.strip_prefix('/').unwrap_or(pattern).split('/') {
+      let segment = segments.next()?;
+      match expected.strip_prefix(':') {
+        Some(name) if !segment.is_empty() => captures.push((name, segment)),
+        None if expected == segment => {},
+        _ => return None,
+      }
+    }
+    if segments.next().is_some() {return None;}
+    Some(captures)
+  }
+
+  /// An `OwnedUrl` with the path and the names and values of the query parameters percent-decoded, it doesn't borrow the parsed buffer.
+  /// Decoded bytes that aren't valid UTF-8 are replaced with U+FFFD like `QueryParam::decoded_val` does
+  #[inline]
+  pub fn to_owned_decoded(&self) -> OwnedUrl {
+    OwnedUrl {
+      path: percent_decode(self.path).into_owned(),
+      params: self.params().iter().map(|param| (percent_decode(param.name).into_owned(), param.decoded_val().into_owned())).collect(),
+    }
+  }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+/// A URL with a percent-decoded path and query parameters that owns its parts, made by `Url::to_owned_decoded`
+pub struct OwnedUrl {
+    /// The decoded path
+    pub path: String,
+    /// The decoded names and values of the query parameters in the order they were sent
+    pub params: Vec<(String, String)>,
+}
 
+
+#[cfg(feature = "fmt")]
 impl<'a, 'queries> fmt::Display for Url<'a, 'queries> {
     /// The string representation of the URL
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -126,7 +281,7 @@ impl<'a, 'queries> fmt::Display for Url<'a, 'queries> {
 
 #[inline]
 fn parse_path(slice: &[u8]) -> Result<(&str, usize), UrlError> {
-  if slice.is_empty() || slice[0] != b'/' {return Err(UrlError::Path);}
+  if slice.first().is_some_and(|character| *character != b'/' && *character != b'?') {return Err(UrlError::Path);}
 
   for (counter, character) in slice.iter().enumerate() {
     if *character == b'?' {
@@ -141,7 +296,8 @@ fn parse_path(slice: &[u8]) -> Result<(&str, usize), UrlError> {
 
 
 #[inline]
-fn parse_query_params<'a>(slice: &'a [u8], queries_buf: &mut [QueryParam<'a>]) -> Result<(), UrlError> {
+// parses the query into the start of the buffer and returns the number of parameters
+fn parse_query_params<'a>(slice: &'a [u8], queries_buf: &mut [QueryParam<'a>]) -> Result<usize, UrlError> {
   let mut offset = 0;
   let mut iteration = 0;
   while offset < slice.len() {
@@ -153,7 +309,7 @@ fn parse_query_params<'a>(slice: &'a [u8], queries_buf: &mut [QueryParam<'a>]) -
     queries_buf[iteration] = QueryParam::new(name.0, val.0);
     iteration += 1;
   };
-  Ok(())
+  Ok(iteration)
 }
 
 
@@ -161,7 +317,7 @@ fn parse_query_params<'a>(slice: &'a [u8], queries_buf: &mut [QueryParam<'a>]) -
 // parses the header name and removes the `:` character and any spaces after it
 fn parse_query_param_name(slice: &[u8]) -> Result<(&str, usize), UrlError> {
   for (counter, character) in slice.iter().enumerate() {
-    if crate::HEADER_NAME_SAFE[*character as usize] {
+    if crate::HEADER_NAME_SAFE.contains(*character) {
       continue;
     } else if *character == b'=' {
       let query_name = &slice[..counter];
@@ -189,13 +345,254 @@ fn parse_query_param_value(slice: &[u8]) -> Result<(&str, usize), UrlError> {
 
 
 
+/// The target of an HTTP request, classified by its form
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum RequestTarget<'a> {
+  /// An absolute path with an optional query such as `/index.html?q=1`, the form used by most requests
+  Origin(&'a str),
+  /// A full URL such as `http://example.com/index.html`, the form used by requests sent to proxies
+  Absolute {
+    /// The scheme of the URL, e.g. `http`
+    scheme: &'a str,
+    /// The host of the URL. IPv6 literals keep their square brackets
+    host: &'a str,
+    /// The port of the URL or `None` if it was omitted
+    port: Option<u16>,
+    /// The path and query of the URL, an empty string if the URL has neither
+    path: &'a str,
+  },
+  /// A host and port such as `example.com:443`, the form used by `CONNECT` requests
+  Authority {
+    /// The host of the target. IPv6 literals keep their square brackets
+    host: &'a str,
+    /// The port of the target
+    port: u16,
+  },
+  /// The `*` target used by server-wide `OPTIONS` requests
+  Asterisk,
+}
+impl<'a> RequestTarget<'a> {
+  /// The form of the target without its parts
+  #[inline]
+  pub fn form(&self) -> TargetForm {
+    match self {
+      Self::Origin(_) => TargetForm::Origin,
+      Self::Absolute { .. } => TargetForm::Absolute,
+      Self::Authority { .. } => TargetForm::Authority,
+      Self::Asterisk => TargetForm::Asterisk,
+    }
+  }
+}
+
+/// The form of a request target, see `RequestTarget` for the target with its parts
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TargetForm {
+  /// An absolute path with an optional query such as `/index.html?q=1`
+  Origin,
+  /// A full URL such as `http://example.com/index.html`
+  Absolute,
+  /// A host and port such as `example.com:443`
+  Authority,
+  /// The `*` target
+  Asterisk,
+}
+
+/// The kind of a URI reference such as the value of a `Location` header, it tells what the reference is resolved against
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum UriRefKind {
+  /// A reference with a scheme such as `https://example.com/a` or `mailto:a@example.com`, it is used as is
+  Absolute,
+  /// A reference starting with `//` such as `//cdn.example.com/x`, it takes the scheme of the base
+  SchemeRelative,
+  /// A reference starting with a single `/` such as `/a/b?c`, it takes the scheme and authority of the base
+  AbsolutePath,
+  /// Any other reference such as `b/c`, `../d`, `?q`, `#top`, or an empty string, it is resolved against the path of the base
+  Relative,
+}
 
+/// Classifies a URI reference by its shape as described in RFC 3986, section 4.1. The reference isn't validated
+///
+/// ```rust
+/// use htpp::uri::{classify_uri_reference, UriRefKind};
+///
+/// assert!(classify_uri_reference("https://example.com/a") == UriRefKind::Absolute);
+/// assert!(classify_uri_reference("//cdn.example.com/x") == UriRefKind::SchemeRelative);
+/// assert!(classify_uri_reference("/login") == UriRefKind::AbsolutePath);
+/// assert!(classify_uri_reference("../up") == UriRefKind::Relative);
+/// ```
+#[inline]
+pub fn classify_uri_reference(reference: &str) -> UriRefKind {
+  if reference.starts_with("//") {return UriRefKind::SchemeRelative;}
+  if reference.starts_with('/') {return UriRefKind::AbsolutePath;}
+  // a scheme ends at the first `:`
+  let scheme = reference.split(':').next().unwrap_or_default();
+  if scheme.len() < reference.len() && is_scheme(scheme.as_bytes()) {UriRefKind::Absolute} else {UriRefKind::Relative}
+}
 
+#[inline]
+// the form of a target from its shape only, it matches the form found by `classify_target` for valid targets
+pub(crate) fn target_form(slice: &[u8]) -> TargetForm {
+  match slice {
+    [b'/', ..] => TargetForm::Origin,
+    b"*" => TargetForm::Asterisk,
+    _ if parse_scheme(slice).is_ok() => TargetForm::Absolute,
+    _ => TargetForm::Authority,
+  }
+}
+
+/// Parses and classifies the target of a request line, the part between the method and the HTTP version
+/// It applies the exact validation `Request::parse` applies to the path of a request, so a target accepted by one is accepted by the other.
+/// Returns an Err(UrlError::Path) if the target contains a character that isn't allowed or an invalid percent-encoding,
+/// and an Err(UrlError::Authority) if the scheme or authority of an absolute-form or authority-form target is invalid
+///
+/// ```rust
+/// use htpp::uri::{parse_request_target, RequestTarget};
+///
+/// let target = parse_request_target(b"http://example.com:8080/index.html").unwrap();
+/// assert!(target == RequestTarget::Absolute { scheme: "http", host: "example.com", port: Some(8080), path: "/index.html" });
+/// ```
+#[inline]
+pub fn parse_request_target(bytes: &[u8]) -> Result<RequestTarget<'_>, UrlError> {
+  if scan_target(bytes)? != bytes.len() {return Err(UrlError::Path);}
+  classify_target(bytes)
+}
+
+#[inline]
+// returns the length of the run of characters allowed in a request target at the start of the slice
+// percent-encodings in the run must be followed by two hex digits
+pub(crate) fn scan_target(slice: &[u8]) -> Result<usize, UrlError> {
+  for (counter, character) in slice.iter().enumerate() {
+    if !URL_SAFE.contains(*character) {
+      return Ok(counter);
+    }
+    if *character == b'%' && !slice.get((counter+1)..(counter+3)).is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+      return Err(UrlError::Path);
+    }
+  }
+  Ok(slice.len())
+}
+
+#[inline]
+// classifies a request target that only holds characters accepted by `scan_target`
+pub(crate) fn classify_target(slice: &[u8]) -> Result<RequestTarget<'_>, UrlError> {
+  match slice.first() {
+    None => return Err(UrlError::Path),
+    //SAFETY: already checked that the input is valid ascii
+    Some(b'/') => return Ok(RequestTarget::Origin(unsafe { std::str::from_utf8_unchecked(slice) })),
+    Some(b'*') if slice.len() == 1 => return Ok(RequestTarget::Asterisk),
+    _ => {},
+  }
+  if let Ok((scheme, read)) = parse_scheme(slice) {
+    let rest = &slice[read..];
+    let authority_end = rest.iter().position(|character| matches!(character, b'/' | b'?')).unwrap_or(rest.len());
+    let (host, port) = parse_authority(&rest[..authority_end])?;
+    //SAFETY: already checked that the input is valid ascii
+    let path = unsafe { std::str::from_utf8_unchecked(&rest[authority_end..]) };
+    return Ok(RequestTarget::Absolute { scheme, host, port, path });
+  }
+  match parse_authority(slice)? {
+    (host, Some(port)) => Ok(RequestTarget::Authority { host, port }),
+    (_, None) => Err(UrlError::Authority),
+  }
+}
 
 
 
+/// The value of an `Origin` header
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Origin<'a> {
+  /// The literal `null` origin sent for privacy-sensitive or opaque contexts
+  Null,
+  /// A serialized origin made of a scheme, a host, and an optional port
+  Tuple {
+    /// The scheme of the origin, e.g. `https`
+    scheme: &'a str,
+    /// The host of the origin. IPv6 literals keep their square brackets
+    host: &'a str,
+    /// The port of the origin or `None` if it was omitted
+    port: Option<u16>,
+  },
+}
+impl<'a> Origin<'a> {
+  /// Parses the value of an `Origin` header
+  /// The value must either be `null` or `scheme://host[:port]` with no path, or an Err(UrlError::Authority) is returned
+  #[inline]
+  pub fn parse(slice: &'a [u8]) -> Result<Origin<'a>, UrlError> {
+    if slice == b"null" {return Ok(Origin::Null);}
+    let (scheme, read) = parse_scheme(slice)?;
+    let (host, port) = parse_authority(&slice[read..])?;
+    Ok(Origin::Tuple { scheme, host, port })
+  }
+}
+#[cfg(feature = "fmt")]
+impl<'a> fmt::Display for Origin<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      match self {
+        Origin::Null => f.write_str("null"),
+        Origin::Tuple { scheme, host, port: Some(port) } => write!(f, "{scheme}://{host}:{port}"),
+        Origin::Tuple { scheme, host, port: None } => write!(f, "{scheme}://{host}"),
+      }
+    }
+}
 
 
+#[inline]
+// whether the slice is a scheme, a letter followed by letters, digits, `+`, `-`, or `.`
+pub(crate) fn is_scheme(slice: &[u8]) -> bool {
+  slice.first().is_some_and(u8::is_ascii_alphabetic)
+    && slice.iter().all(|character| character.is_ascii_alphanumeric() || matches!(character, b'+' | b'-' | b'.'))
+}
 
+#[inline]
+// parses the scheme and removes the `://` after it
+fn parse_scheme(slice: &[u8]) -> Result<(&str, usize), UrlError> {
+  if slice.is_empty() || !slice[0].is_ascii_alphabetic() {return Err(UrlError::Authority);}
+  for (counter, character) in slice.iter().enumerate() {
+    if character.is_ascii_alphanumeric() || matches!(character, b'+' | b'-' | b'.') {
+      continue;
+    } else if slice[counter..].starts_with(b"://") {
+      //SAFETY: already checked that the input is valid ascii
+      return Ok( (unsafe { std::str::from_utf8_unchecked(&slice[..counter]) }, counter+3));
+    }
+    return Err(UrlError::Authority);
+  }
+  Err(UrlError::Authority)
+}
 
+#[inline]
+// parses a `host[:port]` authority that makes up the whole slice
+pub(crate) fn parse_authority(slice: &[u8]) -> Result<(&str, Option<u16>), UrlError> {
+  let ipv6 = slice.first() == Some(&b'[');
+  let host_end = if ipv6 {
+    // the port separator comes after the closing bracket of an IPv6 literal
+    match slice.iter().position(|character| *character == b']') {
+      Some(close) => close+1,
+      None => return Err(UrlError::Authority),
+    }
+  } else {
+    slice.iter().position(|character| *character == b':').unwrap_or(slice.len())
+  };
+  let host = &slice[..host_end];
+  let valid_host = if ipv6 {
+    host.len() > 2 && host[1..(host_end-1)].iter().all(|character| character.is_ascii_hexdigit() || matches!(character, b':' | b'.'))
+  } else {
+    !host.is_empty() && host.iter().all(|character| character.is_ascii_alphanumeric() || matches!(character, b'-' | b'.' | b'_' | b'~'))
+  };
+  if !valid_host {return Err(UrlError::Authority);}
+  let port = match &slice[host_end..] {
+    [] => None,
+    [b':', digits @ ..] => Some(parse_port(digits)?),
+    _ => return Err(UrlError::Authority),
+  };
+  //SAFETY: already checked that the input is valid ascii
+  Ok((unsafe { std::str::from_utf8_unchecked(host) }, port))
+}
 
+#[inline]
+fn parse_port(slice: &[u8]) -> Result<u16, UrlError> {
+  if slice.is_empty() || slice.len() > 5 || !slice.iter().all(u8::is_ascii_digit) {
+    return Err(UrlError::Authority);
+  }
+  let port = slice.iter().fold(0u32, |port, digit| port * 10 + (digit - b'0') as u32);
+  u16::try_from(port).map_err(|_| UrlError::Authority)
+}
@@ -0,0 +1,59 @@
+// Counts heap allocations made by the serializers. This lives in its own test binary because
+// it installs a global allocator, which would count allocations made by every other test too
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use htpp::{Header, Method, Request, Response};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// SAFETY: every call is forwarded to the system allocator unchanged
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::alloc`
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::dealloc`
+        unsafe { System.dealloc(ptr, layout) }
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::realloc`
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// the number of allocations made on this thread while running `f`
+fn allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    let after = ALLOCATIONS.with(Cell::get);
+    drop(result);
+    after - before
+}
+
+#[test]
+fn request_as_bytes_allocates_once() {
+    let headers = [Header::new("Host", b"example.com"), Header::new("Accept", b"*/*")];
+    let req = Request::new(Method::Post, "/index.html?q=1", &headers, b"a body");
+    assert_eq!(allocations(|| req.as_bytes()), 1);
+}
+
+#[test]
+fn response_as_bytes_allocates_once() {
+    let headers = [Header::new("Content-Length", b"6"), Header::new("Connection", b"keep-alive")];
+    let res = Response::new(200, "OK", &headers, b"a body");
+    assert_eq!(allocations(|| res.as_bytes()), 1);
+    let res = Response::new(204, "", &[], b"");
+    assert_eq!(allocations(|| res.as_bytes()), 1);
+}
@@ -0,0 +1,238 @@
+// Runs the message corpus in `tests/vectors` through the parsers. Every vector is a file with the raw bytes of
+// a message and a `.json` sidecar with the expected outcome, so cases can be added without writing Rust:
+//
+//   tests/vectors/request/**/<name>.http   parsed with `Request::parse`
+//   tests/vectors/response/**/<name>.http  parsed with `Response::parse`
+//   tests/vectors/url/**/<name>.url        parsed with `Url::parse`
+//
+// A sidecar holds either the parsed parts or the expected error variant:
+//
+//   {"method": "GET", "path": "/", "headers": [["Host", "example.com"]], "body": ""}
+//   {"status": 200, "reason": "OK", "headers": [], "body": ""}
+//   {"path": "/search", "query": [["q", "1"]]}
+//   {"error": "Malformed"}
+//
+// Every request and response is also parsed with `parse_indices` and rehydrated from a copy of the input, which must
+// give the same result as parsing directly.
+// Header values and bodies are compared as strings, bytes that aren't valid UTF-8 are written as `\xNN`.
+// To add a vector write the message file, run `HTPP_BLESS=1 cargo test --test vectors` to generate the
+// missing sidecars from the current parser, then review the generated sidecar before committing it.
+// Existing sidecars are never overwritten.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use htpp::indices::EMPTY_HEADER_INDICES;
+use htpp::{Header, QueryParam, Request, Response, Url, EMPTY_HEADER, EMPTY_QUERY};
+use serde_json::{json, Map, Value};
+
+const HEADERS: usize = 64;
+const QUERIES: usize = 32;
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Request,
+    Response,
+    Url,
+}
+
+#[test]
+fn vectors() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors");
+    let bless = std::env::var_os("HTPP_BLESS").is_some();
+    let mut failures = Vec::new();
+    let mut count = 0;
+    for (kind, dir) in [(Kind::Request, "request"), (Kind::Response, "response"), (Kind::Url, "url")] {
+        for path in message_files(&root.join(dir)) {
+            count += 1;
+            let name = path.strip_prefix(&root).unwrap_or(&path).display().to_string();
+            let input = fs::read(&path).unwrap_or_else(|err| panic!("{name}: can't read the vector: {err}"));
+            let actual = outcome(kind, &input);
+            if let Some(mismatch) = indices_mismatch(kind, &input) {
+                failures.push(format!("{name}: {mismatch}"));
+            }
+            let sidecar = path.with_extension("json");
+            if !sidecar.exists() {
+                if bless {
+                    fs::write(&sidecar, sidecar_text(&actual)).unwrap_or_else(|err| panic!("{name}: can't write the sidecar: {err}"));
+                    eprintln!("{name}: wrote a sidecar, review it before committing");
+                } else {
+                    failures.push(format!("{name}: no sidecar, review the vector and run with HTPP_BLESS=1 to generate one"));
+                }
+                continue;
+            }
+            let text = fs::read_to_string(&sidecar).unwrap_or_else(|err| panic!("{name}: can't read the sidecar: {err}"));
+            let expected: Value = match serde_json::from_str(&text) {
+                Ok(expected) => expected,
+                Err(err) => {
+                    failures.push(format!("{name}: invalid sidecar: {err}"));
+                    continue;
+                }
+            };
+            let mut mismatches = Vec::new();
+            diff("", &expected, &actual, &mut mismatches);
+            if !mismatches.is_empty() {
+                failures.push(format!("{name}:\n    {}", mismatches.join("\n    ")));
+            }
+        }
+    }
+    assert!(count > 0, "no vectors found in {}", root.display());
+    assert!(failures.is_empty(), "{} of {count} vectors failed\n  {}", failures.len(), failures.join("\n  "));
+}
+
+// all message files below `dir` in a stable order, sidecars are skipped
+fn message_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries {
+        let path = entry.expect("can't read the vectors directory").path();
+        if path.is_dir() {
+            files.extend(message_files(&path));
+        } else if path.extension().is_some_and(|ext| ext != "json") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+// the outcome of parsing the input in the sidecar format
+fn outcome(kind: Kind, input: &[u8]) -> Value {
+    match kind {
+        Kind::Request => {
+            let mut headers = [EMPTY_HEADER; HEADERS];
+            match Request::parse(input, &mut headers) {
+                Ok(req) => json!({
+                    "method": req.method.to_string(),
+                    "path": req.path,
+                    "headers": header_pairs(req.headers),
+                    "body": escape(req.body),
+                }),
+                Err(err) => json!({ "error": variant(&err) }),
+            }
+        }
+        Kind::Response => {
+            let mut headers = [EMPTY_HEADER; HEADERS];
+            match Response::parse(input, &mut headers) {
+                Ok(res) => json!({
+                    "status": res.status,
+                    "reason": res.reason,
+                    "headers": header_pairs(res.headers),
+                    "body": escape(res.body),
+                }),
+                Err(err) => json!({ "error": variant(&err) }),
+            }
+        }
+        Kind::Url => {
+            let mut queries: [QueryParam; QUERIES] = [EMPTY_QUERY; QUERIES];
+            match Url::parse(input, &mut queries) {
+                Ok(url) => json!({
+                    "path": url.path,
+                    "query": url.params().iter().map(|param| json!([param.name, param.val])).collect::<Vec<_>>(),
+                }),
+                Err(err) => json!({ "error": variant(&err) }),
+            }
+        }
+    }
+}
+
+// parsing into indices and rehydrating from a copy of the input must give the same result as parsing directly
+fn indices_mismatch(kind: Kind, input: &[u8]) -> Option<String> {
+    let copy = input.to_vec();
+    let mut header_indices = [EMPTY_HEADER_INDICES; HEADERS];
+    let (mut headers, mut direct_headers) = ([EMPTY_HEADER; HEADERS], [EMPTY_HEADER; HEADERS]);
+    let (rehydrated, direct) = match kind {
+        Kind::Request => (
+            format!("{:?}", Request::parse_indices(input, &mut header_indices).and_then(|indices| indices.request(&copy, &header_indices, &mut headers))),
+            format!("{:?}", Request::parse(input, &mut direct_headers)),
+        ),
+        Kind::Response => (
+            format!("{:?}", Response::parse_indices(input, &mut header_indices).and_then(|indices| indices.response(&copy, &header_indices, &mut headers))),
+            format!("{:?}", Response::parse(input, &mut direct_headers)),
+        ),
+        Kind::Url => return None,
+    };
+    (rehydrated != direct).then(|| format!("rehydrated from indices as {rehydrated}, parsed as {direct}"))
+}
+
+// the outcome with a field per line, so a reviewer reads one header per line too
+fn sidecar_text(outcome: &Value) -> String {
+    let mut text = String::from("{\n");
+    let fields = outcome.as_object().expect("outcomes are objects");
+    for (index, (key, val)) in fields.iter().enumerate() {
+        let val = match val {
+            Value::Array(items) if !items.is_empty() => {
+                let items: Vec<String> = items.iter().map(|item| format!("    {item}")).collect();
+                format!("[\n{}\n  ]", items.join(",\n"))
+            }
+            _ => val.to_string(),
+        };
+        let separator = if index + 1 < fields.len() { "," } else { "" };
+        writeln!(text, "  {}: {val}{separator}", Value::from(key.as_str())).unwrap();
+    }
+    text.push_str("}\n");
+    text
+}
+
+fn header_pairs(headers: &[Header]) -> Vec<Value> {
+    headers.iter().map(|header| json!([header.name, escape(header.val)])).collect()
+}
+
+// the bytes as a string with invalid UTF-8 written as `\xNN`
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        escaped.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            write!(escaped, "\\x{byte:02x}").unwrap();
+        }
+    }
+    escaped
+}
+
+// pushes a line for every difference between the expected and the actual outcome, `at` is the location in the sidecar
+fn diff(at: &str, expected: &Value, actual: &Value, mismatches: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => diff_objects(at, expected, actual, mismatches),
+        (Value::Array(expected), Value::Array(actual)) => {
+            for index in 0..expected.len().max(actual.len()) {
+                let at = format!("{at}[{index}]");
+                match (expected.get(index), actual.get(index)) {
+                    (Some(expected), Some(actual)) => diff(&at, expected, actual, mismatches),
+                    (Some(expected), None) => mismatches.push(format!("{at}: expected {expected}, got nothing")),
+                    (None, Some(actual)) => mismatches.push(format!("{at}: unexpected {actual}")),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if expected != actual => mismatches.push(format!("{}: expected {expected}, got {actual}", if at.is_empty() { "outcome" } else { at })),
+        _ => {}
+    }
+}
+
+fn diff_objects(at: &str, expected: &Map<String, Value>, actual: &Map<String, Value>, mismatches: &mut Vec<String>) {
+    // a success where an error was expected or the other way around, comparing the fields would only add noise
+    if expected.contains_key("error") != actual.contains_key("error") {
+        mismatches.push(format!("expected {}, got {}", Value::Object(expected.clone()), Value::Object(actual.clone())));
+        return;
+    }
+    for (key, expected) in expected {
+        let at = if at.is_empty() { key.clone() } else { format!("{at}.{key}") };
+        match actual.get(key) {
+            Some(actual) => diff(&at, expected, actual, mismatches),
+            None => mismatches.push(format!("{at}: unknown field in the sidecar")),
+        }
+    }
+    for key in actual.keys().filter(|key| !expected.contains_key(*key)) {
+        mismatches.push(format!("{}: missing from the sidecar", if at.is_empty() { key.clone() } else { format!("{at}.{key}") }));
+    }
+}
+
+// the name of the error variant without its fields, the sidecars only record the variant
+fn variant(err: &impl std::fmt::Debug) -> String {
+    let debug = format!("{err:?}");
+    debug.split(' ').next().unwrap_or_default().to_string()
+}